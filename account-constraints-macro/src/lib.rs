@@ -0,0 +1,251 @@
+//! Derive macro for `trezoa-account-constraints`.
+//!
+//! See the documentation of `trezoa-account-constraints` for details.
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+extern crate proc_macro;
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::TokenStream as TokenStream2,
+    quote::{quote, ToTokens},
+    syn::{
+        parse::{Parse, ParseStream},
+        parse_macro_input,
+        punctuated::Punctuated,
+        Attribute, Data, DeriveInput, Error, Expr, ExprArray, Fields, Ident, Result, Token, Type,
+    },
+};
+
+/// A constraint of an `#[account(...)]` attribute, either a flag such as
+/// `signer` or a value such as `owner = ID`.
+struct Constraint {
+    name: Ident,
+    value: Option<Expr>,
+}
+
+impl Parse for Constraint {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let name = input.parse()?;
+        let value = if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { name, value })
+    }
+}
+
+/// The constraints of one account.
+#[derive(Default)]
+struct AccountConstraints {
+    signer: bool,
+    writable: bool,
+    executable: Option<bool>,
+    rent_exempt: bool,
+    owner: Option<Expr>,
+    address: Option<Expr>,
+    seeds: Option<ExprArray>,
+    bump: Option<Expr>,
+    program: Option<Expr>,
+}
+
+impl AccountConstraints {
+    fn from_attributes(attrs: &[Attribute]) -> Result<Self> {
+        let mut constraints = Self::default();
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("account")) {
+            let list =
+                attr.parse_args_with(Punctuated::<Constraint, Token![,]>::parse_terminated)?;
+            for Constraint { name, value } in list {
+                match (name.to_string().as_str(), value) {
+                    ("signer", None) => constraints.signer = true,
+                    ("writable", None) => constraints.writable = true,
+                    ("executable", None) => constraints.executable = Some(true),
+                    ("not_executable", None) => constraints.executable = Some(false),
+                    ("rent_exempt", None) => constraints.rent_exempt = true,
+                    ("owner", Some(value)) => constraints.owner = Some(value),
+                    ("address", Some(value)) => constraints.address = Some(value),
+                    ("seeds", Some(Expr::Array(seeds))) => constraints.seeds = Some(seeds),
+                    ("seeds", Some(value)) => {
+                        return Err(Error::new_spanned(value, "expected an array of seeds"))
+                    }
+                    ("bump", Some(value)) => constraints.bump = Some(value),
+                    ("program", Some(value)) => constraints.program = Some(value),
+                    (
+                        "signer" | "writable" | "executable" | "not_executable" | "rent_exempt",
+                        Some(value),
+                    ) => return Err(Error::new_spanned(value, "unexpected value")),
+                    ("owner" | "address" | "bump" | "program", None) => {
+                        return Err(Error::new_spanned(
+                            &name,
+                            format!("expected `{name} = <value>`"),
+                        ))
+                    }
+                    _ => {
+                        return Err(Error::new_spanned(
+                            &name,
+                            "unknown constraint, expected one of `signer`, `writable`, \
+                             `executable`, `not_executable`, `rent_exempt`, `owner`, `address`, \
+                             `seeds`, `bump` or `program`",
+                        ))
+                    }
+                }
+            }
+        }
+        if constraints.seeds.is_none() {
+            if let Some(value) = constraints.bump.as_ref().or(constraints.program.as_ref()) {
+                return Err(Error::new_spanned(
+                    value,
+                    "`bump` and `program` require `seeds`",
+                ));
+            }
+        }
+        Ok(constraints)
+    }
+
+    /// The checks of the account bound to `name`, in the order they are
+    /// listed in the documentation.
+    fn checks(&self, name: &Ident) -> TokenStream2 {
+        let krate = quote! { ::trezoa_account_constraints };
+        let mut checks = TokenStream2::new();
+        if self.signer {
+            checks.extend(quote! { #krate::check_signer(#name)?; });
+        }
+        if self.writable {
+            checks.extend(quote! { #krate::check_writable(#name)?; });
+        }
+        if let Some(executable) = self.executable {
+            checks.extend(quote! { #krate::check_executable(#name, #executable)?; });
+        }
+        if let Some(address) = &self.address {
+            checks.extend(quote! { #krate::check_address(#name, &(#address))?; });
+        }
+        if let Some(owner) = &self.owner {
+            checks.extend(quote! { #krate::check_owner(#name, &(#owner))?; });
+        }
+        if let Some(seeds) = &self.seeds {
+            let seeds = seeds.elems.iter().map(|seed| {
+                quote! { ::core::convert::AsRef::<[u8]>::as_ref(&(#seed)) }
+            });
+            let program = self
+                .program
+                .as_ref()
+                .map_or_else(|| quote! { crate::ID }, ToTokens::to_token_stream);
+            checks.extend(match &self.bump {
+                Some(bump) => quote! {
+                    #krate::check_program_address(
+                        #name,
+                        &[#(#seeds,)* &[#bump]],
+                        &(#program),
+                    )?;
+                },
+                None => quote! {
+                    #krate::check_canonical_program_address(
+                        #name,
+                        &[#(#seeds),*],
+                        &(#program),
+                    )?;
+                },
+            });
+        }
+        if self.rent_exempt {
+            checks.extend(quote! { #krate::check_rent_exempt(#name)?; });
+        }
+        checks
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let krate = quote! { ::trezoa_account_constraints };
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input, "expected a struct"));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(Error::new_spanned(
+            &input,
+            "expected a struct with named fields",
+        ));
+    };
+
+    // Every field is a reference with the same lifetime to the same account
+    // type, which is the element type of the accounts slice.
+    let mut account_type = None;
+    let mut names = Vec::new();
+    let mut checks = Vec::new();
+    for field in &fields.named {
+        let Type::Reference(reference) = &field.ty else {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "expected a reference to an account, such as `&'a AccountInfo<'info>`",
+            ));
+        };
+        if reference.mutability.is_some() || reference.lifetime.is_none() {
+            return Err(Error::new_spanned(
+                reference,
+                "expected a shared reference with a lifetime",
+            ));
+        }
+        let field_type = (&reference.lifetime, &reference.elem);
+        match account_type {
+            None => account_type = Some(field_type),
+            Some((lifetime, elem)) => {
+                if lifetime != field_type.0
+                    || elem.to_token_stream().to_string()
+                        != field_type.1.to_token_stream().to_string()
+                {
+                    return Err(Error::new_spanned(
+                        reference,
+                        "every account must have the same type as the first one",
+                    ));
+                }
+            }
+        }
+        let name = field.ident.as_ref().expect("named field");
+        checks.push(AccountConstraints::from_attributes(&field.attrs)?.checks(name));
+        names.push(name);
+    }
+    let Some((lifetime, elem)) = account_type else {
+        return Err(Error::new_spanned(&input, "expected at least one account"));
+    };
+
+    let ident = &input.ident;
+    let len = names.len();
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// Number of accounts taken by `try_from_accounts`.
+            pub const LEN: usize = #len;
+
+            /// Take the accounts from the start of `accounts` and check
+            /// their constraints.
+            ///
+            /// Accounts after the first `LEN` are ignored.
+            pub fn try_from_accounts(
+                accounts: &#lifetime [#elem],
+            ) -> ::core::result::Result<Self, #krate::__private::ProgramError> {
+                #[allow(unused_imports)]
+                use #krate::AccountBackend as _;
+                let [#(#names,)* ..] = accounts else {
+                    return ::core::result::Result::Err(
+                        #krate::__private::ProgramError::NotEnoughAccountKeys,
+                    );
+                };
+                #(#checks)*
+                ::core::result::Result::Ok(Self { #(#names),* })
+            }
+        }
+    })
+}
+
+/// Derive `try_from_accounts` for a struct of named accounts, checking the
+/// constraints of their `#[account(...)]` attributes.
+///
+/// See the documentation of `trezoa-account-constraints` for details.
+#[proc_macro_derive(Accounts, attributes(account))]
+pub fn derive_accounts(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
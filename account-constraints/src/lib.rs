@@ -0,0 +1,263 @@
+//! Declarative validation of the accounts passed to an instruction.
+//!
+//! The [`Accounts`] derive macro turns a struct of named accounts into a
+//! `try_from_accounts` function, which takes the accounts from the start of
+//! the instruction's accounts and checks the constraints given in each
+//! field's `#[account(...)]` attribute, returning the matching
+//! [`ProgramError`] for the first failed check. Nothing is allocated.
+//!
+//! Every field is a reference with the same lifetime to the same account
+//! type, which implements [`AccountBackend`]: `AccountInfo` with the
+//! `account-info` feature, or `AccountView` with the `account-view` feature.
+//!
+//! ```ignore
+//! use {
+//!     trezoa_account_constraints::Accounts,
+//!     trezoa_account_info::AccountInfo,
+//!     trezoa_program_error::ProgramResult,
+//! };
+//!
+//! #[derive(Accounts)]
+//! struct Deposit<'a, 'info> {
+//!     #[account(signer, writable)]
+//!     depositor: &'a AccountInfo<'info>,
+//!     #[account(writable, owner = crate::ID, seeds = [b"vault", depositor.key.as_ref()])]
+//!     vault: &'a AccountInfo<'info>,
+//!     #[account(address = trezoa_sdk_ids::system_program::ID, executable)]
+//!     system_program: &'a AccountInfo<'info>,
+//! }
+//!
+//! fn process_deposit(accounts: &[AccountInfo]) -> ProgramResult {
+//!     let Deposit { depositor, vault, .. } = Deposit::try_from_accounts(accounts)?;
+//!     // ...
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Constraints
+//!
+//! The constraints of an account are checked in this order, whatever their
+//! order in the attribute:
+//!
+//! | Constraint | Check | Error |
+//! |---|---|---|
+//! | `signer` | the account signed the transaction | [`MissingRequiredSignature`] |
+//! | `writable` | the account is writable | [`Immutable`] |
+//! | `executable` | the account is a program | [`InvalidAccountData`] |
+//! | `not_executable` | the account is not a program | [`InvalidAccountData`] |
+//! | `address = <expr>` | the account has the address | [`InvalidArgument`] |
+//! | `owner = <expr>` | the account is owned by the program | [`InvalidAccountOwner`] |
+//! | `seeds = [<expr>, ...]` | the account is the program derived address of the seeds | [`InvalidSeeds`] |
+//! | `rent_exempt` | the account holds enough lamports to be rent exempt | [`AccountNotRentExempt`] |
+//!
+//! Seeds are expressions that implement `AsRef<[u8]>`, and may refer to the
+//! other accounts by their field names. The program derived address is
+//! derived from `crate::ID` unless `program = <expr>` is given. With
+//! `bump = <expr>`, the bump seed is appended to the seeds; otherwise the
+//! canonical bump is searched for, which costs much more compute. Checking
+//! seeds off-chain requires the `curve25519` feature, and `rent_exempt`
+//! requires the `rent` feature.
+//!
+//! An instruction with fewer accounts than the struct fails with
+//! [`NotEnoughAccountKeys`], and accounts after those of the struct are
+//! ignored.
+//!
+//! [`MissingRequiredSignature`]: ProgramError::MissingRequiredSignature
+//! [`Immutable`]: ProgramError::Immutable
+//! [`InvalidAccountData`]: ProgramError::InvalidAccountData
+//! [`InvalidArgument`]: ProgramError::InvalidArgument
+//! [`InvalidAccountOwner`]: ProgramError::InvalidAccountOwner
+//! [`InvalidSeeds`]: ProgramError::InvalidSeeds
+//! [`AccountNotRentExempt`]: ProgramError::AccountNotRentExempt
+//! [`NotEnoughAccountKeys`]: ProgramError::NotEnoughAccountKeys
+#![no_std]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+#[cfg(feature = "macro")]
+pub use trezoa_account_constraints_macro::Accounts;
+use {
+    trezoa_address::Address,
+    trezoa_program_error::{ProgramError, ProgramResult},
+};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use trezoa_program_error::ProgramError;
+}
+
+/// The fields of an account checked by the constraints.
+pub trait AccountBackend {
+    /// Address of the account.
+    fn address(&self) -> &Address;
+
+    /// Whether the transaction was signed by this account.
+    fn is_signer(&self) -> bool;
+
+    /// Whether the account is writable.
+    fn is_writable(&self) -> bool;
+
+    /// Whether the account is a program.
+    fn executable(&self) -> bool;
+
+    /// Whether the account is owned by `program`.
+    fn owned_by(&self, program: &Address) -> bool;
+
+    /// The lamports in the account.
+    fn lamports(&self) -> u64;
+
+    /// The length of the account data.
+    fn data_len(&self) -> usize;
+}
+
+#[cfg(feature = "account-info")]
+impl AccountBackend for trezoa_account_info::AccountInfo<'_> {
+    fn address(&self) -> &Address {
+        self.key
+    }
+
+    fn is_signer(&self) -> bool {
+        self.is_signer
+    }
+
+    fn is_writable(&self) -> bool {
+        self.is_writable
+    }
+
+    fn executable(&self) -> bool {
+        self.executable
+    }
+
+    fn owned_by(&self, program: &Address) -> bool {
+        self.owner == program
+    }
+
+    fn lamports(&self) -> u64 {
+        trezoa_account_info::AccountInfo::lamports(self)
+    }
+
+    fn data_len(&self) -> usize {
+        trezoa_account_info::AccountInfo::data_len(self)
+    }
+}
+
+#[cfg(feature = "account-view")]
+impl AccountBackend for trezoa_account_view::AccountView {
+    fn address(&self) -> &Address {
+        trezoa_account_view::AccountView::address(self)
+    }
+
+    fn is_signer(&self) -> bool {
+        trezoa_account_view::AccountView::is_signer(self)
+    }
+
+    fn is_writable(&self) -> bool {
+        trezoa_account_view::AccountView::is_writable(self)
+    }
+
+    fn executable(&self) -> bool {
+        trezoa_account_view::AccountView::executable(self)
+    }
+
+    fn owned_by(&self, program: &Address) -> bool {
+        trezoa_account_view::AccountView::owned_by(self, program)
+    }
+
+    fn lamports(&self) -> u64 {
+        trezoa_account_view::AccountView::lamports(self)
+    }
+
+    fn data_len(&self) -> usize {
+        trezoa_account_view::AccountView::data_len(self)
+    }
+}
+
+/// Check that the account signed the transaction.
+#[inline]
+pub fn check_signer<A: AccountBackend + ?Sized>(account: &A) -> ProgramResult {
+    if !account.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
+/// Check that the account is writable.
+#[inline]
+pub fn check_writable<A: AccountBackend + ?Sized>(account: &A) -> ProgramResult {
+    if !account.is_writable() {
+        return Err(ProgramError::Immutable);
+    }
+    Ok(())
+}
+
+/// Check whether the account is a program.
+#[inline]
+pub fn check_executable<A: AccountBackend + ?Sized>(
+    account: &A,
+    executable: bool,
+) -> ProgramResult {
+    if account.executable() != executable {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// Check that the account has the address `address`.
+#[inline]
+pub fn check_address<A: AccountBackend + ?Sized>(account: &A, address: &Address) -> ProgramResult {
+    if account.address() != address {
+        return Err(ProgramError::InvalidArgument);
+    }
+    Ok(())
+}
+
+/// Check that the account is owned by `program`.
+#[inline]
+pub fn check_owner<A: AccountBackend + ?Sized>(account: &A, program: &Address) -> ProgramResult {
+    if !account.owned_by(program) {
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    Ok(())
+}
+
+/// Check that the account is the program derived address of `seeds`, which
+/// include the bump seed.
+#[cfg(any(target_os = "trezoa", target_arch = "bpf", feature = "curve25519"))]
+#[inline]
+pub fn check_program_address<A: AccountBackend + ?Sized>(
+    account: &A,
+    seeds: &[&[u8]],
+    program_id: &Address,
+) -> ProgramResult {
+    match Address::create_program_address(seeds, program_id) {
+        Ok(address) if &address == account.address() => Ok(()),
+        _ => Err(ProgramError::InvalidSeeds),
+    }
+}
+
+/// Check that the account is the program derived address of `seeds` with
+/// the canonical bump seed.
+#[cfg(any(target_os = "trezoa", target_arch = "bpf", feature = "curve25519"))]
+#[inline]
+pub fn check_canonical_program_address<A: AccountBackend + ?Sized>(
+    account: &A,
+    seeds: &[&[u8]],
+    program_id: &Address,
+) -> ProgramResult {
+    match Address::try_find_program_address(seeds, program_id) {
+        Some((address, _bump)) if &address == account.address() => Ok(()),
+        _ => Err(ProgramError::InvalidSeeds),
+    }
+}
+
+/// Check that the account holds enough lamports for its data to be rent
+/// exempt, according to the rent sysvar.
+#[cfg(feature = "rent")]
+#[inline]
+pub fn check_rent_exempt<A: AccountBackend + ?Sized>(account: &A) -> ProgramResult {
+    use trezoa_sysvar::{rent::Rent, Sysvar};
+
+    if !Rent::get()?.is_exempt(account.lamports(), account.data_len()) {
+        return Err(ProgramError::AccountNotRentExempt);
+    }
+    Ok(())
+}
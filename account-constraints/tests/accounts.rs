@@ -0,0 +1,178 @@
+use {
+    trezoa_account_constraints::Accounts,
+    trezoa_account_info::AccountInfo,
+    trezoa_account_view::{test_utils::AccountBuffer, AccountView, RuntimeAccount},
+    trezoa_address::Address,
+    trezoa_program_error::ProgramError,
+    std::ptr,
+};
+
+pub const ID: Address = Address::new_from_array([7; 32]);
+const OTHER_PROGRAM: Address = Address::new_from_array([8; 32]);
+
+#[derive(Accounts)]
+struct Deposit<'a, 'info> {
+    #[account(signer, writable)]
+    depositor: &'a AccountInfo<'info>,
+    #[account(writable, owner = ID, seeds = [b"vault", depositor.key.as_ref()])]
+    vault: &'a AccountInfo<'info>,
+    #[account(address = OTHER_PROGRAM, executable)]
+    program: &'a AccountInfo<'info>,
+}
+
+#[test]
+fn test_account_info() {
+    let depositor_key = Address::new_from_array([1; 32]);
+    let (vault_key, _) = Address::find_program_address(&[b"vault", depositor_key.as_ref()], &ID);
+    let system_program = Address::default();
+    let (mut lamports, mut vault_lamports, mut program_lamports) = (0, 0, 0);
+    let (mut data, mut vault_data, mut program_data) = ([0u8; 0], [0u8; 8], [0u8; 0]);
+    let depositor = AccountInfo::new(
+        &depositor_key,
+        true,
+        true,
+        &mut lamports,
+        &mut data,
+        &system_program,
+        false,
+    );
+    let vault = AccountInfo::new(
+        &vault_key,
+        false,
+        true,
+        &mut vault_lamports,
+        &mut vault_data,
+        &ID,
+        false,
+    );
+    let program = AccountInfo::new(
+        &OTHER_PROGRAM,
+        false,
+        false,
+        &mut program_lamports,
+        &mut program_data,
+        &system_program,
+        true,
+    );
+    let error = |accounts: &[AccountInfo]| Deposit::try_from_accounts(accounts).err();
+
+    let accounts = [
+        depositor.clone(),
+        vault.clone(),
+        program.clone(),
+        vault.clone(),
+    ];
+    let deposit = Deposit::try_from_accounts(&accounts).unwrap();
+    assert!(ptr::eq(deposit.depositor, &accounts[0]));
+    assert!(ptr::eq(deposit.vault, &accounts[1]));
+    assert!(ptr::eq(deposit.program, &accounts[2]));
+    assert_eq!(Deposit::LEN, 3);
+
+    assert_eq!(
+        error(&accounts[..2]),
+        Some(ProgramError::NotEnoughAccountKeys)
+    );
+
+    let mut not_signer = depositor.clone();
+    not_signer.is_signer = false;
+    assert_eq!(
+        error(&[not_signer, vault.clone(), program.clone()]),
+        Some(ProgramError::MissingRequiredSignature)
+    );
+
+    let mut read_only = vault.clone();
+    read_only.is_writable = false;
+    assert_eq!(
+        error(&[depositor.clone(), read_only, program.clone()]),
+        Some(ProgramError::Immutable)
+    );
+
+    let mut wrong_owner = vault.clone();
+    wrong_owner.owner = &system_program;
+    assert_eq!(
+        error(&[depositor.clone(), wrong_owner, program.clone()]),
+        Some(ProgramError::InvalidAccountOwner)
+    );
+
+    // the vault is derived from the depositor
+    let mut other_vault = vault.clone();
+    other_vault.key = &depositor_key;
+    assert_eq!(
+        error(&[depositor.clone(), other_vault, program.clone()]),
+        Some(ProgramError::InvalidSeeds)
+    );
+
+    let mut not_executable = program.clone();
+    not_executable.executable = false;
+    assert_eq!(
+        error(&[depositor.clone(), vault.clone(), not_executable]),
+        Some(ProgramError::InvalidAccountData)
+    );
+
+    let mut wrong_program = program.clone();
+    wrong_program.key = &system_program;
+    assert_eq!(
+        error(&[depositor, vault, wrong_program]),
+        Some(ProgramError::InvalidArgument)
+    );
+}
+
+fn state_bump(authority: &Address) -> u8 {
+    Address::find_program_address(&[b"state", authority.as_ref()], &OTHER_PROGRAM).1
+}
+
+#[derive(Accounts)]
+struct Close<'a> {
+    #[account(signer)]
+    authority: &'a AccountView,
+    #[account(
+        writable,
+        not_executable,
+        owner = OTHER_PROGRAM,
+        seeds = [b"state", authority.address()],
+        bump = state_bump(authority.address()),
+        program = OTHER_PROGRAM,
+    )]
+    state: &'a AccountView,
+}
+
+fn account_buffer(address: Address, owner: Address, is_signer: bool) -> AccountBuffer<0> {
+    AccountBuffer::new(RuntimeAccount {
+        is_signer: is_signer.into(),
+        is_writable: 1,
+        address,
+        owner,
+        ..RuntimeAccount::default()
+    })
+}
+
+#[test]
+fn test_account_view() {
+    let authority_key = Address::new_from_array([1; 32]);
+    let (state_key, _) =
+        Address::find_program_address(&[b"state", authority_key.as_ref()], &OTHER_PROGRAM);
+    let mut authority_buffer = account_buffer(authority_key, Address::default(), true);
+    let mut state_buffer = account_buffer(state_key, OTHER_PROGRAM, false);
+    let mut other_buffer = account_buffer(Address::new_from_array([2; 32]), OTHER_PROGRAM, false);
+    let (authority, state, other) = unsafe {
+        (
+            authority_buffer.view(),
+            state_buffer.view(),
+            other_buffer.view(),
+        )
+    };
+
+    let accounts = [authority.clone(), state.clone()];
+    let close = Close::try_from_accounts(&accounts).unwrap();
+    assert_eq!(close.authority, &authority);
+    assert_eq!(close.state, &state);
+
+    assert_eq!(
+        Close::try_from_accounts(&[authority.clone(), other]).err(),
+        Some(ProgramError::InvalidSeeds)
+    );
+    assert_eq!(
+        Close::try_from_accounts(&[state.clone(), authority]).err(),
+        Some(ProgramError::MissingRequiredSignature)
+    );
+}
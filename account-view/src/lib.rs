@@ -16,6 +16,9 @@ use {
     trezoa_program_error::{ProgramError, ProgramResult},
 };
 
+#[cfg(feature = "dev-context-only-utils")]
+pub mod test_utils;
+
 /// Maximum number of bytes a program may add to an account during a
 /// single top-level instruction.
 pub const MAX_PERMITTED_DATA_INCREASE: usize = 1_024 * 10;
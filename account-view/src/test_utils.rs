@@ -0,0 +1,38 @@
+//! Helpers for building [`AccountView`]s over local memory in tests.
+
+use crate::{AccountView, RuntimeAccount, NOT_BORROWED};
+
+/// A [`RuntimeAccount`] followed by `DATA_LEN` bytes of zeroed account data,
+/// laid out as the runtime serializes an account.
+///
+/// The data is aligned to 8 bytes, like the account data of the runtime.
+#[repr(C)]
+pub struct AccountBuffer<const DATA_LEN: usize> {
+    account: RuntimeAccount,
+    data: [u8; DATA_LEN],
+}
+
+impl<const DATA_LEN: usize> AccountBuffer<DATA_LEN> {
+    /// Creates a buffer for `account`, which is marked as not borrowed and
+    /// given a data length of `DATA_LEN`.
+    pub fn new(account: RuntimeAccount) -> Self {
+        Self {
+            account: RuntimeAccount {
+                borrow_state: NOT_BORROWED,
+                data_len: DATA_LEN as u64,
+                ..account
+            },
+            data: [0; DATA_LEN],
+        }
+    }
+
+    /// Returns a view of the account.
+    ///
+    /// # Safety
+    ///
+    /// The view, and every view cloned from it, must not be used after the
+    /// buffer is moved or dropped.
+    pub unsafe fn view(&mut self) -> AccountView {
+        AccountView::new_unchecked(&mut self.account)
+    }
+}
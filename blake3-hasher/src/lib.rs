@@ -6,31 +6,13 @@
 
 pub use trezoa_hash::{Hash, ParseHashError, HASH_BYTES, MAX_BASE58_LEN};
 
-#[derive(Clone, Default)]
-#[cfg(all(
+trezoa_hash::define_hasher! {
     feature = "blake3",
-    not(any(target_os = "trezoa", target_arch = "bpf"))
-))]
-pub struct Hasher {
-    hasher: blake3::Hasher,
-}
-
-#[cfg(all(
-    feature = "blake3",
-    not(any(target_os = "trezoa", target_arch = "bpf"))
-))]
-impl Hasher {
-    pub fn hash(&mut self, val: &[u8]) {
-        self.hasher.update(val);
-    }
-    pub fn hashv(&mut self, vals: &[&[u8]]) {
-        for val in vals {
-            self.hash(val);
-        }
-    }
-    pub fn result(self) -> Hash {
-        Hash::new_from_array(*self.hasher.finalize().as_bytes())
-    }
+    syscall = "sol_blake3",
+    hasher = blake3::Hasher,
+    hashv = hashv,
+    update = |hasher, val| hasher.update(val),
+    finalize = |hasher| *hasher.finalize().as_bytes(),
 }
 
 /// Return a Blake3 hash for the given data.
@@ -77,7 +59,7 @@ pub fn hash(val: &[u8]) -> Hash {
 #[cfg(test)]
 #[cfg(feature = "blake3")]
 mod tests {
-    use super::*;
+    use {super::*, trezoa_hash::hasher::Hasher as _};
 
     #[test]
     fn test_hashv() {
@@ -90,4 +72,19 @@ mod tests {
         let hash_ext = [&val_hash.to_bytes(), ext.as_bytes()].concat();
         assert!(ext_hash == hash(&hash_ext));
     }
+
+    #[test]
+    fn test_hasher_known_answer() {
+        let expected = Hash::new_from_array([
+            0x64, 0x37, 0xb3, 0xac, 0x38, 0x46, 0x51, 0x33, 0xff, 0xb6, 0x3b, 0x75, 0x27, 0x3a,
+            0x8d, 0xb5, 0x48, 0xc5, 0x58, 0x46, 0x5d, 0x79, 0xdb, 0x03, 0xfd, 0x35, 0x9c, 0x6c,
+            0xd5, 0xbd, 0x9d, 0x85,
+        ]);
+        assert_eq!(hash(b"abc"), expected);
+
+        let mut hasher = Hasher::default();
+        hasher.hashv(&[b"a", b"bc"]);
+        assert_eq!(hasher.result(), expected);
+        assert_eq!(Hasher::hashv_once(&[b"a", b"bc"]), expected);
+    }
 }
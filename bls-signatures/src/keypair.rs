@@ -44,6 +44,16 @@ impl Keypair {
         Ok(Self { secret, public })
     }
 
+    /// Derive a `Keypair` from a seed and a path of child indexes using
+    /// EIP-2333 hierarchical derivation
+    ///
+    /// See [`SecretKey::derive_eip2333`].
+    pub fn derive_eip2333(seed: &[u8], path: &[u32]) -> Result<Self, BlsError> {
+        let secret = SecretKey::derive_eip2333(seed, path)?;
+        let public = PubkeyProjective::from_secret(&secret).into();
+        Ok(Self { secret, public })
+    }
+
     /// Derive a `BlsSecretKey` from a Trezoa signer
     #[cfg(feature = "trezoa-signer-derive")]
     pub fn derive_from_signer(signer: &dyn Signer, public_seed: &[u8]) -> Result<Self, BlsError> {
@@ -156,7 +166,7 @@ impl Keypair {
 
 #[cfg(test)]
 mod tests {
-    use {super::*, tempfile::NamedTempFile};
+    use {super::*, blstrs::Scalar, ff::PrimeField, tempfile::NamedTempFile};
 
     #[test]
     fn test_keygen_derive() {
@@ -168,6 +178,48 @@ mod tests {
         assert_eq!(keypair.public, public);
     }
 
+    #[test]
+    fn test_derive_eip2333() {
+        // Test cases 0 and 1 from EIP-2333
+        let test_cases = [
+            (
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+                "6083874454709270928345386274498605044986640685124978867557563392430687146096",
+                0,
+                "20397789859736650942317412262472558107875392172444076792671091975210932703118",
+            ),
+            (
+                "3141592653589793238462643383279502884197169399375105820974944592",
+                "29757020647961307431480504535336562678282505419141012933316116377660817309383",
+                3141592653,
+                "25457201688850691947727629385191704516744796114925897962676248250929345014287",
+            ),
+        ];
+        for (seed, master_secret, child_index, child_secret) in test_cases {
+            let seed: Vec<u8> = (0..seed.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&seed[i..i.saturating_add(2)], 16).unwrap())
+                .collect();
+            let master = SecretKey::derive_eip2333(&seed, &[]).unwrap();
+            assert_eq!(master.0, Scalar::from_str_vartime(master_secret).unwrap());
+            let child = SecretKey::derive_eip2333(&seed, &[child_index]).unwrap();
+            assert_eq!(child.0, Scalar::from_str_vartime(child_secret).unwrap());
+        }
+
+        let seed = [7u8; 64];
+        let keypair = Keypair::derive_eip2333(&seed, &[12381, 3600, 0, 0, 0]).unwrap();
+        let public: PubkeyAffine = PubkeyProjective::from_secret(&keypair.secret).into();
+        assert_eq!(keypair.public, public);
+        assert_ne!(
+            keypair,
+            Keypair::derive_eip2333(&seed, &[12381, 3600, 1, 0, 0]).unwrap()
+        );
+        assert_eq!(
+            Keypair::derive_eip2333(&seed[..31], &[]),
+            Err(BlsError::KeyDerivation)
+        );
+    }
+
     #[test]
     #[cfg(feature = "trezoa-signer-derive")]
     fn test_keygen_derive_from_signer() {
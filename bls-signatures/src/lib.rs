@@ -14,7 +14,7 @@ pub use crate::{
         AsProofOfPossessionProjective, ProofOfPossessionProjective, VerifiableProofOfPossession,
     },
     pubkey::{AsPubkeyProjective, PubkeyProjective, VerifiablePubkey},
-    secret_key::{SecretKey, BLS_SECRET_KEY_SIZE},
+    secret_key::{SecretKey, BLS_SECRET_KEY_SIZE, EIP2333_MIN_SEED_SIZE},
     signature::{AsSignatureProjective, SignatureProjective, VerifiableSignature},
 };
 pub use crate::{
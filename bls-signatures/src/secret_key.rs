@@ -6,7 +6,7 @@ use {
         pubkey::PubkeyProjective,
         signature::SignatureProjective,
     },
-    blst::{blst_keygen, blst_scalar},
+    blst::{blst_derive_child_eip2333, blst_derive_master_eip2333, blst_keygen, blst_scalar},
     blstrs::Scalar,
     core::ptr,
     ff::Field,
@@ -18,6 +18,9 @@ use {trezoa_signature::Signature, trezoa_signer::Signer, subtle::ConstantTimeEq}
 /// Size of BLS secret key in bytes
 pub const BLS_SECRET_KEY_SIZE: usize = 32;
 
+/// Minimum size in bytes of the seed of an EIP-2333 key tree
+pub const EIP2333_MIN_SEED_SIZE: usize = 32;
+
 /// A BLS secret key
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct SecretKey(pub(crate) Scalar);
@@ -48,6 +51,40 @@ impl SecretKey {
             .map_err(|_| BlsError::FieldDecode)
     }
 
+    /// Derive a `BlsSecretKey` from a seed and a path of child indexes using
+    /// [EIP-2333] hierarchical derivation
+    ///
+    /// The seed is typically derived from a mnemonic and must be at least
+    /// [`EIP2333_MIN_SEED_SIZE`] bytes long. Validator signing keys use the
+    /// [EIP-2334] path `m/12381/3600/i/0/0`, given here as
+    /// `&[12381, 3600, i, 0, 0]`.
+    ///
+    /// [EIP-2333]: https://eips.ethereum.org/EIPS/eip-2333
+    /// [EIP-2334]: https://eips.ethereum.org/EIPS/eip-2334
+    pub fn derive_eip2333(seed: &[u8], path: &[u32]) -> Result<Self, BlsError> {
+        if seed.len() < EIP2333_MIN_SEED_SIZE {
+            return Err(BlsError::KeyDerivation);
+        }
+        let mut scalar = blst_scalar::default();
+        unsafe {
+            blst_derive_master_eip2333(&mut scalar as *mut blst_scalar, seed.as_ptr(), seed.len());
+        }
+        for child_index in path {
+            let parent = scalar;
+            unsafe {
+                blst_derive_child_eip2333(
+                    &mut scalar as *mut blst_scalar,
+                    &parent as *const blst_scalar,
+                    *child_index,
+                );
+            }
+        }
+        scalar
+            .try_into()
+            .map(Self)
+            .map_err(|_| BlsError::FieldDecode)
+    }
+
     /// Derive a `BlsSecretKey` from a Trezoa signer
     #[cfg(feature = "trezoa-signer-derive")]
     pub fn derive_from_signer(signer: &dyn Signer, public_seed: &[u8]) -> Result<Self, BlsError> {
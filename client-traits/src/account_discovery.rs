@@ -0,0 +1,166 @@
+//! Gap-limit discovery of the BIP-44 accounts derived from a seed.
+//!
+//! Wallets derive one account per hardened index, `m/44'/501'/i'/0'` or
+//! `m/44'/501'/i'`, and only ever use the next unused one. To recover the
+//! accounts of a seed, [`discover_bip44_accounts`] derives them in order and
+//! asks a [`SyncClient`] for their balance, stopping once `gap_limit`
+//! consecutive accounts have none.
+
+use {
+    crate::SyncClient,
+    trezoa_derivation_path::DerivationPath,
+    trezoa_keypair::{seed_derivable::keypairs_from_seed_and_derivation_paths, Keypair},
+    trezoa_signer::Signer,
+    trezoa_transaction_error::TransportError,
+    std::{error, fmt},
+};
+
+/// Number of consecutive unused accounts after which discovery stops, as
+/// recommended by BIP-44.
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// An account with a non-zero balance found by [`discover_bip44_accounts`].
+#[derive(Debug)]
+pub struct DiscoveredAccount {
+    pub derivation_path: DerivationPath,
+    pub keypair: Keypair,
+    pub balance: u64,
+}
+
+#[derive(Debug)]
+pub enum AccountDiscoveryError {
+    /// A keypair could not be derived from the seed.
+    Derivation(Box<dyn error::Error>),
+    /// The client failed to fetch a balance.
+    Transport(TransportError),
+}
+
+impl error::Error for AccountDiscoveryError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Derivation(e) => Some(e.as_ref()),
+            Self::Transport(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for AccountDiscoveryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Derivation(e) => write!(f, "failed to derive keypair: {e}"),
+            Self::Transport(e) => write!(f, "failed to fetch balance: {e}"),
+        }
+    }
+}
+
+impl From<TransportError> for AccountDiscoveryError {
+    fn from(e: TransportError) -> Self {
+        Self::Transport(e)
+    }
+}
+
+/// Returns the accounts `m/44'/501'/i'/change'`, or `m/44'/501'/i'` if
+/// `change` is `None`, that hold a balance, scanning `i` upwards from zero
+/// until `gap_limit` consecutive accounts are found empty.
+///
+/// The balance of every scanned account is fetched from `client`, so a
+/// `gap_limit` of [`DEFAULT_GAP_LIMIT`] costs at least 20 requests.
+pub fn discover_bip44_accounts<C: SyncClient + ?Sized>(
+    client: &C,
+    seed: &[u8],
+    change: Option<u32>,
+    gap_limit: u32,
+) -> Result<Vec<DiscoveredAccount>, AccountDiscoveryError> {
+    let derivation_paths = DerivationPath::new_bip44_accounts(0..u32::MAX, change);
+    let keypairs = keypairs_from_seed_and_derivation_paths(seed, derivation_paths)
+        .map_err(AccountDiscoveryError::Derivation)?;
+
+    let mut discovered = vec![];
+    let mut num_unused = 0u32;
+    for derived in keypairs {
+        if num_unused >= gap_limit {
+            break;
+        }
+        let (derivation_path, keypair) = derived.map_err(AccountDiscoveryError::Derivation)?;
+        let balance = client.get_balance(&keypair.pubkey())?;
+        if balance == 0 {
+            num_unused = num_unused.saturating_add(1);
+        } else {
+            num_unused = 0;
+            discovered.push(DiscoveredAccount {
+                derivation_path,
+                keypair,
+                balance,
+            });
+        }
+    }
+    Ok(discovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::BankLikeClient,
+        trezoa_keypair::seed_derivable::keypair_from_seed_and_derivation_path,
+    };
+
+    const SEED: [u8; 64] = [5; 64];
+
+    fn fund(client: &BankLikeClient, account: u32, change: Option<u32>, lamports: u64) {
+        let derivation_path = DerivationPath::new_bip44(Some(account), change);
+        let keypair = keypair_from_seed_and_derivation_path(&SEED, Some(derivation_path)).unwrap();
+        client.airdrop(&keypair.pubkey(), lamports);
+    }
+
+    fn discovered_accounts(discovered: &[DiscoveredAccount]) -> Vec<(u32, u64)> {
+        discovered
+            .iter()
+            .map(|account| {
+                let index = account.derivation_path.account().unwrap().to_u32();
+                (index, account.balance)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_discover_bip44_accounts_stops_at_gap() {
+        let client = BankLikeClient::new();
+        fund(&client, 0, Some(0), 10);
+        fund(&client, 2, Some(0), 20);
+        fund(&client, 5, Some(0), 30);
+
+        let discovered = discover_bip44_accounts(&client, &SEED, Some(0), 2).unwrap();
+        assert_eq!(discovered_accounts(&discovered), vec![(0, 10), (2, 20)]);
+
+        let discovered = discover_bip44_accounts(&client, &SEED, Some(0), 3).unwrap();
+        assert_eq!(
+            discovered_accounts(&discovered),
+            vec![(0, 10), (2, 20), (5, 30)]
+        );
+        let expected = keypair_from_seed_and_derivation_path(
+            &SEED,
+            Some(DerivationPath::new_bip44(Some(5), Some(0))),
+        )
+        .unwrap();
+        assert_eq!(discovered[2].keypair.pubkey(), expected.pubkey());
+
+        assert!(discover_bip44_accounts(&client, &SEED, Some(0), 0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_discover_bip44_accounts_without_change() {
+        let client = BankLikeClient::new();
+        fund(&client, 0, Some(0), 10);
+        fund(&client, 1, None, 20);
+
+        let discovered = discover_bip44_accounts(&client, &SEED, None, DEFAULT_GAP_LIMIT).unwrap();
+        assert_eq!(discovered_accounts(&discovered), vec![(1, 20)]);
+        assert_eq!(discovered[0].derivation_path.change(), None);
+
+        let discovered =
+            discover_bip44_accounts(&client, &SEED, Some(0), DEFAULT_GAP_LIMIT).unwrap();
+        assert_eq!(discovered_accounts(&discovered), vec![(0, 10)]);
+    }
+}
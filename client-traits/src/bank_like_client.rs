@@ -0,0 +1,1076 @@
+//! An in-memory implementation of [`SyncClient`] and [`AsyncClient`].
+//!
+//! [`BankLikeClient`] keeps a map of [`Account`]s, executes System program
+//! instructions against it, and tracks recent blockhashes and signature
+//! statuses the way a bank would. It is intended for unit testing code that
+//! is written against the client traits without standing up a validator.
+//!
+//! The client produces one slot at a time. Transactions are processed in the
+//! current slot, and [`BankLikeClient::advance_slot`] moves the client to the
+//! next slot, registering a new blockhash. A signature status is visible at
+//! [`CommitmentLevel::Processed`] as soon as its transaction is processed, at
+//! [`CommitmentLevel::Confirmed`] once [`CONFIRMATION_DEPTH`] slots have been
+//! produced on top of it and at [`CommitmentLevel::Finalized`] once
+//! [`FINALIZATION_DEPTH`] slots have been produced on top of it.
+//!
+//! Only the System program is executed. Instructions for any other program
+//! fail with [`InstructionError::UnsupportedProgramId`], and the nonce
+//! instructions fail with [`InstructionError::InvalidInstructionData`].
+//!
+//! [`CommitmentLevel::Processed`]: trezoa_commitment_config::CommitmentLevel::Processed
+//! [`CommitmentLevel::Confirmed`]: trezoa_commitment_config::CommitmentLevel::Confirmed
+//! [`CommitmentLevel::Finalized`]: trezoa_commitment_config::CommitmentLevel::Finalized
+
+use {
+    crate::{AsyncClient, SyncClient},
+    trezoa_account::Account,
+    trezoa_clock::{Slot, DEFAULT_SLOTS_PER_EPOCH, MAX_PROCESSING_AGE},
+    trezoa_commitment_config::{CommitmentConfig, CommitmentLevel},
+    trezoa_epoch_info::EpochInfo,
+    trezoa_hash::Hash,
+    trezoa_instruction::Instruction,
+    trezoa_instruction_error::InstructionError,
+    trezoa_keypair::Keypair,
+    trezoa_message::{compiled_instruction::CompiledInstruction, Message, VersionedMessage},
+    trezoa_pubkey::Pubkey,
+    trezoa_rent::Rent,
+    trezoa_sdk_ids::system_program,
+    trezoa_signature::Signature,
+    trezoa_signer::{signers::Signers, Signer},
+    trezoa_system_interface::{
+        error::SystemError, instruction::SystemInstruction, MAX_PERMITTED_DATA_LENGTH,
+    },
+    trezoa_transaction::{versioned::VersionedTransaction, Transaction},
+    trezoa_transaction_error::{
+        TransactionError, TransactionResult, TransportError, TransportResult as Result,
+    },
+    std::{
+        collections::{HashMap, VecDeque},
+        num::NonZeroU64,
+        sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+    },
+};
+
+/// Number of slots that must be produced on top of a slot before it is
+/// reported as confirmed.
+pub const CONFIRMATION_DEPTH: u64 = 1;
+
+/// Number of slots that must be produced on top of a slot before it is
+/// reported as finalized.
+pub const FINALIZATION_DEPTH: u64 = 32;
+
+/// Default fee charged per transaction signature.
+pub const DEFAULT_LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+
+#[derive(Debug)]
+struct SignatureStatus {
+    slot: Slot,
+    status: TransactionResult<()>,
+}
+
+#[derive(Debug)]
+struct BankState {
+    accounts: HashMap<Pubkey, Account>,
+    slot: Slot,
+    /// Recent blockhashes with the slot that registered them, oldest first.
+    blockhashes: VecDeque<(Slot, Hash)>,
+    statuses: HashMap<Signature, SignatureStatus>,
+    transaction_count: u64,
+}
+
+impl BankState {
+    fn slot_with_commitment(&self, commitment_config: CommitmentConfig) -> Slot {
+        match commitment_config.commitment {
+            CommitmentLevel::Processed => self.slot,
+            CommitmentLevel::Confirmed => self.slot.saturating_sub(CONFIRMATION_DEPTH),
+            CommitmentLevel::Finalized => self.slot.saturating_sub(FINALIZATION_DEPTH),
+        }
+    }
+
+    /// Like [`Self::slot_with_commitment`], but returns `None` while not
+    /// enough slots have been produced to reach the commitment level.
+    fn checked_slot_with_commitment(&self, commitment_config: CommitmentConfig) -> Option<Slot> {
+        match commitment_config.commitment {
+            CommitmentLevel::Processed => Some(self.slot),
+            CommitmentLevel::Confirmed => self.slot.checked_sub(CONFIRMATION_DEPTH),
+            CommitmentLevel::Finalized => self.slot.checked_sub(FINALIZATION_DEPTH),
+        }
+    }
+
+    fn is_blockhash_valid(&self, blockhash: &Hash, max_slot: Slot) -> bool {
+        self.blockhashes
+            .iter()
+            .any(|(slot, hash)| hash == blockhash && *slot <= max_slot)
+    }
+
+    fn latest_blockhash(&self, max_slot: Slot) -> (Slot, Hash) {
+        self.blockhashes
+            .iter()
+            .rev()
+            .find(|(slot, _)| *slot <= max_slot)
+            .or_else(|| self.blockhashes.front())
+            .copied()
+            .expect("at least one blockhash is always registered")
+    }
+}
+
+/// An in-memory, bank-like implementation of the client traits.
+///
+/// See the [module documentation](self) for the supported behavior.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     trezoa_client_traits::{BankLikeClient, SyncClient},
+///     trezoa_keypair::Keypair,
+///     trezoa_pubkey::Pubkey,
+///     trezoa_signer::Signer,
+/// };
+///
+/// let payer = Keypair::new();
+/// let client = BankLikeClient::default();
+/// client.airdrop(&payer.pubkey(), 1_000_000);
+///
+/// let recipient = Pubkey::new_unique();
+/// client.transfer_and_confirm(500_000, &payer, &recipient)?;
+/// assert_eq!(client.get_balance(&recipient)?, 500_000);
+/// # Ok::<(), trezoa_transaction_error::TransportError>(())
+/// ```
+#[derive(Debug)]
+pub struct BankLikeClient {
+    state: RwLock<BankState>,
+    rent: Rent,
+    lamports_per_signature: u64,
+    slots_per_epoch: NonZeroU64,
+}
+
+impl Default for BankLikeClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BankLikeClient {
+    /// Creates an empty client at slot 0 with a single registered blockhash.
+    pub fn new() -> Self {
+        let genesis_blockhash = trezoa_sha256_hasher::hash(b"bank-like-client genesis");
+        Self {
+            state: RwLock::new(BankState {
+                accounts: HashMap::new(),
+                slot: 0,
+                blockhashes: VecDeque::from([(0, genesis_blockhash)]),
+                statuses: HashMap::new(),
+                transaction_count: 0,
+            }),
+            rent: Rent::default(),
+            lamports_per_signature: DEFAULT_LAMPORTS_PER_SIGNATURE,
+            slots_per_epoch: NonZeroU64::new(DEFAULT_SLOTS_PER_EPOCH).unwrap(),
+        }
+    }
+
+    /// Sets the fee charged per transaction signature.
+    pub fn with_lamports_per_signature(mut self, lamports_per_signature: u64) -> Self {
+        self.lamports_per_signature = lamports_per_signature;
+        self
+    }
+
+    /// Sets the rent used by [`SyncClient::get_minimum_balance_for_rent_exemption`].
+    pub fn with_rent(mut self, rent: Rent) -> Self {
+        self.rent = rent;
+        self
+    }
+
+    /// Sets the number of slots per epoch reported by [`SyncClient::get_epoch_info`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `slots_per_epoch` is zero.
+    pub fn with_slots_per_epoch(mut self, slots_per_epoch: u64) -> Self {
+        self.slots_per_epoch =
+            NonZeroU64::new(slots_per_epoch).expect("slots per epoch must be non-zero");
+        self
+    }
+
+    /// Returns the fee charged per transaction signature.
+    pub fn lamports_per_signature(&self) -> u64 {
+        self.lamports_per_signature
+    }
+
+    /// Stores `account` at `pubkey`, replacing any existing account.
+    pub fn set_account(&self, pubkey: &Pubkey, account: Account) {
+        self.write_state().accounts.insert(*pubkey, account);
+    }
+
+    /// Credits `lamports` to the system-owned account at `pubkey`, creating it
+    /// if necessary.
+    pub fn airdrop(&self, pubkey: &Pubkey, lamports: u64) {
+        let mut state = self.write_state();
+        let account = state.accounts.entry(*pubkey).or_default();
+        account.lamports = account.lamports.saturating_add(lamports);
+    }
+
+    /// Produces a new slot and registers a new blockhash, expiring blockhashes
+    /// older than [`MAX_PROCESSING_AGE`].
+    pub fn advance_slot(&self) -> Slot {
+        let mut state = self.write_state();
+        let (_, last_blockhash) = *state
+            .blockhashes
+            .back()
+            .expect("at least one blockhash is always registered");
+        state.slot = state.slot.saturating_add(1);
+        let blockhash =
+            trezoa_sha256_hasher::hashv(&[last_blockhash.as_ref(), &state.slot.to_le_bytes()]);
+        let slot = state.slot;
+        state.blockhashes.push_back((slot, blockhash));
+        while state.blockhashes.len() > MAX_PROCESSING_AGE {
+            state.blockhashes.pop_front();
+        }
+        slot
+    }
+
+    /// Produces `num_slots` new slots, returning the last one.
+    pub fn advance_slots(&self, num_slots: u64) -> Slot {
+        let mut slot = self.read_state().slot;
+        for _ in 0..num_slots {
+            slot = self.advance_slot();
+        }
+        slot
+    }
+
+    /// Processes a signed transaction in the current slot.
+    ///
+    /// Transactions that fail before execution (sanitization, blockhash,
+    /// signature or fee checks) return an error and are not recorded. Once
+    /// the fee has been charged the transaction's status is recorded, and the
+    /// result of executing its instructions is returned.
+    pub fn process_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> TransactionResult<Signature> {
+        let mut state = self.write_state();
+        let signature = self.load_and_charge_fee(&mut state, transaction)?;
+        let result = execute_message(&mut state.accounts, &transaction.message);
+        let slot = state.slot;
+        state.statuses.insert(
+            signature,
+            SignatureStatus {
+                slot,
+                status: result.clone(),
+            },
+        );
+        if result.is_ok() {
+            state.transaction_count = state.transaction_count.saturating_add(1);
+        }
+        result.map(|()| signature)
+    }
+
+    fn load_and_charge_fee(
+        &self,
+        state: &mut BankState,
+        transaction: &VersionedTransaction,
+    ) -> TransactionResult<Signature> {
+        transaction
+            .sanitize()
+            .map_err(|_| TransactionError::SanitizeFailure)?;
+        if transaction
+            .message
+            .address_table_lookups()
+            .is_some_and(|lookups| !lookups.is_empty())
+        {
+            return Err(TransactionError::UnsupportedVersion);
+        }
+        let signature = transaction.signatures[0];
+        if state.statuses.contains_key(&signature) {
+            return Err(TransactionError::AlreadyProcessed);
+        }
+        if !state.is_blockhash_valid(transaction.message.recent_blockhash(), state.slot) {
+            return Err(TransactionError::BlockhashNotFound);
+        }
+        if !transaction.verify_with_results().into_iter().all(|ok| ok) {
+            return Err(TransactionError::SignatureFailure);
+        }
+
+        let fee = self
+            .lamports_per_signature
+            .saturating_mul(transaction.signatures.len() as u64);
+        let fee_payer = transaction.message.static_account_keys()[0];
+        let payer_account = state
+            .accounts
+            .get_mut(&fee_payer)
+            .ok_or(TransactionError::AccountNotFound)?;
+        if !system_program::check_id(&payer_account.owner) || !payer_account.data.is_empty() {
+            return Err(TransactionError::InvalidAccountForFee);
+        }
+        payer_account.lamports = payer_account
+            .lamports
+            .checked_sub(fee)
+            .ok_or(TransactionError::InsufficientFundsForFee)?;
+        if payer_account.lamports == 0 {
+            state.accounts.remove(&fee_payer);
+        }
+        Ok(signature)
+    }
+
+    fn read_state(&self) -> RwLockReadGuard<'_, BankState> {
+        self.state.read().unwrap()
+    }
+
+    fn write_state(&self) -> RwLockWriteGuard<'_, BankState> {
+        self.state.write().unwrap()
+    }
+
+    fn sign_message<T: Signers + ?Sized>(
+        &self,
+        keypairs: &T,
+        message: Message,
+        recent_blockhash: Hash,
+    ) -> Result<Transaction> {
+        let mut transaction = Transaction::new_unsigned(message);
+        transaction
+            .try_sign(keypairs, recent_blockhash)
+            .map_err(|err| TransportError::Custom(err.to_string()))?;
+        Ok(transaction)
+    }
+}
+
+/// Executes every instruction of `message` against a copy of `accounts`,
+/// committing the copy only if all of them succeed.
+fn execute_message(
+    accounts: &mut HashMap<Pubkey, Account>,
+    message: &VersionedMessage,
+) -> TransactionResult<()> {
+    let mut working_accounts = accounts.clone();
+    for (index, instruction) in message.instructions().iter().enumerate() {
+        execute_instruction(&mut working_accounts, message, instruction)
+            .map_err(|err| TransactionError::InstructionError(index as u8, err))?;
+    }
+    working_accounts.retain(|_, account| account.lamports > 0);
+    *accounts = working_accounts;
+    Ok(())
+}
+
+/// The accounts passed to a single instruction, resolved against the message.
+struct InstructionAccounts<'a> {
+    message: &'a VersionedMessage,
+    indexes: &'a [u8],
+}
+
+impl InstructionAccounts<'_> {
+    fn key_index(&self, position: usize) -> std::result::Result<usize, InstructionError> {
+        self.indexes
+            .get(position)
+            .map(|index| *index as usize)
+            .ok_or(InstructionError::MissingAccount)
+    }
+
+    fn key(&self, position: usize) -> std::result::Result<Pubkey, InstructionError> {
+        let index = self.key_index(position)?;
+        self.message
+            .static_account_keys()
+            .get(index)
+            .copied()
+            .ok_or(InstructionError::MissingAccount)
+    }
+
+    fn is_signer(&self, position: usize) -> std::result::Result<bool, InstructionError> {
+        Ok(self.message.is_signer(self.key_index(position)?))
+    }
+
+    fn writable_key(&self, position: usize) -> std::result::Result<Pubkey, InstructionError> {
+        let key = self.key(position)?;
+        if self
+            .message
+            .is_maybe_writable(self.key_index(position)?, None)
+        {
+            Ok(key)
+        } else {
+            Err(InstructionError::ReadonlyLamportChange)
+        }
+    }
+
+    fn require_signer(&self, position: usize) -> std::result::Result<(), InstructionError> {
+        if self.is_signer(position)? {
+            Ok(())
+        } else {
+            Err(InstructionError::MissingRequiredSignature)
+        }
+    }
+}
+
+fn system_error(err: SystemError) -> InstructionError {
+    InstructionError::Custom(err as u32)
+}
+
+fn execute_instruction(
+    accounts: &mut HashMap<Pubkey, Account>,
+    message: &VersionedMessage,
+    instruction: &CompiledInstruction,
+) -> std::result::Result<(), InstructionError> {
+    let program_id = message
+        .static_account_keys()
+        .get(instruction.program_id_index as usize)
+        .ok_or(InstructionError::MissingAccount)?;
+    if !system_program::check_id(program_id) {
+        return Err(InstructionError::UnsupportedProgramId);
+    }
+    let system_instruction: SystemInstruction = bincode::deserialize(&instruction.data)
+        .map_err(|_| InstructionError::InvalidInstructionData)?;
+    let ix_accounts = InstructionAccounts {
+        message,
+        indexes: &instruction.accounts,
+    };
+
+    match system_instruction {
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let from = ix_accounts.writable_key(0)?;
+            let to = ix_accounts.writable_key(1)?;
+            ix_accounts.require_signer(1)?;
+            if accounts
+                .get(&to)
+                .is_some_and(|account| account.lamports > 0)
+            {
+                return Err(system_error(SystemError::AccountAlreadyInUse));
+            }
+            allocate_and_assign(accounts, &to, space, &owner)?;
+            ix_accounts.require_signer(0)?;
+            transfer(accounts, &from, &to, lamports)
+        }
+        SystemInstruction::CreateAccountAllowPrefund {
+            lamports,
+            space,
+            owner,
+        } => {
+            let to = ix_accounts.writable_key(0)?;
+            ix_accounts.require_signer(0)?;
+            allocate_and_assign(accounts, &to, space, &owner)?;
+            if lamports > 0 {
+                let from = ix_accounts.writable_key(1)?;
+                ix_accounts.require_signer(1)?;
+                transfer(accounts, &from, &to, lamports)?;
+            }
+            Ok(())
+        }
+        SystemInstruction::CreateAccountWithSeed {
+            base,
+            seed,
+            lamports,
+            space,
+            owner,
+        } => {
+            let from = ix_accounts.writable_key(0)?;
+            let to = ix_accounts.writable_key(1)?;
+            check_seed_address(&to, &base, &seed, &owner)?;
+            require_base_signer(&ix_accounts, &base)?;
+            if accounts
+                .get(&to)
+                .is_some_and(|account| account.lamports > 0)
+            {
+                return Err(system_error(SystemError::AccountAlreadyInUse));
+            }
+            allocate_and_assign(accounts, &to, space, &owner)?;
+            ix_accounts.require_signer(0)?;
+            transfer(accounts, &from, &to, lamports)
+        }
+        SystemInstruction::Assign { owner } => {
+            let key = ix_accounts.writable_key(0)?;
+            let account = accounts.entry(key).or_default();
+            if account.owner == owner {
+                return Ok(());
+            }
+            ix_accounts.require_signer(0)?;
+            account.owner = owner;
+            Ok(())
+        }
+        SystemInstruction::AssignWithSeed { base, seed, owner } => {
+            let key = ix_accounts.writable_key(0)?;
+            check_seed_address(&key, &base, &seed, &owner)?;
+            require_base_signer(&ix_accounts, &base)?;
+            accounts.entry(key).or_default().owner = owner;
+            Ok(())
+        }
+        SystemInstruction::Transfer { lamports } => {
+            let from = ix_accounts.writable_key(0)?;
+            let to = ix_accounts.writable_key(1)?;
+            ix_accounts.require_signer(0)?;
+            transfer(accounts, &from, &to, lamports)
+        }
+        SystemInstruction::TransferWithSeed {
+            lamports,
+            from_seed,
+            from_owner,
+        } => {
+            let from = ix_accounts.writable_key(0)?;
+            let base = ix_accounts.key(1)?;
+            let to = ix_accounts.writable_key(2)?;
+            ix_accounts.require_signer(1)?;
+            check_seed_address(&from, &base, &from_seed, &from_owner)?;
+            transfer(accounts, &from, &to, lamports)
+        }
+        SystemInstruction::Allocate { space } => {
+            let key = ix_accounts.writable_key(0)?;
+            ix_accounts.require_signer(0)?;
+            allocate(accounts, &key, space)
+        }
+        SystemInstruction::AllocateWithSeed {
+            base,
+            seed,
+            space,
+            owner,
+        } => {
+            let key = ix_accounts.writable_key(0)?;
+            check_seed_address(&key, &base, &seed, &owner)?;
+            require_base_signer(&ix_accounts, &base)?;
+            allocate_and_assign(accounts, &key, space, &owner)
+        }
+        SystemInstruction::AdvanceNonceAccount
+        | SystemInstruction::WithdrawNonceAccount(_)
+        | SystemInstruction::InitializeNonceAccount(_)
+        | SystemInstruction::AuthorizeNonceAccount(_)
+        | SystemInstruction::UpgradeNonceAccount => Err(InstructionError::InvalidInstructionData),
+    }
+}
+
+fn check_seed_address(
+    address: &Pubkey,
+    base: &Pubkey,
+    seed: &str,
+    owner: &Pubkey,
+) -> std::result::Result<(), InstructionError> {
+    let expected = Pubkey::create_with_seed(base, seed, owner)
+        .map_err(|_| system_error(SystemError::MaxSeedLengthExceeded))?;
+    if *address == expected {
+        Ok(())
+    } else {
+        Err(system_error(SystemError::AddressWithSeedMismatch))
+    }
+}
+
+fn require_base_signer(
+    ix_accounts: &InstructionAccounts,
+    base: &Pubkey,
+) -> std::result::Result<(), InstructionError> {
+    let base_is_signer = (0..ix_accounts.indexes.len()).any(|position| {
+        ix_accounts.key(position).ok() == Some(*base)
+            && ix_accounts.is_signer(position).unwrap_or(false)
+    });
+    if base_is_signer {
+        Ok(())
+    } else {
+        Err(InstructionError::MissingRequiredSignature)
+    }
+}
+
+fn allocate(
+    accounts: &mut HashMap<Pubkey, Account>,
+    key: &Pubkey,
+    space: u64,
+) -> std::result::Result<(), InstructionError> {
+    let account = accounts.entry(*key).or_default();
+    if !account.data.is_empty() || !system_program::check_id(&account.owner) {
+        return Err(system_error(SystemError::AccountAlreadyInUse));
+    }
+    if space > MAX_PERMITTED_DATA_LENGTH {
+        return Err(system_error(SystemError::InvalidAccountDataLength));
+    }
+    account.data = vec![0; space as usize];
+    Ok(())
+}
+
+fn allocate_and_assign(
+    accounts: &mut HashMap<Pubkey, Account>,
+    key: &Pubkey,
+    space: u64,
+    owner: &Pubkey,
+) -> std::result::Result<(), InstructionError> {
+    allocate(accounts, key, space)?;
+    accounts.entry(*key).or_default().owner = *owner;
+    Ok(())
+}
+
+fn transfer(
+    accounts: &mut HashMap<Pubkey, Account>,
+    from: &Pubkey,
+    to: &Pubkey,
+    lamports: u64,
+) -> std::result::Result<(), InstructionError> {
+    let from_account = accounts.entry(*from).or_default();
+    if !from_account.data.is_empty() {
+        return Err(InstructionError::InvalidArgument);
+    }
+    from_account.lamports = from_account
+        .lamports
+        .checked_sub(lamports)
+        .ok_or_else(|| system_error(SystemError::ResultWithNegativeLamports))?;
+    let to_account = accounts.entry(*to).or_default();
+    to_account.lamports = to_account
+        .lamports
+        .checked_add(lamports)
+        .ok_or(InstructionError::ArithmeticOverflow)?;
+    Ok(())
+}
+
+impl SyncClient for BankLikeClient {
+    fn send_and_confirm_message<T: Signers + ?Sized>(
+        &self,
+        keypairs: &T,
+        message: Message,
+    ) -> Result<Signature> {
+        let blockhash = self.get_latest_blockhash()?;
+        let transaction = self.sign_message(keypairs, message, blockhash)?;
+        Ok(self.process_transaction(&transaction.into())?)
+    }
+
+    fn send_and_confirm_instruction(
+        &self,
+        keypair: &Keypair,
+        instruction: Instruction,
+    ) -> Result<Signature> {
+        let message = Message::new(&[instruction], Some(&keypair.pubkey()));
+        self.send_and_confirm_message(&[keypair], message)
+    }
+
+    fn transfer_and_confirm(
+        &self,
+        lamports: u64,
+        keypair: &Keypair,
+        pubkey: &Pubkey,
+    ) -> Result<Signature> {
+        let transfer_instruction =
+            trezoa_system_interface::instruction::transfer(&keypair.pubkey(), pubkey, lamports);
+        self.send_and_confirm_instruction(keypair, transfer_instruction)
+    }
+
+    fn get_account_data(&self, pubkey: &Pubkey) -> Result<Option<Vec<u8>>> {
+        Ok(self.get_account(pubkey)?.map(|account| account.data))
+    }
+
+    fn get_account(&self, pubkey: &Pubkey) -> Result<Option<Account>> {
+        Ok(self.read_state().accounts.get(pubkey).cloned())
+    }
+
+    /// Accounts are not versioned by slot, so every commitment level observes
+    /// the latest state.
+    fn get_account_with_commitment(
+        &self,
+        pubkey: &Pubkey,
+        _commitment_config: CommitmentConfig,
+    ) -> Result<Option<Account>> {
+        self.get_account(pubkey)
+    }
+
+    fn get_balance(&self, pubkey: &Pubkey) -> Result<u64> {
+        Ok(self
+            .get_account(pubkey)?
+            .map(|account| account.lamports)
+            .unwrap_or_default())
+    }
+
+    fn get_balance_with_commitment(
+        &self,
+        pubkey: &Pubkey,
+        _commitment_config: CommitmentConfig,
+    ) -> Result<u64> {
+        self.get_balance(pubkey)
+    }
+
+    fn get_minimum_balance_for_rent_exemption(&self, data_len: usize) -> Result<u64> {
+        Ok(self.rent.minimum_balance(data_len))
+    }
+
+    fn get_signature_status(&self, signature: &Signature) -> Result<Option<TransactionResult<()>>> {
+        self.get_signature_status_with_commitment(signature, CommitmentConfig::default())
+    }
+
+    fn get_signature_status_with_commitment(
+        &self,
+        signature: &Signature,
+        commitment_config: CommitmentConfig,
+    ) -> Result<Option<TransactionResult<()>>> {
+        let state = self.read_state();
+        let Some(max_slot) = state.checked_slot_with_commitment(commitment_config) else {
+            return Ok(None);
+        };
+        Ok(state
+            .statuses
+            .get(signature)
+            .filter(|status| status.slot <= max_slot)
+            .map(|status| status.status.clone()))
+    }
+
+    fn get_slot(&self) -> Result<u64> {
+        self.get_slot_with_commitment(CommitmentConfig::default())
+    }
+
+    fn get_slot_with_commitment(&self, commitment_config: CommitmentConfig) -> Result<u64> {
+        Ok(self.read_state().slot_with_commitment(commitment_config))
+    }
+
+    fn get_transaction_count(&self) -> Result<u64> {
+        self.get_transaction_count_with_commitment(CommitmentConfig::default())
+    }
+
+    fn get_transaction_count_with_commitment(
+        &self,
+        _commitment_config: CommitmentConfig,
+    ) -> Result<u64> {
+        Ok(self.read_state().transaction_count)
+    }
+
+    fn get_epoch_info(&self) -> Result<EpochInfo> {
+        let state = self.read_state();
+        Ok(EpochInfo {
+            epoch: state.slot / self.slots_per_epoch,
+            slot_index: state.slot % self.slots_per_epoch,
+            slots_in_epoch: self.slots_per_epoch.get(),
+            absolute_slot: state.slot,
+            block_height: state.slot,
+            transaction_count: Some(state.transaction_count),
+        })
+    }
+
+    /// Returns the number of slots produced on top of the signature's slot.
+    /// Since the client never produces slots on its own, this fails instead
+    /// of blocking when fewer than `min_confirmed_blocks` have been produced.
+    fn poll_for_signature_confirmation(
+        &self,
+        signature: &Signature,
+        min_confirmed_blocks: usize,
+    ) -> Result<usize> {
+        let state = self.read_state();
+        let status = state
+            .statuses
+            .get(signature)
+            .ok_or_else(|| TransportError::Custom(format!("signature not found: {signature}")))?;
+        let confirmations = state.slot.saturating_sub(status.slot) as usize;
+        if confirmations < min_confirmed_blocks {
+            return Err(TransportError::Custom(format!(
+                "signature {signature} has {confirmations} confirmations, expected at least \
+                 {min_confirmed_blocks}"
+            )));
+        }
+        Ok(confirmations)
+    }
+
+    fn poll_for_signature(&self, signature: &Signature) -> Result<()> {
+        match self.get_signature_status_with_commitment(signature, CommitmentConfig::processed())? {
+            Some(_) => Ok(()),
+            None => Err(TransportError::Custom(format!(
+                "signature not found: {signature}"
+            ))),
+        }
+    }
+
+    fn get_latest_blockhash(&self) -> Result<Hash> {
+        let (blockhash, _) =
+            self.get_latest_blockhash_with_commitment(CommitmentConfig::processed())?;
+        Ok(blockhash)
+    }
+
+    fn get_latest_blockhash_with_commitment(
+        &self,
+        commitment_config: CommitmentConfig,
+    ) -> Result<(Hash, u64)> {
+        let state = self.read_state();
+        let (slot, blockhash) =
+            state.latest_blockhash(state.slot_with_commitment(commitment_config));
+        let last_valid_block_height = slot.saturating_add(MAX_PROCESSING_AGE as u64);
+        Ok((blockhash, last_valid_block_height))
+    }
+
+    fn is_blockhash_valid(&self, blockhash: &Hash, commitment: CommitmentConfig) -> Result<bool> {
+        let state = self.read_state();
+        Ok(state.is_blockhash_valid(blockhash, state.slot_with_commitment(commitment)))
+    }
+
+    fn get_fee_for_message(&self, message: &Message) -> Result<u64> {
+        Ok(self
+            .lamports_per_signature
+            .saturating_mul(u64::from(message.header.num_required_signatures)))
+    }
+}
+
+impl AsyncClient for BankLikeClient {
+    /// Processes the transaction immediately. Errors that prevent the
+    /// transaction from being recorded are returned; execution errors are
+    /// only observable through the signature status.
+    fn async_send_versioned_transaction(
+        &self,
+        transaction: VersionedTransaction,
+    ) -> Result<Signature> {
+        match self.process_transaction(&transaction) {
+            Ok(signature) => Ok(signature),
+            Err(TransactionError::InstructionError(..)) => Ok(transaction.signatures[0]),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, trezoa_system_interface::instruction as system_instruction};
+
+    fn funded_client(payer: &Keypair, lamports: u64) -> BankLikeClient {
+        let client = BankLikeClient::new();
+        client.airdrop(&payer.pubkey(), lamports);
+        client
+    }
+
+    #[test]
+    fn test_transfer_charges_fee() {
+        let payer = Keypair::new();
+        let client = funded_client(&payer, 1_000_000);
+        let recipient = Pubkey::new_unique();
+
+        client
+            .transfer_and_confirm(1_000, &payer, &recipient)
+            .unwrap();
+        assert_eq!(client.get_balance(&recipient).unwrap(), 1_000);
+        assert_eq!(
+            client.get_balance(&payer.pubkey()).unwrap(),
+            1_000_000 - 1_000 - DEFAULT_LAMPORTS_PER_SIGNATURE
+        );
+        assert_eq!(client.get_transaction_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_failed_instruction_rolls_back_but_charges_fee() {
+        let payer = Keypair::new();
+        let client = funded_client(&payer, 100_000);
+        let recipient = Pubkey::new_unique();
+
+        let err = client
+            .transfer_and_confirm(1_000_000, &payer, &recipient)
+            .unwrap_err();
+        assert_eq!(
+            err.unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(SystemError::ResultWithNegativeLamports as u32)
+            )
+        );
+        assert_eq!(client.get_balance(&recipient).unwrap(), 0);
+        assert_eq!(
+            client.get_balance(&payer.pubkey()).unwrap(),
+            100_000 - DEFAULT_LAMPORTS_PER_SIGNATURE
+        );
+    }
+
+    #[test]
+    fn test_create_account_and_assign() {
+        let payer = Keypair::new();
+        let new_account = Keypair::new();
+        let owner = Pubkey::new_unique();
+        let client = funded_client(&payer, 10_000_000);
+        let lamports = client.get_minimum_balance_for_rent_exemption(16).unwrap();
+
+        let instruction = system_instruction::create_account(
+            &payer.pubkey(),
+            &new_account.pubkey(),
+            lamports,
+            16,
+            &owner,
+        );
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        client
+            .send_and_confirm_message(&[&payer, &new_account], message)
+            .unwrap();
+
+        let account = client.get_account(&new_account.pubkey()).unwrap().unwrap();
+        assert_eq!(account.lamports, lamports);
+        assert_eq!(account.data, vec![0; 16]);
+        assert_eq!(account.owner, owner);
+
+        // Creating the same account again fails
+        let instruction = system_instruction::create_account(
+            &payer.pubkey(),
+            &new_account.pubkey(),
+            lamports,
+            16,
+            &owner,
+        );
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        client.advance_slot();
+        let err = client
+            .send_and_confirm_message(&[&payer, &new_account], message)
+            .unwrap_err();
+        assert_eq!(
+            err.unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(SystemError::AccountAlreadyInUse as u32)
+            )
+        );
+    }
+
+    #[test]
+    fn test_assign_requires_signature() {
+        let payer = Keypair::new();
+        let client = funded_client(&payer, 1_000_000);
+        let target = Pubkey::new_unique();
+        client.airdrop(&target, 1);
+
+        let mut instruction = system_instruction::assign(&target, &Pubkey::new_unique());
+        instruction.accounts[0].is_signer = false;
+        let err = client
+            .send_and_confirm_instruction(&payer, instruction)
+            .unwrap_err();
+        assert_eq!(
+            err.unwrap(),
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+    }
+
+    #[test]
+    fn test_signature_status_by_commitment() {
+        let payer = Keypair::new();
+        let client = funded_client(&payer, 1_000_000);
+        let signature = client
+            .transfer_and_confirm(1, &payer, &Pubkey::new_unique())
+            .unwrap();
+
+        let status = |commitment| {
+            client
+                .get_signature_status_with_commitment(&signature, commitment)
+                .unwrap()
+        };
+        assert_eq!(status(CommitmentConfig::processed()), Some(Ok(())));
+        assert_eq!(status(CommitmentConfig::confirmed()), None);
+        assert_eq!(status(CommitmentConfig::finalized()), None);
+
+        client.advance_slots(CONFIRMATION_DEPTH);
+        assert_eq!(status(CommitmentConfig::confirmed()), Some(Ok(())));
+        assert_eq!(status(CommitmentConfig::finalized()), None);
+
+        client.advance_slots(FINALIZATION_DEPTH);
+        assert_eq!(status(CommitmentConfig::finalized()), Some(Ok(())));
+        assert_eq!(
+            client.get_signature_status(&signature).unwrap(),
+            Some(Ok(()))
+        );
+        assert_eq!(
+            client
+                .poll_for_signature_confirmation(&signature, 1)
+                .unwrap(),
+            (CONFIRMATION_DEPTH + FINALIZATION_DEPTH) as usize
+        );
+    }
+
+    #[test]
+    fn test_blockhash_expiry() {
+        let payer = Keypair::new();
+        let client = funded_client(&payer, 1_000_000);
+        let blockhash = client.get_latest_blockhash().unwrap();
+        assert!(client
+            .is_blockhash_valid(&blockhash, CommitmentConfig::processed())
+            .unwrap());
+
+        client.advance_slot();
+        let latest = client.get_latest_blockhash().unwrap();
+        assert_ne!(latest, blockhash);
+        assert_eq!(
+            client
+                .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+                .unwrap()
+                .0,
+            blockhash
+        );
+
+        client.advance_slots(MAX_PROCESSING_AGE as u64);
+        assert!(!client
+            .is_blockhash_valid(&blockhash, CommitmentConfig::processed())
+            .unwrap());
+
+        let transaction = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        assert_eq!(
+            client
+                .async_send_transaction(transaction)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::BlockhashNotFound
+        );
+    }
+
+    #[test]
+    fn test_duplicate_and_unsigned_transactions() {
+        let payer = Keypair::new();
+        let client = funded_client(&payer, 1_000_000);
+        let blockhash = client.get_latest_blockhash().unwrap();
+        let transfer = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+
+        let transaction = Transaction::new_signed_with_payer(
+            std::slice::from_ref(&transfer),
+            Some(&payer.pubkey()),
+            &[&payer],
+            blockhash,
+        );
+        client.async_send_transaction(transaction.clone()).unwrap();
+        assert_eq!(
+            client
+                .async_send_transaction(transaction)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::AlreadyProcessed
+        );
+
+        let mut transaction = Transaction::new_with_payer(&[transfer], Some(&payer.pubkey()));
+        transaction.message.recent_blockhash = blockhash;
+        transaction.signatures = vec![Signature::new_unique()];
+        assert_eq!(
+            client
+                .async_send_transaction(transaction)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::SignatureFailure
+        );
+    }
+
+    #[test]
+    fn test_unsupported_program() {
+        let payer = Keypair::new();
+        let client = funded_client(&payer, 1_000_000);
+        let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let blockhash = client.get_latest_blockhash().unwrap();
+        let signature = client
+            .async_send_instruction(&payer, instruction, blockhash)
+            .unwrap();
+        assert_eq!(
+            client
+                .get_signature_status_with_commitment(&signature, CommitmentConfig::processed())
+                .unwrap(),
+            Some(Err(TransactionError::InstructionError(
+                0,
+                InstructionError::UnsupportedProgramId
+            )))
+        );
+    }
+
+    #[test]
+    fn test_epoch_info() {
+        let client = BankLikeClient::new().with_slots_per_epoch(32);
+        client.advance_slots(70);
+        let epoch_info = client.get_epoch_info().unwrap();
+        assert_eq!(epoch_info.epoch, 2);
+        assert_eq!(epoch_info.slot_index, 6);
+        assert_eq!(epoch_info.slots_in_epoch, 32);
+    }
+
+    #[test]
+    #[should_panic(expected = "slots per epoch must be non-zero")]
+    fn test_zero_slots_per_epoch() {
+        let _ = BankLikeClient::new().with_slots_per_epoch(0);
+    }
+}
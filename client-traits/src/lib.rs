@@ -8,6 +8,13 @@
 //! them but without waiting to see if the server accepted it.
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(feature = "account-discovery")]
+pub mod account_discovery;
+mod bank_like_client;
+
+pub use bank_like_client::{
+    BankLikeClient, CONFIRMATION_DEPTH, DEFAULT_LAMPORTS_PER_SIGNATURE, FINALIZATION_DEPTH,
+};
 use {
     trezoa_account::Account,
     trezoa_commitment_config::CommitmentConfig,
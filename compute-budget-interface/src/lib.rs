@@ -77,6 +77,28 @@ impl ComputeBudgetInstruction {
     pub fn set_loaded_accounts_data_size_limit(bytes: u32) -> Instruction {
         to_instruction!(4, bytes, u32)
     }
+
+    /// Decode `ComputeBudgetInstruction` instruction data without a borsh
+    /// dependency, returning `None` if the data is malformed.
+    pub fn from_instruction_data(data: &[u8]) -> Option<Self> {
+        let (discriminator, payload) = data.split_first()?;
+        match discriminator {
+            0 => payload.is_empty().then_some(Self::Unused),
+            1 => Some(Self::RequestHeapFrame(u32::from_le_bytes(
+                payload.try_into().ok()?,
+            ))),
+            2 => Some(Self::SetComputeUnitLimit(u32::from_le_bytes(
+                payload.try_into().ok()?,
+            ))),
+            3 => Some(Self::SetComputeUnitPrice(u64::from_le_bytes(
+                payload.try_into().ok()?,
+            ))),
+            4 => Some(Self::SetLoadedAccountsDataSizeLimit(u32::from_le_bytes(
+                payload.try_into().ok()?,
+            ))),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -90,4 +112,45 @@ mod tests {
         let ix = ComputeBudgetInstruction::set_compute_unit_price(u64::MAX);
         assert_eq!(ix.data, vec![3, 255, 255, 255, 255, 255, 255, 255, 255]);
     }
+
+    #[test]
+    fn test_from_instruction_data() {
+        for (ix, expected) in [
+            (
+                ComputeBudgetInstruction::request_heap_frame(64 * 1024),
+                ComputeBudgetInstruction::RequestHeapFrame(64 * 1024),
+            ),
+            (
+                ComputeBudgetInstruction::set_compute_unit_limit(257),
+                ComputeBudgetInstruction::SetComputeUnitLimit(257),
+            ),
+            (
+                ComputeBudgetInstruction::set_compute_unit_price(u64::MAX),
+                ComputeBudgetInstruction::SetComputeUnitPrice(u64::MAX),
+            ),
+            (
+                ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(1),
+                ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(1),
+            ),
+        ] {
+            assert_eq!(
+                ComputeBudgetInstruction::from_instruction_data(&ix.data),
+                Some(expected)
+            );
+        }
+        assert_eq!(
+            ComputeBudgetInstruction::from_instruction_data(&[0]),
+            Some(ComputeBudgetInstruction::Unused)
+        );
+        assert_eq!(ComputeBudgetInstruction::from_instruction_data(&[]), None);
+        assert_eq!(
+            ComputeBudgetInstruction::from_instruction_data(&[2, 1]),
+            None
+        );
+        assert_eq!(
+            ComputeBudgetInstruction::from_instruction_data(&[3, 0, 0, 0, 0]),
+            None
+        );
+        assert_eq!(ComputeBudgetInstruction::from_instruction_data(&[5]), None);
+    }
 }
@@ -11,7 +11,7 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 use {
-    core::{iter::IntoIterator, slice::Iter},
+    core::{iter::IntoIterator, ops::Range, slice::Iter},
     derivation_path::{ChildIndex, DerivationPath as DerivationPathInner},
     std::{
         convert::{Infallible, TryFrom},
@@ -23,6 +23,8 @@ use {
 
 const ACCOUNT_INDEX: usize = 2;
 const CHANGE_INDEX: usize = 3;
+// Hardened child indexes are below 2^31
+const HARDENED_INDEX_LIMIT: u32 = 1 << 31;
 
 /// Derivation path error.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -118,6 +120,27 @@ impl DerivationPath {
         Self::new_bip44_with_coin(Trezoa, account, change)
     }
 
+    /// Returns the paths `m/44'/501'/i'/change'` for each account `i` in
+    /// `accounts`, or `m/44'/501'/i'` if `change` is `None`.
+    ///
+    /// Accounts of 2^31 and above cannot be hardened and are not returned.
+    pub fn new_bip44_accounts(
+        accounts: Range<u32>,
+        change: Option<u32>,
+    ) -> impl DoubleEndedIterator<Item = Self> + ExactSizeIterator {
+        Self::new_bip44_accounts_with_coin(Trezoa, accounts, change)
+    }
+
+    fn new_bip44_accounts_with_coin<T: Bip44 + Copy>(
+        coin: T,
+        accounts: Range<u32>,
+        change: Option<u32>,
+    ) -> impl DoubleEndedIterator<Item = Self> + ExactSizeIterator {
+        let end = accounts.end.min(HARDENED_INDEX_LIMIT);
+        (accounts.start..end)
+            .map(move |account| Self::new_bip44_with_coin(coin, Some(account), change))
+    }
+
     fn new_bip44_with_coin<T: Bip44>(coin: T, account: Option<u32>, change: Option<u32>) -> Self {
         let mut indexes = coin.base_indexes();
         if let Some(account) = account {
@@ -278,6 +301,7 @@ trait Bip44 {
     }
 }
 
+#[derive(Clone, Copy)]
 struct Trezoa;
 
 impl Bip44 for Trezoa {
@@ -288,6 +312,7 @@ impl Bip44 for Trezoa {
 mod tests {
     use {super::*, assert_matches::assert_matches, uriparse::URIReferenceBuilder};
 
+    #[derive(Clone, Copy)]
     struct TestCoin;
     impl Bip44 for TestCoin {
         const COIN: u32 = 999;
@@ -331,6 +356,36 @@ mod tests {
         assert!(DerivationPath::from_key_str_with_coin("1o", TestCoin).is_err());
     }
 
+    #[test]
+    fn test_new_bip44_accounts() {
+        let paths: Vec<_> =
+            DerivationPath::new_bip44_accounts_with_coin(TestCoin, 2..5, Some(0)).collect();
+        assert_eq!(
+            paths,
+            (2..5)
+                .map(|account| DerivationPath::new_bip44_with_coin(
+                    TestCoin,
+                    Some(account),
+                    Some(0)
+                ))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(format!("{:?}", paths[0]), "m/44'/999'/2'/0'");
+
+        let mut paths = DerivationPath::new_bip44_accounts(7..8, None);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(format!("{:?}", paths.next().unwrap()), "m/44'/501'/7'");
+        assert_eq!(paths.next(), None);
+
+        let paths = DerivationPath::new_bip44_accounts(u32::MAX - 1..u32::MAX, Some(0));
+        assert_eq!(paths.len(), 0);
+        let last = DerivationPath::new_bip44_accounts(0..u32::MAX, None).next_back();
+        assert_eq!(
+            last.unwrap().account(),
+            Some(&ChildIndex::Hardened((1 << 31) - 1))
+        );
+    }
+
     #[test]
     fn test_from_absolute_path_str() {
         let s = "m/44/501";
@@ -2,6 +2,11 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![cfg_attr(feature = "frozen-abi", feature(min_specialization))]
 
+#[cfg(feature = "message")]
+mod message_fee;
+
+#[cfg(feature = "message")]
+pub use message_fee::*;
 use std::num::NonZeroU32;
 
 /// A fee and its associated compute unit limit
@@ -0,0 +1,372 @@
+//! Offline fee calculation for transaction messages.
+
+use {
+    crate::{FeeDetails, FeeStructure},
+    trezoa_address::Address,
+    trezoa_compute_budget_interface::ComputeBudgetInstruction,
+    trezoa_instruction_error::InstructionError,
+    trezoa_message::{
+        compiled_instruction::CompiledInstruction, SanitizedMessage, TransactionSignatureDetails,
+        VersionedMessage,
+    },
+    trezoa_sdk_ids::{
+        address_lookup_table, bpf_loader, bpf_loader_deprecated, bpf_loader_upgradeable,
+        compute_budget, loader_v4, stake, system_program, vote,
+    },
+    trezoa_transaction_error::TransactionError,
+};
+
+/// Maximum compute units a transaction may request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Compute units allotted to each non-builtin instruction when the
+/// transaction does not set a compute unit limit.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Compute units allotted to each builtin instruction when the transaction
+/// does not set a compute unit limit.
+pub const MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT: u32 = 3_000;
+/// Number of micro-lamports in a lamport, the unit of the compute unit price.
+pub const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// Builtin programs, which are allotted
+/// [`MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT`] compute units by default.
+const BUILTIN_PROGRAM_IDS: &[Address] = &[
+    address_lookup_table::ID,
+    bpf_loader::ID,
+    bpf_loader_deprecated::ID,
+    bpf_loader_upgradeable::ID,
+    compute_budget::ID,
+    loader_v4::ID,
+    stake::ID,
+    system_program::ID,
+    vote::ID,
+];
+
+/// A message whose fee can be calculated with
+/// [`FeeStructure::calculate_fee_details`].
+pub trait FeeMessage {
+    /// Number of transaction and precompile signatures.
+    fn get_signature_details(&self) -> TransactionSignatureDetails;
+
+    /// Number of accounts the message write-locks.
+    fn num_write_locks(&self) -> u64;
+
+    /// Each instruction paired with its program id.
+    fn program_instructions_iter(&self) -> impl Iterator<Item = (&Address, &CompiledInstruction)>;
+}
+
+impl FeeMessage for SanitizedMessage {
+    fn get_signature_details(&self) -> TransactionSignatureDetails {
+        SanitizedMessage::get_signature_details(self)
+    }
+
+    fn num_write_locks(&self) -> u64 {
+        SanitizedMessage::num_write_locks(self)
+    }
+
+    fn program_instructions_iter(&self) -> impl Iterator<Item = (&Address, &CompiledInstruction)> {
+        SanitizedMessage::program_instructions_iter(self)
+    }
+}
+
+impl FeeMessage for VersionedMessage {
+    fn get_signature_details(&self) -> TransactionSignatureDetails {
+        VersionedMessage::get_signature_details(self)
+    }
+
+    fn num_write_locks(&self) -> u64 {
+        VersionedMessage::num_write_locks(self)
+    }
+
+    fn program_instructions_iter(&self) -> impl Iterator<Item = (&Address, &CompiledInstruction)> {
+        VersionedMessage::program_instructions_iter(self)
+    }
+}
+
+/// The compute budget requested by a message's instructions.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub struct ComputeBudgetLimits {
+    /// Compute unit limit, either requested or the runtime default.
+    pub compute_unit_limit: u32,
+    /// Compute unit price in micro-lamports.
+    pub compute_unit_price: u64,
+}
+
+impl ComputeBudgetLimits {
+    /// Parses the compute-budget instructions of a message the way the
+    /// runtime does, rejecting duplicate and malformed instructions.
+    pub fn try_from_message(message: &impl FeeMessage) -> Result<Self, TransactionError> {
+        let mut requested_compute_unit_limit = None;
+        let mut compute_unit_price = None;
+        let mut requested_heap_frame = None;
+        let mut requested_loaded_accounts_data_size_limit = None;
+        let mut num_builtin_instructions = 0u32;
+        let mut num_non_builtin_instructions = 0u32;
+
+        for (index, (program_id, instruction)) in message.program_instructions_iter().enumerate() {
+            let index = index as u8;
+            if BUILTIN_PROGRAM_IDS.contains(program_id) {
+                num_builtin_instructions = num_builtin_instructions.saturating_add(1);
+            } else {
+                num_non_builtin_instructions = num_non_builtin_instructions.saturating_add(1);
+            }
+            if !compute_budget::check_id(program_id) {
+                continue;
+            }
+
+            let duplicate = match ComputeBudgetInstruction::from_instruction_data(&instruction.data)
+            {
+                Some(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                    requested_heap_frame.replace(bytes).is_some()
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                    requested_compute_unit_limit.replace(units).is_some()
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                    compute_unit_price.replace(micro_lamports).is_some()
+                }
+                Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => {
+                    requested_loaded_accounts_data_size_limit
+                        .replace(bytes)
+                        .is_some()
+                }
+                Some(ComputeBudgetInstruction::Unused) | None => {
+                    return Err(TransactionError::InstructionError(
+                        index,
+                        InstructionError::InvalidInstructionData,
+                    ));
+                }
+            };
+            if duplicate {
+                return Err(TransactionError::DuplicateInstruction(index));
+            }
+        }
+
+        let compute_unit_limit = requested_compute_unit_limit
+            .unwrap_or_else(|| {
+                num_builtin_instructions
+                    .saturating_mul(MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT)
+                    .saturating_add(
+                        num_non_builtin_instructions
+                            .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT),
+                    )
+            })
+            .min(MAX_COMPUTE_UNIT_LIMIT);
+
+        Ok(Self {
+            compute_unit_limit,
+            compute_unit_price: compute_unit_price.unwrap_or_default(),
+        })
+    }
+
+    /// The prioritization fee in lamports, rounded up.
+    pub fn prioritization_fee(&self) -> u64 {
+        let micro_lamport_fee =
+            u128::from(self.compute_unit_price).saturating_mul(u128::from(self.compute_unit_limit));
+        micro_lamport_fee
+            .div_ceil(u128::from(MICRO_LAMPORTS_PER_LAMPORT))
+            .try_into()
+            .unwrap_or(u64::MAX)
+    }
+}
+
+impl FeeStructure {
+    /// Calculates the fee charged for a message without a round trip to a
+    /// node.
+    ///
+    /// The transaction fee covers the transaction signatures, the signatures
+    /// verified by ed25519, secp256k1 and secp256r1 precompile instructions,
+    /// and the message's write locks. The prioritization fee is derived from
+    /// the message's compute-budget instructions.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error the runtime would reject the message with if its
+    /// compute-budget instructions are malformed or duplicated.
+    pub fn calculate_fee_details(
+        &self,
+        message: &impl FeeMessage,
+    ) -> Result<FeeDetails, TransactionError> {
+        let compute_budget_limits = ComputeBudgetLimits::try_from_message(message)?;
+        let transaction_fee = message
+            .get_signature_details()
+            .total_signatures()
+            .saturating_mul(self.lamports_per_signature)
+            .saturating_add(
+                message
+                    .num_write_locks()
+                    .saturating_mul(self.lamports_per_write_lock),
+            );
+        Ok(FeeDetails::new(
+            transaction_fee,
+            compute_budget_limits.prioritization_fee(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::collections::HashSet,
+        trezoa_hash::Hash,
+        trezoa_instruction::Instruction,
+        trezoa_message::{v0, AddressLookupTableAccount, Message},
+        trezoa_pubkey::Pubkey,
+        trezoa_sdk_ids::ed25519_program,
+        trezoa_system_interface::instruction::transfer,
+    };
+
+    fn versioned_message(instructions: &[Instruction], payer: &Pubkey) -> VersionedMessage {
+        VersionedMessage::Legacy(Message::new(instructions, Some(payer)))
+    }
+
+    #[test]
+    fn test_signature_fee_only() {
+        let payer = Pubkey::new_unique();
+        let message = versioned_message(&[transfer(&payer, &Pubkey::new_unique(), 1)], &payer);
+        let fee_details = FeeStructure::default()
+            .calculate_fee_details(&message)
+            .unwrap();
+        assert_eq!(fee_details, FeeDetails::new(5_000, 0));
+
+        let sanitized_message = SanitizedMessage::try_from_legacy_message(
+            match message {
+                VersionedMessage::Legacy(message) => message,
+                VersionedMessage::V0(_) => unreachable!(),
+            },
+            &HashSet::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            FeeStructure::default()
+                .calculate_fee_details(&sanitized_message)
+                .unwrap(),
+            fee_details
+        );
+    }
+
+    #[test]
+    fn test_precompile_signatures() {
+        let payer = Pubkey::new_unique();
+        let precompile_ix = Instruction::new_with_bytes(ed25519_program::id(), &[2, 0], vec![]);
+        let message = versioned_message(&[precompile_ix], &payer);
+        let fee_details = FeeStructure::default()
+            .calculate_fee_details(&message)
+            .unwrap();
+        assert_eq!(fee_details.transaction_fee(), 3 * 5_000);
+    }
+
+    #[test]
+    fn test_prioritization_fee() {
+        let payer = Pubkey::new_unique();
+        let transfer_ix = transfer(&payer, &Pubkey::new_unique(), 1);
+
+        // explicit limit, fee is rounded up
+        let message = versioned_message(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+                transfer_ix.clone(),
+            ],
+            &payer,
+        );
+        let fee_details = FeeStructure::default()
+            .calculate_fee_details(&message)
+            .unwrap();
+        assert_eq!(fee_details, FeeDetails::new(5_000, 1));
+
+        // default limit for two builtin instructions
+        let message = versioned_message(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(MICRO_LAMPORTS_PER_LAMPORT),
+                transfer_ix.clone(),
+            ],
+            &payer,
+        );
+        let fee_details = FeeStructure::default()
+            .calculate_fee_details(&message)
+            .unwrap();
+        assert_eq!(
+            fee_details.prioritization_fee(),
+            u64::from(MAX_BUILTIN_ALLOCATION_COMPUTE_UNIT_LIMIT).saturating_mul(2)
+        );
+
+        // requested limit is capped
+        let message = versioned_message(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_limit(u32::MAX),
+                ComputeBudgetInstruction::set_compute_unit_price(MICRO_LAMPORTS_PER_LAMPORT),
+                transfer_ix,
+            ],
+            &payer,
+        );
+        let fee_details = FeeStructure::default()
+            .calculate_fee_details(&message)
+            .unwrap();
+        assert_eq!(
+            fee_details.prioritization_fee(),
+            u64::from(MAX_COMPUTE_UNIT_LIMIT)
+        );
+    }
+
+    #[test]
+    fn test_invalid_compute_budget_instructions() {
+        let payer = Pubkey::new_unique();
+        let message = versioned_message(
+            &[
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+                ComputeBudgetInstruction::set_compute_unit_price(2),
+            ],
+            &payer,
+        );
+        assert_eq!(
+            FeeStructure::default().calculate_fee_details(&message),
+            Err(TransactionError::DuplicateInstruction(1))
+        );
+
+        let message = versioned_message(
+            &[Instruction::new_with_bytes(
+                compute_budget::id(),
+                &[2, 0],
+                vec![],
+            )],
+            &payer,
+        );
+        assert_eq!(
+            FeeStructure::default().calculate_fee_details(&message),
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::InvalidInstructionData
+            ))
+        );
+    }
+
+    #[test]
+    fn test_write_lock_fee_includes_lookup_tables() {
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![recipient],
+        };
+        let message = VersionedMessage::V0(
+            v0::Message::try_compile(
+                &payer,
+                &[transfer(&payer, &recipient, 1)],
+                &[table],
+                Hash::default(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(message.num_write_locks(), 2);
+
+        let fee_structure = FeeStructure {
+            lamports_per_write_lock: 100,
+            ..FeeStructure::default()
+        };
+        assert_eq!(
+            fee_structure.calculate_fee_details(&message).unwrap(),
+            FeeDetails::new(5_000 + 2 * 100, 0)
+        );
+    }
+}
@@ -0,0 +1,332 @@
+//! A hash function interface shared by the Trezoa hasher crates.
+//!
+//! The `Hasher` types of `trezoa-sha256-hasher`, `trezoa-keccak-hasher` and
+//! `trezoa-blake3-hasher` implement [`Hasher`], so code such as Merkle trees
+//! can be generic over the hash function. The implementations work both
+//! on-chain, where they call the hashing syscalls, and off-chain. The
+//! `Hasher` of `trezoa-poseidon`, which fails on inputs that are not field
+//! elements, implements [`TryHasher`] instead.
+//!
+//! ```
+//! use trezoa_hash::{hasher::Hasher, Hash};
+//!
+//! /// Hashes two nodes of a Merkle tree with any of the hasher crates, for
+//! /// example `hash_pair::<trezoa_sha256_hasher::Hasher>(&left, &right)`.
+//! fn hash_pair<H: Hasher>(left: &Hash, right: &Hash) -> Hash {
+//!     H::hashv_once(&[left.as_ref(), right.as_ref()])
+//! }
+//! ```
+
+use {crate::Hash, core::convert::Infallible};
+
+/// A hash function producing a 32-byte [`Hash`].
+///
+/// Data can be hashed incrementally with [`Hasher::hash`] and
+/// [`Hasher::result`], or in one call with [`Hasher::hashv_once`]. On-chain,
+/// the incremental methods buffer the data until `result` is called, since
+/// each hashing syscall hashes all of its input at once.
+pub trait Hasher: Default {
+    /// Appends `val` to the data being hashed.
+    fn hash(&mut self, val: &[u8]);
+
+    /// Appends each of `vals` to the data being hashed.
+    fn hashv(&mut self, vals: &[&[u8]]) {
+        for val in vals {
+            self.hash(val);
+        }
+    }
+
+    /// Returns the hash of the appended data.
+    fn result(self) -> Hash;
+
+    /// Returns the hash of the concatenation of `vals`.
+    ///
+    /// On-chain this is a single syscall without buffering.
+    fn hashv_once(vals: &[&[u8]]) -> Hash {
+        let mut hasher = Self::default();
+        hasher.hashv(vals);
+        hasher.result()
+    }
+
+    /// Returns the hash of `val`.
+    fn hash_once(val: &[u8]) -> Hash {
+        Self::hashv_once(&[val])
+    }
+}
+
+/// A hash function producing a 32-byte [`Hash`] that can fail to hash some
+/// data, such as Poseidon, whose inputs must be field elements.
+///
+/// Every [`Hasher`] is a `TryHasher` that never fails, so code that only
+/// needs fallible hashing can be generic over both.
+pub trait TryHasher: Default {
+    /// Why the data could not be hashed.
+    type Error;
+
+    /// Appends `val` to the data being hashed.
+    fn hash(&mut self, val: &[u8]);
+
+    /// Appends each of `vals` to the data being hashed.
+    fn hashv(&mut self, vals: &[&[u8]]) {
+        for val in vals {
+            self.hash(val);
+        }
+    }
+
+    /// Returns the hash of the appended data, or why it could not be hashed.
+    fn try_result(self) -> Result<Hash, Self::Error>;
+
+    /// Returns the hash of the concatenation of `vals`, or why it could not
+    /// be hashed.
+    fn try_hashv_once(vals: &[&[u8]]) -> Result<Hash, Self::Error> {
+        let mut hasher = Self::default();
+        hasher.hashv(vals);
+        hasher.try_result()
+    }
+
+    /// Returns the hash of `val`, or why it could not be hashed.
+    fn try_hash_once(val: &[u8]) -> Result<Hash, Self::Error> {
+        Self::try_hashv_once(&[val])
+    }
+}
+
+impl<H: Hasher> TryHasher for H {
+    type Error = Infallible;
+
+    fn hash(&mut self, val: &[u8]) {
+        Hasher::hash(self, val);
+    }
+
+    fn hashv(&mut self, vals: &[&[u8]]) {
+        Hasher::hashv(self, vals);
+    }
+
+    fn try_result(self) -> Result<Hash, Self::Error> {
+        Ok(self.result())
+    }
+
+    fn try_hashv_once(vals: &[&[u8]]) -> Result<Hash, Self::Error> {
+        Ok(Self::hashv_once(vals))
+    }
+}
+
+/// Defines the `Hasher` type of a hasher crate and implements [`Hasher`],
+/// `std::io::Write` and the `digest` traits for it.
+///
+/// Off-chain, with the crate feature `feature` enabled (or always, if it is
+/// omitted), `Hasher` wraps the `hasher` type, which is updated with `update`
+/// and finalized into a `[u8; 32]` with `finalize`. On-chain, data is
+/// buffered and hashed by the `hashv` function, which calls the `syscall`
+/// hashing syscall.
+///
+/// The `std::io::Write` and `digest` implementations are enabled by the
+/// `std` and `digest` features of the calling crate, which must enable the
+/// features of the same name of this crate.
+///
+/// ```
+/// use {
+///     sha2::{Digest, Sha256},
+///     trezoa_hash::{hasher::Hasher as _, Hash},
+/// };
+///
+/// trezoa_hash::define_hasher! {
+///     syscall = "sol_sha256",
+///     hasher = Sha256,
+///     hashv = hashv,
+///     update = |hasher, val| hasher.update(val),
+///     finalize = |hasher| hasher.finalize().into(),
+/// }
+///
+/// pub fn hashv(vals: &[&[u8]]) -> Hash {
+///     let mut hasher = Hasher::default();
+///     hasher.hashv(vals);
+///     hasher.result()
+/// }
+///
+/// fn main() {
+///     let expected = Hash::new_from_array(Sha256::digest(b"data").into());
+///     assert_eq!(Hasher::hash_once(b"data"), expected);
+/// }
+/// ```
+#[doc(hidden)]
+#[macro_export]
+macro_rules! define_hasher {
+    (
+        $(feature = $feature:literal,)?
+        syscall = $syscall:literal,
+        hasher = $hasher:ty,
+        hashv = $hashv:path,
+        update = |$update_hasher:ident, $update_val:ident| $update:expr,
+        finalize = |$finalize_hasher:ident| $finalize:expr $(,)?
+    ) => {
+        #[cfg(all($(feature = $feature,)? not(any(target_os = "trezoa", target_arch = "bpf"))))]
+        #[derive(Clone, Default)]
+        pub struct Hasher {
+            hasher: $hasher,
+        }
+
+        #[cfg(all($(feature = $feature,)? not(any(target_os = "trezoa", target_arch = "bpf"))))]
+        impl Hasher {
+            #[inline(always)]
+            pub fn hash(&mut self, val: &[u8]) {
+                let $update_hasher = &mut self.hasher;
+                let $update_val = val;
+                $update;
+            }
+
+            #[inline(always)]
+            pub fn hashv(&mut self, vals: &[&[u8]]) {
+                for val in vals {
+                    self.hash(val);
+                }
+            }
+
+            #[inline(always)]
+            pub fn result(self) -> $crate::Hash {
+                let $finalize_hasher = self.hasher;
+                $crate::Hash::new_from_array($finalize)
+            }
+        }
+
+        #[doc = concat!("On-chain, data is buffered and hashed with a single `", $syscall, "` syscall by [`Hasher::result`].")]
+        #[cfg(any(target_os = "trezoa", target_arch = "bpf"))]
+        #[derive(Clone, Default)]
+        pub struct Hasher {
+            data: $crate::__private::Vec<u8>,
+        }
+
+        #[cfg(any(target_os = "trezoa", target_arch = "bpf"))]
+        impl Hasher {
+            #[inline(always)]
+            pub fn hash(&mut self, val: &[u8]) {
+                self.data.extend_from_slice(val);
+            }
+
+            #[inline(always)]
+            pub fn hashv(&mut self, vals: &[&[u8]]) {
+                for val in vals {
+                    self.hash(val);
+                }
+            }
+
+            #[inline(always)]
+            pub fn result(self) -> $crate::Hash {
+                $hashv(&[self.data.as_slice()])
+            }
+        }
+
+        #[cfg(any(all($(feature = $feature)?), target_os = "trezoa", target_arch = "bpf"))]
+        impl $crate::hasher::Hasher for Hasher {
+            fn hash(&mut self, val: &[u8]) {
+                self.hash(val);
+            }
+
+            fn result(self) -> $crate::Hash {
+                self.result()
+            }
+
+            fn hashv_once(vals: &[&[u8]]) -> $crate::Hash {
+                $hashv(vals)
+            }
+        }
+
+        #[cfg(all(feature = "std", any(all($(feature = $feature)?), target_os = "trezoa", target_arch = "bpf")))]
+        impl $crate::__private::io::Write for Hasher {
+            fn write(&mut self, buf: &[u8]) -> $crate::__private::io::Result<usize> {
+                self.hash(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> $crate::__private::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        #[cfg(all(feature = "digest", any(all($(feature = $feature)?), target_os = "trezoa", target_arch = "bpf")))]
+        const _: () = {
+            use $crate::__private::digest;
+
+            impl digest::HashMarker for Hasher {}
+
+            impl digest::OutputSizeUser for Hasher {
+                type OutputSize = digest::consts::U32;
+            }
+
+            impl digest::Update for Hasher {
+                fn update(&mut self, data: &[u8]) {
+                    self.hash(data);
+                }
+            }
+
+            impl digest::FixedOutput for Hasher {
+                fn finalize_into(self, out: &mut digest::Output<Self>) {
+                    out.copy_from_slice(self.result().as_ref());
+                }
+            }
+
+            impl digest::Reset for Hasher {
+                fn reset(&mut self) {
+                    *self = Self::default();
+                }
+            }
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, sha2::Digest, std::io};
+
+    mod sha256 {
+        use {
+            crate::Hash,
+            sha2::{Digest, Sha256},
+        };
+
+        crate::define_hasher! {
+            syscall = "sol_sha256",
+            hasher = Sha256,
+            hashv = hashv,
+            update = |hasher, val| hasher.update(val),
+            finalize = |hasher| hasher.finalize().into(),
+        }
+
+        pub fn hashv(vals: &[&[u8]]) -> Hash {
+            Hash::new_from_array(Sha256::digest(vals.concat()).into())
+        }
+    }
+
+    #[test]
+    fn test_hasher_trait() {
+        let vals: &[&[u8]] = &[b"gHiljKpq", b"lM890t"];
+        let expected = sha256::hashv(vals);
+
+        let mut hasher = sha256::Hasher::default();
+        hasher.hashv(vals);
+        assert_eq!(Hasher::result(hasher), expected);
+        assert_eq!(<sha256::Hasher as Hasher>::hashv_once(vals), expected);
+        assert_eq!(
+            <sha256::Hasher as Hasher>::hash_once(b"gHiljKpqlM890t"),
+            expected
+        );
+
+        let mut hasher = sha256::Hasher::default();
+        TryHasher::hashv(&mut hasher, vals);
+        assert_eq!(hasher.try_result(), Ok(expected));
+        assert_eq!(
+            <sha256::Hasher as TryHasher>::try_hashv_once(vals),
+            Ok(expected)
+        );
+        assert_eq!(
+            <sha256::Hasher as TryHasher>::try_hash_once(b"gHiljKpqlM890t"),
+            Ok(expected)
+        );
+
+        let mut hasher = sha256::Hasher::default();
+        io::copy(&mut &b"gHiljKpqlM890t"[..], &mut hasher).unwrap();
+        assert_eq!(hasher.result(), expected);
+
+        let digest = sha256::Hasher::digest(b"gHiljKpqlM890t");
+        assert_eq!(digest.as_slice(), expected.as_ref());
+    }
+}
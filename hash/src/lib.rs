@@ -16,13 +16,26 @@ use core::{
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "sanitize")]
 use trezoa_sanitize::Sanitize;
-#[cfg(feature = "borsh")]
+#[cfg(any(feature = "borsh", target_os = "trezoa", target_arch = "bpf"))]
 extern crate alloc;
 #[cfg(feature = "borsh")]
 use alloc::string::ToString;
 #[cfg(feature = "wincode")]
 use wincode::{SchemaRead, SchemaWrite};
 
+pub mod hasher;
+
+// hidden re-exports to make macros work
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(any(target_os = "trezoa", target_arch = "bpf"))]
+    pub use alloc::vec::Vec;
+    #[cfg(feature = "digest")]
+    pub use digest;
+    #[cfg(feature = "std")]
+    pub use std::io;
+}
+
 /// Size of a hash in bytes.
 pub const HASH_BYTES: usize = 32;
 /// Maximum string length of a base58 encoded hash.
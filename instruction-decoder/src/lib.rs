@@ -0,0 +1,600 @@
+//! Human-readable decoding of instructions for Trezoa's builtin programs.
+//!
+//! [`decode_instruction`] maps a program ID and instruction data to a typed
+//! [`KnownInstruction`], and names the accounts the instruction references
+//! according to the program's interface. The result implements [`Display`]
+//! for showing to users, and `Serialize` with the `serde` feature.
+//!
+//! The following programs are supported:
+//!
+//! - System program, as [`SystemInstruction`]
+//! - Vote program, as [`VoteInstruction`]
+//! - Compute budget program, as [`ComputeBudgetInstruction`]
+//! - Loader v4, as [`LoaderV4Instruction`]
+//! - Upgradeable BPF loader, as [`UpgradeableLoaderInstruction`]
+//! - Address lookup table program, as [`ProgramInstruction`]
+//! - Feature gate program, as [`FeatureGateInstruction`]
+//!
+//! # Examples
+//!
+//! ```
+//! use {
+//!     trezoa_instruction_decoder::{decode_instruction, KnownInstruction},
+//!     trezoa_pubkey::Pubkey,
+//!     trezoa_system_interface::instruction::{transfer, SystemInstruction},
+//! };
+//!
+//! let from = Pubkey::new_unique();
+//! let to = Pubkey::new_unique();
+//! let instruction = transfer(&from, &to, 42);
+//! let accounts: Vec<_> = instruction.accounts.iter().map(|meta| meta.pubkey).collect();
+//!
+//! let decoded = decode_instruction(&instruction.program_id, &instruction.data, &accounts)?;
+//! assert_eq!(
+//!     decoded.instruction,
+//!     KnownInstruction::System(SystemInstruction::Transfer { lamports: 42 })
+//! );
+//! assert_eq!(decoded.accounts[1].name, "recipient_account");
+//! println!("{decoded}");
+//! # Ok::<(), trezoa_instruction_decoder::DecodeError>(())
+//! ```
+//!
+//! [`Display`]: core::fmt::Display
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use {
+    core::fmt,
+    serde::de::DeserializeOwned,
+    trezoa_address::Address,
+    trezoa_bincode::limited_deserialize,
+    trezoa_message::compiled_instruction::CompiledInstruction,
+    trezoa_packet::PACKET_DATA_SIZE,
+    trezoa_sdk_ids::{
+        address_lookup_table, bpf_loader_upgradeable, compute_budget, feature, loader_v4,
+        system_program, vote,
+    },
+};
+pub use {
+    trezoa_address_lookup_table_interface::instruction::ProgramInstruction,
+    trezoa_compute_budget_interface::ComputeBudgetInstruction,
+    trezoa_loader_v3_interface::instruction::UpgradeableLoaderInstruction,
+    trezoa_loader_v4_interface::instruction::LoaderV4Instruction,
+    trezoa_system_interface::instruction::SystemInstruction,
+    trezoa_vote_interface::instruction::VoteInstruction,
+};
+
+/// Name given to accounts beyond those described by the program interface.
+pub const REMAINING_ACCOUNT_NAME: &str = "remaining_account";
+
+/// Errors produced while decoding an instruction.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The program is not one of the supported builtin programs.
+    UnknownProgram(Address),
+    /// The instruction data does not decode for the program.
+    InvalidInstructionData,
+    /// An account index of a compiled instruction is out of bounds.
+    AccountIndexOutOfBounds(u8),
+}
+
+impl core::error::Error for DecodeError {}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnknownProgram(program_id) => write!(f, "unknown program {program_id}"),
+            Self::InvalidInstructionData => f.write_str("invalid instruction data"),
+            Self::AccountIndexOutOfBounds(index) => {
+                write!(f, "account index {index} is out of bounds")
+            }
+        }
+    }
+}
+
+/// Instructions of the feature gate program.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum FeatureGateInstruction {
+    /// Revoke a pending feature activation.
+    ///
+    /// # Account references
+    ///   0. `[WRITE, SIGNER]` Feature account
+    ///   1. `[WRITE]` Incinerator
+    ///   2. `[]` System program
+    RevokePendingActivation,
+}
+
+/// A decoded instruction of one of the supported programs.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KnownInstruction {
+    System(SystemInstruction),
+    Vote(Box<VoteInstruction>),
+    ComputeBudget(ComputeBudgetInstruction),
+    LoaderV4(LoaderV4Instruction),
+    UpgradeableLoader(UpgradeableLoaderInstruction),
+    AddressLookupTable(ProgramInstruction),
+    FeatureGate(FeatureGateInstruction),
+}
+
+impl KnownInstruction {
+    /// Decodes the data of an instruction for `program_id`.
+    pub fn decode(program_id: &Address, data: &[u8]) -> Result<Self, DecodeError> {
+        let limit = PACKET_DATA_SIZE as u64;
+        let instruction = if system_program::check_id(program_id) {
+            Self::System(deserialize(data, limit)?)
+        } else if vote::check_id(program_id) {
+            Self::Vote(Box::new(deserialize(data, limit)?))
+        } else if compute_budget::check_id(program_id) {
+            Self::ComputeBudget(
+                ComputeBudgetInstruction::from_instruction_data(data)
+                    .ok_or(DecodeError::InvalidInstructionData)?,
+            )
+        } else if loader_v4::check_id(program_id) {
+            Self::LoaderV4(deserialize(data, limit)?)
+        } else if bpf_loader_upgradeable::check_id(program_id) {
+            Self::UpgradeableLoader(deserialize(data, limit)?)
+        } else if address_lookup_table::check_id(program_id) {
+            Self::AddressLookupTable(deserialize(data, limit)?)
+        } else if feature::check_id(program_id) {
+            match data.first() {
+                Some(0) => Self::FeatureGate(FeatureGateInstruction::RevokePendingActivation),
+                _ => return Err(DecodeError::InvalidInstructionData),
+            }
+        } else {
+            return Err(DecodeError::UnknownProgram(*program_id));
+        };
+        Ok(instruction)
+    }
+
+    /// Returns the human-readable name of the program.
+    pub fn program_name(&self) -> &'static str {
+        match self {
+            Self::System(_) => "System Program",
+            Self::Vote(_) => "Vote Program",
+            Self::ComputeBudget(_) => "Compute Budget Program",
+            Self::LoaderV4(_) => "Loader V4",
+            Self::UpgradeableLoader(_) => "Upgradeable BPF Loader",
+            Self::AddressLookupTable(_) => "Address Lookup Table Program",
+            Self::FeatureGate(_) => "Feature Gate Program",
+        }
+    }
+
+    /// Returns the instruction of the program, without the enum variant.
+    fn program_instruction(&self) -> &dyn fmt::Debug {
+        match self {
+            Self::System(instruction) => instruction,
+            Self::Vote(instruction) => instruction,
+            Self::ComputeBudget(instruction) => instruction,
+            Self::LoaderV4(instruction) => instruction,
+            Self::UpgradeableLoader(instruction) => instruction,
+            Self::AddressLookupTable(instruction) => instruction,
+            Self::FeatureGate(instruction) => instruction,
+        }
+    }
+
+    /// Returns the names of the accounts referenced by the instruction, in
+    /// order. Trailing optional accounts may be absent from an instruction.
+    pub fn account_names(&self) -> &'static [&'static str] {
+        match self {
+            Self::System(instruction) => system_account_names(instruction),
+            Self::Vote(instruction) => vote_account_names(instruction),
+            Self::ComputeBudget(_) => &[],
+            Self::LoaderV4(instruction) => loader_v4_account_names(instruction),
+            Self::UpgradeableLoader(instruction) => upgradeable_loader_account_names(instruction),
+            Self::AddressLookupTable(instruction) => {
+                address_lookup_table_account_names(instruction)
+            }
+            Self::FeatureGate(FeatureGateInstruction::RevokePendingActivation) => {
+                &["feature_account", "incinerator", "system_program"]
+            }
+        }
+    }
+}
+
+fn deserialize<T: DeserializeOwned>(data: &[u8], limit: u64) -> Result<T, DecodeError> {
+    limited_deserialize(data, limit).map_err(|_| DecodeError::InvalidInstructionData)
+}
+
+/// An account referenced by a decoded instruction.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NamedAccount {
+    pub name: &'static str,
+    pub address: Address,
+}
+
+/// An instruction decoded together with its named accounts.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedInstruction {
+    pub program_id: Address,
+    pub instruction: KnownInstruction,
+    pub accounts: Vec<NamedAccount>,
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {:?}",
+            self.instruction.program_name(),
+            self.instruction.program_instruction()
+        )?;
+        for account in &self.accounts {
+            write!(f, "\n  {}: {}", account.name, account.address)?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes an instruction of one of the supported programs, naming the
+/// `accounts` it references.
+pub fn decode_instruction(
+    program_id: &Address,
+    data: &[u8],
+    accounts: &[Address],
+) -> Result<DecodedInstruction, DecodeError> {
+    let instruction = KnownInstruction::decode(program_id, data)?;
+    let names = instruction.account_names();
+    let accounts = accounts
+        .iter()
+        .enumerate()
+        .map(|(index, address)| NamedAccount {
+            name: names.get(index).copied().unwrap_or(REMAINING_ACCOUNT_NAME),
+            address: *address,
+        })
+        .collect();
+    Ok(DecodedInstruction {
+        program_id: *program_id,
+        instruction,
+        accounts,
+    })
+}
+
+/// Decodes a compiled instruction of a message.
+///
+/// `account_keys` are the keys the instruction indexes into. For v0 messages
+/// these are the static account keys followed by the writable and then the
+/// readonly addresses loaded from lookup tables.
+pub fn decode_compiled_instruction(
+    instruction: &CompiledInstruction,
+    account_keys: &[Address],
+) -> Result<DecodedInstruction, DecodeError> {
+    let account_key = |index: u8| {
+        account_keys
+            .get(index as usize)
+            .copied()
+            .ok_or(DecodeError::AccountIndexOutOfBounds(index))
+    };
+    let program_id = account_key(instruction.program_id_index)?;
+    let accounts = instruction
+        .accounts
+        .iter()
+        .map(|index| account_key(*index))
+        .collect::<Result<Vec<_>, _>>()?;
+    decode_instruction(&program_id, &instruction.data, &accounts)
+}
+
+fn system_account_names(instruction: &SystemInstruction) -> &'static [&'static str] {
+    match instruction {
+        SystemInstruction::CreateAccount { .. } => &["funding_account", "new_account"],
+        SystemInstruction::Assign { .. } => &["assigned_account"],
+        SystemInstruction::Transfer { .. } => &["funding_account", "recipient_account"],
+        SystemInstruction::CreateAccountWithSeed { .. } => {
+            &["funding_account", "created_account", "base_account"]
+        }
+        SystemInstruction::AdvanceNonceAccount => &[
+            "nonce_account",
+            "recent_blockhashes_sysvar",
+            "nonce_authority",
+        ],
+        SystemInstruction::WithdrawNonceAccount(_) => &[
+            "nonce_account",
+            "recipient_account",
+            "recent_blockhashes_sysvar",
+            "rent_sysvar",
+            "nonce_authority",
+        ],
+        SystemInstruction::InitializeNonceAccount(_) => {
+            &["nonce_account", "recent_blockhashes_sysvar", "rent_sysvar"]
+        }
+        SystemInstruction::AuthorizeNonceAccount(_) => &["nonce_account", "nonce_authority"],
+        SystemInstruction::Allocate { .. } => &["new_account"],
+        SystemInstruction::AllocateWithSeed { .. } => &["allocated_account", "base_account"],
+        SystemInstruction::AssignWithSeed { .. } => &["assigned_account", "base_account"],
+        SystemInstruction::TransferWithSeed { .. } => &[
+            "funding_account",
+            "funding_base_account",
+            "recipient_account",
+        ],
+        SystemInstruction::UpgradeNonceAccount => &["nonce_account"],
+        SystemInstruction::CreateAccountAllowPrefund { .. } => &["new_account", "funding_account"],
+    }
+}
+
+fn vote_account_names(instruction: &VoteInstruction) -> &'static [&'static str] {
+    match instruction {
+        VoteInstruction::InitializeAccount(_) => &[
+            "vote_account",
+            "rent_sysvar",
+            "clock_sysvar",
+            "validator_identity",
+        ],
+        VoteInstruction::Authorize(..) => &["vote_account", "clock_sysvar", "authority"],
+        VoteInstruction::Vote(_) | VoteInstruction::VoteSwitch(..) => &[
+            "vote_account",
+            "slot_hashes_sysvar",
+            "clock_sysvar",
+            "vote_authority",
+        ],
+        VoteInstruction::Withdraw(_) => {
+            &["vote_account", "recipient_account", "withdraw_authority"]
+        }
+        VoteInstruction::UpdateValidatorIdentity => {
+            &["vote_account", "validator_identity", "withdraw_authority"]
+        }
+        VoteInstruction::UpdateCommission(_) | VoteInstruction::UpdateCommissionBps { .. } => {
+            &["vote_account", "withdraw_authority"]
+        }
+        VoteInstruction::AuthorizeChecked(_) => {
+            &["vote_account", "clock_sysvar", "authority", "new_authority"]
+        }
+        VoteInstruction::UpdateVoteState(_)
+        | VoteInstruction::UpdateVoteStateSwitch(..)
+        | VoteInstruction::CompactUpdateVoteState(_)
+        | VoteInstruction::CompactUpdateVoteStateSwitch(..)
+        | VoteInstruction::TowerSync(_)
+        | VoteInstruction::TowerSyncSwitch(..) => &["vote_account", "vote_authority"],
+        VoteInstruction::AuthorizeWithSeed(_) => {
+            &["vote_account", "clock_sysvar", "authority_base_account"]
+        }
+        VoteInstruction::AuthorizeCheckedWithSeed(_) => &[
+            "vote_account",
+            "clock_sysvar",
+            "authority_base_account",
+            "new_authority",
+        ],
+        VoteInstruction::InitializeAccountV2(_) => &["vote_account", "validator_identity"],
+        VoteInstruction::UpdateCommissionCollector(_) => &[
+            "vote_account",
+            "new_collector_account",
+            "withdraw_authority",
+        ],
+        VoteInstruction::DepositDelegatorRewards { .. } => &["vote_account", "source_account"],
+    }
+}
+
+fn loader_v4_account_names(instruction: &LoaderV4Instruction) -> &'static [&'static str] {
+    match instruction {
+        LoaderV4Instruction::Write { .. } | LoaderV4Instruction::Retract => {
+            &["program_account", "authority"]
+        }
+        LoaderV4Instruction::Copy { .. } => &["program_account", "authority", "source_program"],
+        LoaderV4Instruction::SetProgramLength { .. } => {
+            &["program_account", "authority", "recipient_account"]
+        }
+        LoaderV4Instruction::Deploy => &["program_account", "authority", "source_program"],
+        LoaderV4Instruction::TransferAuthority => {
+            &["program_account", "authority", "new_authority"]
+        }
+        LoaderV4Instruction::Finalize => &["program_account", "authority", "next_version"],
+    }
+}
+
+fn upgradeable_loader_account_names(
+    instruction: &UpgradeableLoaderInstruction,
+) -> &'static [&'static str] {
+    match instruction {
+        UpgradeableLoaderInstruction::InitializeBuffer
+        | UpgradeableLoaderInstruction::Write { .. } => &["buffer_account", "buffer_authority"],
+        UpgradeableLoaderInstruction::DeployWithMaxDataLen { .. } => &[
+            "payer",
+            "program_data_account",
+            "program_account",
+            "buffer_account",
+            "rent_sysvar",
+            "clock_sysvar",
+            "system_program",
+            "authority",
+        ],
+        UpgradeableLoaderInstruction::Upgrade => &[
+            "program_data_account",
+            "program_account",
+            "buffer_account",
+            "spill_account",
+            "rent_sysvar",
+            "clock_sysvar",
+            "authority",
+        ],
+        UpgradeableLoaderInstruction::SetAuthority
+        | UpgradeableLoaderInstruction::SetAuthorityChecked => {
+            &["account", "authority", "new_authority"]
+        }
+        UpgradeableLoaderInstruction::Close => &[
+            "closed_account",
+            "recipient_account",
+            "authority",
+            "program_account",
+        ],
+        UpgradeableLoaderInstruction::ExtendProgram { .. } => &[
+            "program_data_account",
+            "program_account",
+            "system_program",
+            "payer",
+        ],
+        UpgradeableLoaderInstruction::Migrate => {
+            &["program_data_account", "program_account", "authority"]
+        }
+        UpgradeableLoaderInstruction::ExtendProgramChecked { .. } => &[
+            "program_data_account",
+            "program_account",
+            "authority",
+            "system_program",
+            "payer",
+        ],
+    }
+}
+
+fn address_lookup_table_account_names(instruction: &ProgramInstruction) -> &'static [&'static str] {
+    match instruction {
+        ProgramInstruction::CreateLookupTable { .. } => &[
+            "lookup_table_account",
+            "authority",
+            "payer",
+            "system_program",
+        ],
+        ProgramInstruction::FreezeLookupTable | ProgramInstruction::DeactivateLookupTable => {
+            &["lookup_table_account", "authority"]
+        }
+        ProgramInstruction::ExtendLookupTable { .. } => &[
+            "lookup_table_account",
+            "authority",
+            "payer",
+            "system_program",
+        ],
+        ProgramInstruction::CloseLookupTable => {
+            &["lookup_table_account", "authority", "recipient_account"]
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        trezoa_instruction::Instruction,
+        trezoa_pubkey::Pubkey,
+        trezoa_system_interface::instruction::{advance_nonce_account, transfer},
+    };
+
+    fn addresses(instruction: &Instruction) -> Vec<Address> {
+        instruction
+            .accounts
+            .iter()
+            .map(|meta| meta.pubkey)
+            .collect()
+    }
+
+    #[test]
+    fn test_decode_system_instruction() {
+        let nonce_account = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let instruction = advance_nonce_account(&nonce_account, &authority);
+        let decoded = decode_instruction(
+            &instruction.program_id,
+            &instruction.data,
+            &addresses(&instruction),
+        )
+        .unwrap();
+        assert_eq!(
+            decoded.instruction,
+            KnownInstruction::System(SystemInstruction::AdvanceNonceAccount)
+        );
+        assert_eq!(
+            decoded.accounts[0],
+            NamedAccount {
+                name: "nonce_account",
+                address: nonce_account,
+            }
+        );
+        assert_eq!(
+            decoded.accounts[2],
+            NamedAccount {
+                name: "nonce_authority",
+                address: authority,
+            }
+        );
+        assert_eq!(
+            decoded.to_string(),
+            format!(
+                "System Program: AdvanceNonceAccount\n  nonce_account: \
+                 {nonce_account}\n  recent_blockhashes_sysvar: {}\n  nonce_authority: \
+                 {authority}",
+                instruction.accounts[1].pubkey
+            )
+        );
+    }
+
+    #[test]
+    fn test_decode_compute_budget_instruction() {
+        let instruction = ComputeBudgetInstruction::set_compute_unit_price(7);
+        let decoded = decode_instruction(&instruction.program_id, &instruction.data, &[]).unwrap();
+        assert_eq!(
+            decoded.instruction,
+            KnownInstruction::ComputeBudget(ComputeBudgetInstruction::SetComputeUnitPrice(7))
+        );
+        assert!(decoded.accounts.is_empty());
+    }
+
+    #[test]
+    fn test_decode_compiled_instruction() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let extra = Pubkey::new_unique();
+        let account_keys = [from, to, system_program::id(), extra];
+        let compiled = CompiledInstruction::new(
+            2,
+            &SystemInstruction::Transfer { lamports: 3 },
+            vec![0, 1, 3],
+        );
+        let decoded = decode_compiled_instruction(&compiled, &account_keys).unwrap();
+        let names: Vec<_> = decoded
+            .accounts
+            .iter()
+            .map(|account| account.name)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "funding_account",
+                "recipient_account",
+                REMAINING_ACCOUNT_NAME
+            ]
+        );
+
+        let out_of_bounds =
+            CompiledInstruction::new(2, &SystemInstruction::Transfer { lamports: 3 }, vec![0, 4]);
+        assert_eq!(
+            decode_compiled_instruction(&out_of_bounds, &account_keys),
+            Err(DecodeError::AccountIndexOutOfBounds(4))
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let program_id = Pubkey::new_unique();
+        assert_eq!(
+            decode_instruction(&program_id, &[], &[]),
+            Err(DecodeError::UnknownProgram(program_id))
+        );
+        assert_eq!(
+            decode_instruction(&system_program::id(), &[200, 0, 0, 0], &[]),
+            Err(DecodeError::InvalidInstructionData)
+        );
+        assert_eq!(
+            decode_instruction(&feature::id(), &[1], &[]),
+            Err(DecodeError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn test_serialize_json() {
+        let from = Pubkey::new_unique();
+        let instruction = transfer(&from, &Pubkey::new_unique(), 5);
+        let decoded = decode_instruction(
+            &instruction.program_id,
+            &instruction.data,
+            &addresses(&instruction),
+        )
+        .unwrap();
+        let json = serde_json::to_value(&decoded).unwrap();
+        assert_eq!(
+            json["instruction"],
+            serde_json::json!({ "System": { "Transfer": { "lamports": 5 } } })
+        );
+        assert_eq!(json["accounts"][0]["name"], "funding_account");
+    }
+}
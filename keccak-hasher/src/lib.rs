@@ -8,25 +8,13 @@
 use sha3::{Digest, Keccak256};
 pub use trezoa_hash::{Hash, ParseHashError, HASH_BYTES, MAX_BASE58_LEN};
 
-#[derive(Clone, Default)]
-#[cfg(all(feature = "sha3", not(any(target_os = "trezoa", target_arch = "bpf"))))]
-pub struct Hasher {
-    hasher: Keccak256,
-}
-
-#[cfg(all(feature = "sha3", not(any(target_os = "trezoa", target_arch = "bpf"))))]
-impl Hasher {
-    pub fn hash(&mut self, val: &[u8]) {
-        self.hasher.update(val);
-    }
-    pub fn hashv(&mut self, vals: &[&[u8]]) {
-        for val in vals {
-            self.hash(val);
-        }
-    }
-    pub fn result(self) -> Hash {
-        Hash::new_from_array(self.hasher.finalize().into())
-    }
+trezoa_hash::define_hasher! {
+    feature = "sha3",
+    syscall = "sol_keccak256",
+    hasher = Keccak256,
+    hashv = hashv,
+    update = |hasher, val| hasher.update(val),
+    finalize = |hasher| hasher.finalize().into(),
 }
 
 /// Return a Keccak256 hash for the given data.
@@ -69,3 +57,43 @@ pub fn hashv(vals: &[&[u8]]) -> Hash {
 pub fn hash(val: &[u8]) -> Hash {
     hashv(&[val])
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, trezoa_hash::hasher::Hasher as _};
+
+    #[test]
+    fn test_hashv() {
+        // Keccak-256, not the standardized SHA3-256
+        assert_eq!(
+            hash(&[]).to_bytes(),
+            [
+                0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7,
+                0x03, 0xc0, 0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04,
+                0x5d, 0x85, 0xa4, 0x70,
+            ]
+        );
+
+        let val = "gHiljKpq";
+        let val_hash = hash(val.as_bytes());
+        let ext = "lM890t";
+        let ext_hash = hashv(&[val_hash.as_ref(), ext.as_bytes()]);
+        let hash_ext = [val_hash.as_ref(), ext.as_bytes()].concat();
+        assert_eq!(ext_hash, hash(&hash_ext));
+    }
+
+    #[test]
+    fn test_hasher_known_answer() {
+        let expected = Hash::new_from_array([
+            0x4e, 0x03, 0x65, 0x7a, 0xea, 0x45, 0xa9, 0x4f, 0xc7, 0xd4, 0x7b, 0xa8, 0x26, 0xc8,
+            0xd6, 0x67, 0xc0, 0xd1, 0xe6, 0xe3, 0x3a, 0x64, 0xa0, 0x36, 0xec, 0x44, 0xf5, 0x8f,
+            0xa1, 0x2d, 0x6c, 0x45,
+        ]);
+        assert_eq!(hash(b"abc"), expected);
+
+        let mut hasher = Hasher::default();
+        hasher.hashv(&[b"a", b"bc"]);
+        assert_eq!(hasher.result(), expected);
+        assert_eq!(Hasher::hashv_once(&[b"a", b"bc"]), expected);
+    }
+}
@@ -0,0 +1,571 @@
+//! Password-protected keystore files for a [`Keypair`].
+//!
+//! A keystore is a versioned JSON document, modelled on the Ethereum v3
+//! keystore, that holds the secret key encrypted with XChaCha20-Poly1305
+//! under a key derived from a password with scrypt:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "pubkey": "<base58 address>",
+//!   "crypto": {
+//!     "cipher": "xchacha20-poly1305",
+//!     "ciphertext": "<hex>",
+//!     "nonce": "<hex>",
+//!     "kdf": "scrypt",
+//!     "kdfparams": { "log_n": 17, "r": 8, "p": 1, "salt": "<hex>" }
+//!   }
+//! }
+//! ```
+//!
+//! The pubkey is stored in the clear so that a keystore can be identified
+//! without its password. It is authenticated as associated data, so that it
+//! cannot be swapped for another without decryption failing.
+//!
+//! [`EncryptedKeypair`] implements [`EncodableKey`], so keystore files are
+//! read and written like plaintext keypair files.
+
+use {
+    crate::{read_keypair_file, Keypair},
+    chacha20poly1305::{
+        aead::{Aead, KeyInit, Payload},
+        Key, XChaCha20Poly1305, XNonce,
+    },
+    core::fmt,
+    serde_derive::{Deserialize, Serialize},
+    trezoa_address::Address,
+    trezoa_signer::{EncodableKey, EncodableKeypair, Signer},
+    zeroize::Zeroizing,
+    std::{
+        error,
+        fs::{self, OpenOptions},
+        io::{Read, Write},
+        path::Path,
+    },
+};
+
+/// The keystore format version written by this crate.
+pub const KEYSTORE_VERSION: u32 = 1;
+
+const CIPHER: &str = "xchacha20-poly1305";
+const KDF: &str = "scrypt";
+const KEY_LENGTH: usize = 32;
+const NONCE_LENGTH: usize = 24;
+const SALT_LENGTH: usize = 32;
+
+#[derive(Debug)]
+pub enum KeystoreError {
+    /// The keystore was written by an unknown version of the format.
+    UnsupportedVersion(u32),
+    UnsupportedCipher(String),
+    UnsupportedKdf(String),
+    InvalidKdfParams,
+    /// A salt, nonce or ciphertext has the wrong length.
+    InvalidLength,
+    /// The password is wrong or the keystore is corrupted.
+    DecryptionFailed,
+    /// The decrypted secret key does not belong to the stored pubkey.
+    PubkeyMismatch,
+    Hex(hex::FromHexError),
+    Json(serde_json::Error),
+}
+
+impl error::Error for KeystoreError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Hex(e) => Some(e),
+            Self::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported keystore version {version}")
+            }
+            Self::UnsupportedCipher(cipher) => write!(f, "unsupported keystore cipher {cipher}"),
+            Self::UnsupportedKdf(kdf) => write!(f, "unsupported keystore kdf {kdf}"),
+            Self::InvalidKdfParams => f.write_str("invalid keystore kdf parameters"),
+            Self::InvalidLength => f.write_str("keystore field has the wrong length"),
+            Self::DecryptionFailed => {
+                f.write_str("failed to decrypt keystore: wrong password or corrupted data")
+            }
+            Self::PubkeyMismatch => f.write_str("decrypted key does not match keystore pubkey"),
+            Self::Hex(e) => write!(f, "invalid hex in keystore: {e}"),
+            Self::Json(e) => write!(f, "invalid keystore json: {e}"),
+        }
+    }
+}
+
+impl From<hex::FromHexError> for KeystoreError {
+    fn from(e: hex::FromHexError) -> Self {
+        Self::Hex(e)
+    }
+}
+
+impl From<serde_json::Error> for KeystoreError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// Cost parameters of the scrypt key derivation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScryptParams {
+    /// Base two logarithm of the CPU/memory cost.
+    pub log_n: u8,
+    /// Block size.
+    pub r: u32,
+    /// Parallelization.
+    pub p: u32,
+}
+
+impl ScryptParams {
+    /// The largest `log_n` accepted when deriving a key.
+    pub const MAX_LOG_N: u8 = 20;
+    /// The largest `r` accepted when deriving a key.
+    pub const MAX_R: u32 = 16;
+    /// The largest `p` accepted when deriving a key.
+    pub const MAX_P: u32 = 16;
+}
+
+impl Default for ScryptParams {
+    /// The parameters recommended by the scrypt crate, which take on the
+    /// order of a second and 128 MiB of memory to derive a key.
+    fn default() -> Self {
+        Self {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct KdfParams {
+    #[serde(flatten)]
+    scrypt: ScryptParams,
+    salt: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Crypto {
+    cipher: String,
+    ciphertext: String,
+    nonce: String,
+    kdf: String,
+    kdfparams: KdfParams,
+}
+
+/// A [`Keypair`] encrypted with a password.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedKeypair {
+    version: u32,
+    #[serde(with = "pubkey_string")]
+    pubkey: Address,
+    crypto: Crypto,
+}
+
+impl EncryptedKeypair {
+    /// Encrypts `keypair` with `password` using the default scrypt parameters.
+    pub fn encrypt(keypair: &Keypair, password: &[u8]) -> Result<Self, KeystoreError> {
+        Self::encrypt_with_params(keypair, password, ScryptParams::default())
+    }
+
+    /// Encrypts `keypair` with `password` using the given scrypt parameters.
+    pub fn encrypt_with_params(
+        keypair: &Keypair,
+        password: &[u8],
+        params: ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        let pubkey = keypair.pubkey();
+        let salt = rand::random::<[u8; SALT_LENGTH]>();
+        let nonce = rand::random::<[u8; NONCE_LENGTH]>();
+        let cipher = cipher(password, &salt, params)?;
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: keypair.secret_bytes(),
+                    aad: pubkey.as_ref(),
+                },
+            )
+            .expect("secret key is within the cipher's plaintext limit");
+        Ok(Self {
+            version: KEYSTORE_VERSION,
+            pubkey,
+            crypto: Crypto {
+                cipher: CIPHER.to_string(),
+                ciphertext: hex::encode(ciphertext),
+                nonce: hex::encode(nonce),
+                kdf: KDF.to_string(),
+                kdfparams: KdfParams {
+                    scrypt: params,
+                    salt: hex::encode(salt),
+                },
+            },
+        })
+    }
+
+    /// Decrypts the keypair with `password`.
+    pub fn decrypt(&self, password: &[u8]) -> Result<Keypair, KeystoreError> {
+        if self.version != KEYSTORE_VERSION {
+            return Err(KeystoreError::UnsupportedVersion(self.version));
+        }
+        if self.crypto.cipher != CIPHER {
+            return Err(KeystoreError::UnsupportedCipher(self.crypto.cipher.clone()));
+        }
+        if self.crypto.kdf != KDF {
+            return Err(KeystoreError::UnsupportedKdf(self.crypto.kdf.clone()));
+        }
+        let salt = hex::decode(&self.crypto.kdfparams.salt)?;
+        let nonce: [u8; NONCE_LENGTH] = hex::decode(&self.crypto.nonce)?
+            .try_into()
+            .map_err(|_| KeystoreError::InvalidLength)?;
+        let ciphertext = hex::decode(&self.crypto.ciphertext)?;
+
+        let cipher = cipher(password, &salt, self.crypto.kdfparams.scrypt)?;
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    XNonce::from_slice(&nonce),
+                    Payload {
+                        msg: &ciphertext,
+                        aad: self.pubkey.as_ref(),
+                    },
+                )
+                .map_err(|_| KeystoreError::DecryptionFailed)?,
+        );
+        let secret_key: Zeroizing<[u8; Keypair::SECRET_KEY_LENGTH]> = Zeroizing::new(
+            plaintext
+                .as_slice()
+                .try_into()
+                .map_err(|_| KeystoreError::InvalidLength)?,
+        );
+        let keypair = Keypair::new_from_array(*secret_key);
+        if keypair.pubkey() != self.pubkey {
+            return Err(KeystoreError::PubkeyMismatch);
+        }
+        Ok(keypair)
+    }
+
+    /// Returns the pubkey of the encrypted keypair, which is readable without
+    /// the password.
+    pub fn pubkey(&self) -> &Address {
+        &self.pubkey
+    }
+
+    /// Returns the keystore format version.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+}
+
+impl EncodableKey for EncryptedKeypair {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>> {
+        Ok(serde_json::from_reader(reader).map_err(KeystoreError::from)?)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<String, Box<dyn error::Error>> {
+        let json = serde_json::to_string_pretty(self).map_err(KeystoreError::from)?;
+        writer.write_all(json.as_bytes())?;
+        Ok(json)
+    }
+}
+
+impl EncodableKeypair for EncryptedKeypair {
+    type Pubkey = Address;
+
+    fn encodable_pubkey(&self) -> Self::Pubkey {
+        self.pubkey
+    }
+}
+
+/// Reads a keystore file and decrypts its keypair with `password`.
+pub fn read_encrypted_keypair_file<F: AsRef<Path>>(
+    path: F,
+    password: &[u8],
+) -> Result<Keypair, Box<dyn error::Error>> {
+    Ok(EncryptedKeypair::read_from_file(path)?.decrypt(password)?)
+}
+
+/// Encrypts `keypair` with `password` and writes it to a keystore file.
+pub fn write_encrypted_keypair_file<F: AsRef<Path>>(
+    keypair: &Keypair,
+    password: &[u8],
+    outfile: F,
+) -> Result<String, Box<dyn error::Error>> {
+    EncryptedKeypair::encrypt(keypair, password)?.write_to_file(outfile)
+}
+
+/// Replaces a plaintext keypair file with a keystore encrypted with
+/// `password`, and returns the pubkey of the keypair.
+///
+/// The keystore is written to a new file next to the keypair file, synced to
+/// disk and then renamed over it, so the plaintext file is left untouched if
+/// anything fails. Fails without touching anything if the temporary file
+/// `<path>.tmp` already exists.
+pub fn migrate_keypair_file<F: AsRef<Path>>(
+    path: F,
+    password: &[u8],
+    params: ScryptParams,
+) -> Result<Address, Box<dyn error::Error>> {
+    let path = path.as_ref();
+    let keypair = read_keypair_file(path)?;
+    let encrypted = EncryptedKeypair::encrypt_with_params(&keypair, password, params)?;
+
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    let mut tmp_file = {
+        #[cfg(not(unix))]
+        {
+            OpenOptions::new()
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            OpenOptions::new().mode(0o600)
+        }
+    }
+    .write(true)
+    .create_new(true)
+    .open(&tmp_path)?;
+
+    let result = encrypted
+        .write(&mut tmp_file)
+        .and_then(|_| Ok(tmp_file.sync_all()?));
+    drop(tmp_file);
+    if let Err(e) = result.and_then(|()| Ok(fs::rename(&tmp_path, path)?)) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    sync_parent_dir(path)?;
+    Ok(encrypted.pubkey)
+}
+
+/// Syncs the directory containing `path`, so that a rename into it is
+/// durable.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+fn cipher(
+    password: &[u8],
+    salt: &[u8],
+    params: ScryptParams,
+) -> Result<XChaCha20Poly1305, KeystoreError> {
+    if salt.len() != SALT_LENGTH {
+        return Err(KeystoreError::InvalidLength);
+    }
+    // The parameters are read from the keystore, so bound the work and memory
+    // an untrusted file can make us spend.
+    if params.log_n > ScryptParams::MAX_LOG_N
+        || params.r > ScryptParams::MAX_R
+        || params.p > ScryptParams::MAX_P
+    {
+        return Err(KeystoreError::InvalidKdfParams);
+    }
+    let params = scrypt::Params::new(params.log_n, params.r, params.p, KEY_LENGTH)
+        .map_err(|_| KeystoreError::InvalidKdfParams)?;
+    let mut key = Zeroizing::new([0u8; KEY_LENGTH]);
+    scrypt::scrypt(password, salt, &params, &mut *key)
+        .map_err(|_| KeystoreError::InvalidKdfParams)?;
+    Ok(XChaCha20Poly1305::new(Key::from_slice(&*key)))
+}
+
+mod pubkey_string {
+    use {
+        serde::{de, Deserialize, Deserializer, Serializer},
+        trezoa_address::Address,
+        std::str::FromStr,
+    };
+
+    pub fn serialize<S: Serializer>(pubkey: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Address::from_str(&s).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::write_keypair_file};
+
+    // Cheap parameters so that the tests run quickly.
+    const TEST_PARAMS: ScryptParams = ScryptParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    fn tmp_file_path(name: &str) -> String {
+        let out_dir = std::env::var("FARF_DIR").unwrap_or_else(|_| "farf".to_string());
+        format!("{}/tmp/{}-{}", out_dir, name, Keypair::new().pubkey())
+    }
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let keypair = Keypair::new();
+        let encrypted =
+            EncryptedKeypair::encrypt_with_params(&keypair, b"hunter2", TEST_PARAMS).unwrap();
+        assert_eq!(encrypted.pubkey(), &keypair.pubkey());
+        assert_eq!(encrypted.encodable_pubkey(), keypair.pubkey());
+        assert_eq!(encrypted.version(), KEYSTORE_VERSION);
+        assert_eq!(encrypted.decrypt(b"hunter2").unwrap(), keypair);
+        assert!(matches!(
+            encrypted.decrypt(b"hunter3"),
+            Err(KeystoreError::DecryptionFailed)
+        ));
+    }
+
+    #[test]
+    fn test_read_write() {
+        let keypair = Keypair::new();
+        let encrypted =
+            EncryptedKeypair::encrypt_with_params(&keypair, b"password", TEST_PARAMS).unwrap();
+        let mut buffer = vec![];
+        let json = encrypted.write(&mut buffer).unwrap();
+        assert_eq!(json.as_bytes(), buffer);
+
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], 1);
+        assert_eq!(value["pubkey"], keypair.pubkey().to_string());
+        assert_eq!(value["crypto"]["cipher"], "xchacha20-poly1305");
+        assert_eq!(value["crypto"]["kdf"], "scrypt");
+        assert_eq!(value["crypto"]["kdfparams"]["log_n"], 4);
+        assert!(!json.contains(&format!("{:?}", keypair.secret_bytes())));
+
+        let read = EncryptedKeypair::read(&mut buffer.as_slice()).unwrap();
+        assert_eq!(read, encrypted);
+        assert_eq!(read.decrypt(b"password").unwrap(), keypair);
+    }
+
+    #[test]
+    fn test_tampered_keystore() {
+        let keypair = Keypair::new();
+        let encrypted =
+            EncryptedKeypair::encrypt_with_params(&keypair, b"password", TEST_PARAMS).unwrap();
+
+        let mut swapped = encrypted.clone();
+        swapped.pubkey = Address::new_unique();
+        assert!(matches!(
+            swapped.decrypt(b"password"),
+            Err(KeystoreError::DecryptionFailed)
+        ));
+
+        let mut future = encrypted.clone();
+        future.version = 2;
+        assert!(matches!(
+            future.decrypt(b"password"),
+            Err(KeystoreError::UnsupportedVersion(2))
+        ));
+
+        let mut argon2 = encrypted.clone();
+        argon2.crypto.kdf = "argon2id".to_string();
+        assert!(matches!(
+            argon2.decrypt(b"password"),
+            Err(KeystoreError::UnsupportedKdf(_))
+        ));
+
+        let mut invalid_params = encrypted.clone();
+        invalid_params.crypto.kdfparams.scrypt.r = 0;
+        assert!(matches!(
+            invalid_params.decrypt(b"password"),
+            Err(KeystoreError::InvalidKdfParams)
+        ));
+    }
+
+    #[test]
+    fn test_kdf_params_bounds() {
+        let keypair = Keypair::new();
+        let encrypted =
+            EncryptedKeypair::encrypt_with_params(&keypair, b"password", TEST_PARAMS).unwrap();
+
+        for scrypt in [
+            ScryptParams {
+                log_n: 63,
+                ..TEST_PARAMS
+            },
+            ScryptParams {
+                log_n: ScryptParams::MAX_LOG_N + 1,
+                ..TEST_PARAMS
+            },
+            ScryptParams {
+                r: ScryptParams::MAX_R + 1,
+                ..TEST_PARAMS
+            },
+            ScryptParams {
+                p: u32::MAX,
+                ..TEST_PARAMS
+            },
+        ] {
+            let mut expensive = encrypted.clone();
+            expensive.crypto.kdfparams.scrypt = scrypt;
+            assert!(matches!(
+                expensive.decrypt(b"password"),
+                Err(KeystoreError::InvalidKdfParams)
+            ));
+            assert!(matches!(
+                EncryptedKeypair::encrypt_with_params(&keypair, b"password", scrypt),
+                Err(KeystoreError::InvalidKdfParams)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_migrate_keypair_file() {
+        let path = tmp_file_path("test_migrate_keypair_file.json");
+        let keypair = Keypair::new();
+        write_keypair_file(&keypair, &path).unwrap();
+
+        let pubkey = migrate_keypair_file(&path, b"password", TEST_PARAMS).unwrap();
+        assert_eq!(pubkey, keypair.pubkey());
+        assert!(read_keypair_file(&path).is_err());
+        assert_eq!(
+            read_encrypted_keypair_file(&path, b"password").unwrap(),
+            keypair
+        );
+
+        #[cfg(unix)]
+        {
+            use std::{fs::File, os::unix::fs::PermissionsExt};
+            let mode = File::open(&path)
+                .unwrap()
+                .metadata()
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // A keystore is not a plaintext keypair file
+        assert!(migrate_keypair_file(&path, b"password", TEST_PARAMS).is_err());
+        fs::remove_file(&path).unwrap();
+
+        // A leftover temporary file is neither reused nor removed
+        write_keypair_file(&keypair, &path).unwrap();
+        let tmp_path = format!("{path}.tmp");
+        fs::write(&tmp_path, b"leftover").unwrap();
+        assert!(migrate_keypair_file(&path, b"password", TEST_PARAMS).is_err());
+        assert_eq!(read_keypair_file(&path).unwrap(), keypair);
+        assert_eq!(fs::read(&tmp_path).unwrap(), b"leftover");
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&tmp_path).unwrap();
+    }
+}
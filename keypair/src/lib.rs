@@ -2,7 +2,7 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 use {
     ed25519_dalek::Signer as DalekSigner,
-    trezoa_seed_phrase::generate_seed_from_seed_phrase_and_passphrase,
+    trezoa_seed_phrase::{generate_seed_from_seed_phrase_and_passphrase, validate_seed_phrase},
     trezoa_signer::SignerError,
     std::{
         error,
@@ -16,6 +16,8 @@ pub use {
     trezoa_signer::{EncodableKey, EncodableKeypair, Signer},
 };
 
+#[cfg(feature = "keystore")]
+pub mod keystore;
 #[cfg(feature = "seed-derivable")]
 pub mod seed_derivable;
 pub mod signable;
@@ -244,10 +246,17 @@ pub fn keypair_from_seed(seed: &[u8]) -> Result<Keypair, Box<dyn error::Error>>
     Ok(Keypair(ed25519_dalek::SigningKey::from(secret_key)))
 }
 
+/// Constructs a `Keypair` from a BIP39 seed phrase and passphrase
+///
+/// Returns an error if the seed phrase is not a valid mnemonic, rather than
+/// deriving a key from the mistyped phrase. The key is derived from the
+/// phrase exactly as given, so a phrase that differs from its normalized form
+/// in case or whitespace still derives the key it always has.
 pub fn keypair_from_seed_phrase_and_passphrase(
     seed_phrase: &str,
     passphrase: &str,
 ) -> Result<Keypair, Box<dyn core::error::Error>> {
+    validate_seed_phrase(seed_phrase)?;
     keypair_from_seed(&generate_seed_from_seed_phrase_and_passphrase(
         seed_phrase,
         passphrase,
@@ -420,6 +429,31 @@ mod tests {
         let keypair =
             keypair_from_seed_phrase_and_passphrase(mnemonic.phrase(), passphrase).unwrap();
         assert_eq!(keypair.pubkey(), expected_keypair.pubkey());
+
+        // a phrase that is only valid once normalized is accepted, and the
+        // key is still derived from the phrase as given
+        let phrase =
+            " Legal  winner thank year wave sausage worth useful legal WINNER thank yellow\n";
+        let expected_keypair = keypair_from_seed(&generate_seed_from_seed_phrase_and_passphrase(
+            phrase, passphrase,
+        ))
+        .unwrap();
+        assert_eq!(
+            keypair_from_seed_phrase_and_passphrase(phrase, passphrase).unwrap(),
+            expected_keypair
+        );
+        assert_ne!(
+            keypair_from_seed_phrase_and_passphrase(phrase.trim(), passphrase).unwrap(),
+            expected_keypair
+        );
+
+        // the last two words are swapped, which breaks the checksum
+        assert!(keypair_from_seed_phrase_and_passphrase(
+            "legal winner thank year wave sausage worth useful legal winner yellow thank",
+            passphrase,
+        )
+        .is_err());
+        assert!(keypair_from_seed_phrase_and_passphrase("not a seed phrase", passphrase).is_err());
     }
 
     #[test]
@@ -2,7 +2,7 @@
 
 use {
     crate::{keypair_from_seed, keypair_from_seed_phrase_and_passphrase, Keypair},
-    ed25519_dalek_bip32::Error as Bip32Error,
+    ed25519_dalek_bip32::{Error as Bip32Error, ExtendedSigningKey},
     trezoa_derivation_path::DerivationPath,
     trezoa_seed_derivable::SeedDerivable,
     std::error,
@@ -47,3 +47,81 @@ fn bip32_derived_keypair(
         .and_then(|extended| extended.derive(&derivation_path))?;
     Ok(Keypair(extended.signing_key))
 }
+
+/// Derives the Keypair of each derivation path from a single seed, sharing
+/// the master key between paths.
+///
+/// ```
+/// # use {
+/// #     trezoa_derivation_path::DerivationPath, trezoa_keypair::seed_derivable::*,
+/// #     trezoa_signer::Signer,
+/// # };
+/// let seed = [7u8; 64];
+/// // m/44'/501'/0'/0' to m/44'/501'/9'/0'
+/// let paths = DerivationPath::new_bip44_accounts(0..10, Some(0));
+/// for derived in keypairs_from_seed_and_derivation_paths(&seed, paths).unwrap() {
+///     let (derivation_path, keypair) = derived.unwrap();
+///     println!("{derivation_path:?}: {}", keypair.pubkey());
+/// }
+/// ```
+pub fn keypairs_from_seed_and_derivation_paths<I: IntoIterator<Item = DerivationPath>>(
+    seed: &[u8],
+    derivation_paths: I,
+) -> Result<DerivedKeypairs<I::IntoIter>, Box<dyn error::Error>> {
+    let master = ExtendedSigningKey::from_seed(seed).map_err(|err| err.to_string())?;
+    Ok(DerivedKeypairs {
+        master,
+        derivation_paths: derivation_paths.into_iter(),
+    })
+}
+
+/// Iterator over the Keypairs derived by
+/// [`keypairs_from_seed_and_derivation_paths`].
+pub struct DerivedKeypairs<I> {
+    master: ExtendedSigningKey,
+    derivation_paths: I,
+}
+
+impl<I: Iterator<Item = DerivationPath>> Iterator for DerivedKeypairs<I> {
+    type Item = Result<(DerivationPath, Keypair), Box<dyn error::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let derivation_path = self.derivation_paths.next()?;
+        Some(
+            self.master
+                .derive(&derivation_path)
+                .map(|extended| (derivation_path, Keypair(extended.signing_key)))
+                .map_err(|err| err.to_string().into()),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.derivation_paths.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, trezoa_signer::Signer};
+
+    #[test]
+    fn test_keypairs_from_seed_and_derivation_paths() {
+        let seed = [3u8; 64];
+        let paths: Vec<_> = DerivationPath::new_bip44_accounts(0..3, Some(0))
+            .chain(DerivationPath::new_bip44_accounts(0..3, None))
+            .collect();
+
+        let derived: Vec<_> = keypairs_from_seed_and_derivation_paths(&seed, paths.clone())
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(derived.len(), 6);
+        for ((derivation_path, keypair), expected_path) in derived.iter().zip(&paths) {
+            assert_eq!(derivation_path, expected_path);
+            let expected =
+                keypair_from_seed_and_derivation_path(&seed, Some(expected_path.clone())).unwrap();
+            assert_eq!(keypair.pubkey(), expected.pubkey());
+        }
+        assert_ne!(derived[0].1.pubkey(), derived[3].1.pubkey());
+    }
+}
@@ -0,0 +1,235 @@
+//! Merkle trees and proofs over [`Hash`].
+//!
+//! Trees are generic over a [`Hasher`], usually `trezoa_sha256_hasher::Hasher`
+//! or `trezoa_keccak_hasher::Hasher`, so the same code builds a tree
+//! off-chain and verifies its proofs on-chain through the hashing syscalls.
+//!
+//! # Tree layout
+//!
+//! Leaves are hashed as `H(0x00 || data)` and inner nodes as
+//! `H(0x01 || left || right)`, so a leaf can never be passed off as an inner
+//! node. Each level pairs up adjacent nodes from the left; the last node of a
+//! level with an odd number of nodes has no sibling and is moved up to the
+//! next level unchanged. The root of the tree is
+//! `H(0x02 || leaf_count || node)`, where `leaf_count` is the number of leaves
+//! as a little-endian `u32` and `node` the single node of the last level, so
+//! a proof is bound to the number of leaves as well as to its index. The root
+//! of a tree without leaves is `Hash::default()`.
+//!
+//! # Verification cost
+//!
+//! Verifying a proof with [`verify_proof`] computes one hash per sibling and
+//! one for the root, and a valid proof for a tree of `n` leaves has at most
+//! [`depth`]`(n)` siblings. [`verify_multiproof`] computes one hash per node
+//! on the paths from the proven leaves to the root, at most
+//! `k * depth(n) + 1` hashes for `k` leaves. Neither allocates, and proofs of
+//! the wrong length are rejected before any hashing.
+//!
+//! Proofs are built off-chain with [`MerkleTree`], and
+//! [`partition::PartitionedMerkleTree`] splits large trees into partitions
+//! keyed by address.
+#![no_std]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "partition")]
+pub mod partition;
+#[cfg(feature = "alloc")]
+mod tree;
+
+#[cfg(feature = "alloc")]
+pub use tree::{MerkleTree, MultiProof, Proof};
+use trezoa_hash::{hasher::Hasher, Hash};
+
+/// Prefix of the hashed data of a leaf.
+pub const LEAF_PREFIX: &[u8] = &[0];
+/// Prefix of the hashed data of an inner node.
+pub const NODE_PREFIX: &[u8] = &[1];
+/// Prefix of the hashed data of the root.
+pub const ROOT_PREFIX: &[u8] = &[2];
+
+/// Hash the data of a leaf.
+pub fn hash_leaf<H: Hasher>(data: &[u8]) -> Hash {
+    H::hashv_once(&[LEAF_PREFIX, data])
+}
+
+/// Hash an inner node from its children.
+pub fn hash_node<H: Hasher>(left: &Hash, right: &Hash) -> Hash {
+    H::hashv_once(&[NODE_PREFIX, left.as_ref(), right.as_ref()])
+}
+
+/// Hash the root of a tree of `leaf_count` leaves from the single node of its
+/// last level.
+pub fn hash_root<H: Hasher>(leaf_count: u32, node: &Hash) -> Hash {
+    H::hashv_once(&[ROOT_PREFIX, &leaf_count.to_le_bytes(), node.as_ref()])
+}
+
+/// Number of levels above the leaves in a tree of `leaf_count` leaves, which
+/// is the maximum number of siblings in a proof.
+#[allow(clippy::arithmetic_side_effects)]
+pub const fn depth(leaf_count: u32) -> u32 {
+    match leaf_count {
+        0 | 1 => 0,
+        _ => u32::BITS - (leaf_count - 1).leading_zeros(),
+    }
+}
+
+/// Number of siblings in the proof of leaf `index` in a tree of `leaf_count`
+/// leaves, or `None` if the leaf is not in the tree.
+#[allow(clippy::arithmetic_side_effects)]
+pub const fn proof_len(index: u32, leaf_count: u32) -> Option<usize> {
+    if index >= leaf_count {
+        return None;
+    }
+    let mut index = index;
+    let mut len = leaf_count;
+    let mut proof_len = 0;
+    while len > 1 {
+        if (index ^ 1) < len {
+            proof_len += 1;
+        }
+        index /= 2;
+        len = len.div_ceil(2);
+    }
+    Some(proof_len)
+}
+
+/// Compute the root of a tree of `leaf_count` leaves from the hash of leaf
+/// `index` and its proof, or `None` if the proof has the wrong length.
+#[allow(clippy::arithmetic_side_effects)]
+pub fn root_from_proof<H: Hasher>(
+    leaf: &Hash,
+    index: u32,
+    leaf_count: u32,
+    siblings: &[Hash],
+) -> Option<Hash> {
+    if proof_len(index, leaf_count) != Some(siblings.len()) {
+        return None;
+    }
+    let mut siblings = siblings.iter();
+    let mut node = *leaf;
+    let mut index = index;
+    let mut len = leaf_count;
+    while len > 1 {
+        if (index ^ 1) < len {
+            // the length was checked above
+            let sibling = siblings.next()?;
+            node = if index % 2 == 0 {
+                hash_node::<H>(&node, sibling)
+            } else {
+                hash_node::<H>(sibling, &node)
+            };
+        }
+        index /= 2;
+        len = len.div_ceil(2);
+    }
+    Some(hash_root::<H>(leaf_count, &node))
+}
+
+/// Verify that leaf `index` of a tree of `leaf_count` leaves hashes to `leaf`.
+pub fn verify_proof<H: Hasher>(
+    root: &Hash,
+    leaf: &Hash,
+    index: u32,
+    leaf_count: u32,
+    siblings: &[Hash],
+) -> bool {
+    root_from_proof::<H>(leaf, index, leaf_count, siblings).as_ref() == Some(root)
+}
+
+/// Verify that the given leaves of a tree of `leaf_count` leaves hash to
+/// their paired hashes.
+///
+/// `leaves` holds `(index, leaf hash)` pairs sorted by strictly increasing
+/// index, and `proof` the hashes produced by [`MerkleTree::prove_multiple`]
+/// for those indexes. `leaves` is used as scratch space and is overwritten.
+#[allow(clippy::arithmetic_side_effects)]
+pub fn verify_multiproof<H: Hasher>(
+    root: &Hash,
+    leaf_count: u32,
+    leaves: &mut [(u32, Hash)],
+    proof: &[Hash],
+) -> bool {
+    let is_sorted = leaves.windows(2).all(|pair| pair[0].0 < pair[1].0);
+    match leaves.last() {
+        Some((index, _)) if is_sorted && *index < leaf_count => {}
+        _ => return false,
+    }
+    if proof.len() > depth(leaf_count).saturating_mul(leaves.len() as u32) as usize {
+        return false;
+    }
+    let mut proof = proof.iter();
+    let mut num_nodes = leaves.len();
+    let mut len = leaf_count;
+    while len > 1 {
+        let mut read = 0;
+        let mut write = 0;
+        while read < num_nodes {
+            let (index, node) = leaves[read];
+            let parent = if (index ^ 1) >= len {
+                // no sibling, the node moves up unchanged
+                node
+            } else if index % 2 == 0 {
+                match leaves.get(read + 1) {
+                    Some((sibling_index, sibling))
+                        if read + 1 < num_nodes && *sibling_index == index + 1 =>
+                    {
+                        read += 1;
+                        hash_node::<H>(&node, sibling)
+                    }
+                    _ => match proof.next() {
+                        Some(sibling) => hash_node::<H>(&node, sibling),
+                        None => return false,
+                    },
+                }
+            } else {
+                match proof.next() {
+                    Some(sibling) => hash_node::<H>(sibling, &node),
+                    None => return false,
+                }
+            };
+            leaves[write] = (index / 2, parent);
+            read += 1;
+            write += 1;
+        }
+        num_nodes = write;
+        len = len.div_ceil(2);
+    }
+    proof.next().is_none() && hash_root::<H>(leaf_count, &leaves[0].1) == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_depth() {
+        assert_eq!(depth(0), 0);
+        assert_eq!(depth(1), 0);
+        assert_eq!(depth(2), 1);
+        assert_eq!(depth(3), 2);
+        assert_eq!(depth(4), 2);
+        assert_eq!(depth(5), 3);
+        assert_eq!(depth(u32::MAX), 32);
+    }
+
+    #[test]
+    fn test_proof_len() {
+        assert_eq!(proof_len(0, 0), None);
+        assert_eq!(proof_len(0, 1), Some(0));
+        assert_eq!(proof_len(1, 2), Some(1));
+        // leaf 4 of 5 has no sibling until the root
+        assert_eq!(proof_len(4, 5), Some(1));
+        assert_eq!(proof_len(0, 5), Some(3));
+        assert_eq!(proof_len(5, 5), None);
+        for leaf_count in 1..=64 {
+            for index in 0..leaf_count {
+                assert!(proof_len(index, leaf_count).unwrap() <= depth(leaf_count) as usize);
+            }
+        }
+    }
+}
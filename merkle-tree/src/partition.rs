@@ -0,0 +1,273 @@
+//! Merkle trees split into partitions of leaves keyed by address.
+//!
+//! Each leaf belongs to an address, which is assigned to one of a fixed
+//! number of partitions with [`EpochRewardsHasher`] keyed by a seed, the same
+//! way epoch rewards are partitioned. Every partition is a [`MerkleTree`] of
+//! its leaves, and the root of the whole tree is the root of a tree over the
+//! partition roots. Since every root commits to the number of its leaves, a
+//! proof is bound to its partition and the number of partitions.
+//!
+//! Partitions can be built, stored and served independently, and a program
+//! can track the state of each one separately, for example which leaves of a
+//! partition have been claimed. A proof is the proof of the leaf in its
+//! partition followed by the proof of the partition root.
+
+use {
+    crate::{MerkleTree, Proof, LEAF_PREFIX},
+    core::fmt,
+    trezoa_address::Address,
+    trezoa_epoch_rewards_hasher::EpochRewardsHasher,
+    trezoa_hash::{hasher::Hasher, Hash},
+    std::{collections::HashMap, vec, vec::Vec},
+};
+
+/// Hash the data of the leaf of `address`.
+///
+/// The address is hashed with the data so that a proof for one address can't
+/// be used for another.
+pub fn hash_leaf<H: Hasher>(address: &Address, data: &[u8]) -> Hash {
+    H::hashv_once(&[LEAF_PREFIX, address.as_ref(), data])
+}
+
+/// A proof that a leaf is part of a [`PartitionedMerkleTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartitionedProof {
+    /// Proof of the leaf in its partition.
+    pub leaf_proof: Proof,
+    /// Proof of the partition root in the tree over all partition roots. Its
+    /// index is the partition and its leaf count the number of partitions.
+    pub partition_proof: Proof,
+}
+
+impl PartitionedProof {
+    /// The partition of the proven leaf.
+    pub fn partition(&self) -> u32 {
+        self.partition_proof.index
+    }
+
+    /// Verify that the proven leaf hashes to `leaf` in the tree with `root`.
+    pub fn verify<H: Hasher>(&self, root: &Hash, leaf: &Hash) -> bool {
+        self.leaf_proof
+            .root::<H>(leaf)
+            .is_some_and(|partition_root| self.partition_proof.verify::<H>(root, &partition_root))
+    }
+}
+
+/// An address has more than one leaf in a [`PartitionedMerkleTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DuplicateAddressError(pub Address);
+
+impl core::error::Error for DuplicateAddressError {}
+
+impl fmt::Display for DuplicateAddressError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "address {:?} has more than one leaf", self.0)
+    }
+}
+
+/// A Merkle tree whose leaves are split into partitions by address.
+#[derive(Clone, Debug)]
+pub struct PartitionedMerkleTree<H> {
+    seed: Hash,
+    partitions: Vec<MerkleTree<H>>,
+    partition_roots: MerkleTree<H>,
+    // The partition of each address and its index in the partition
+    positions: HashMap<Address, (u32, u32)>,
+}
+
+impl<H: Hasher> PartitionedMerkleTree<H> {
+    /// Build a tree over the data of each address, split into
+    /// `num_partitions` partitions keyed by `seed`.
+    ///
+    /// Leaves are hashed with [`hash_leaf`]. Every address has at most one
+    /// leaf, so an error is returned if an address appears more than once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_partitions` is zero or greater than `u32::MAX`, or if a
+    /// partition has more than `u32::MAX` leaves.
+    pub fn new<T: AsRef<[u8]>>(
+        num_partitions: usize,
+        seed: &Hash,
+        leaves: impl IntoIterator<Item = (Address, T)>,
+    ) -> Result<Self, DuplicateAddressError> {
+        assert!(num_partitions > 0, "no partitions");
+        assert!(u32::try_from(num_partitions).is_ok(), "too many partitions");
+        let hasher = EpochRewardsHasher::new(num_partitions, seed);
+        let mut partition_leaves = vec![Vec::new(); num_partitions];
+        let mut positions = HashMap::new();
+        for (address, data) in leaves {
+            if positions.contains_key(&address) {
+                return Err(DuplicateAddressError(address));
+            }
+            let partition = hasher.clone().hash_address_to_partition(&address);
+            let hashes = &mut partition_leaves[partition];
+            let index = u32::try_from(hashes.len()).expect("too many leaves");
+            hashes.push(hash_leaf::<H>(&address, data.as_ref()));
+            positions.insert(address, (partition as u32, index));
+        }
+        let partitions: Vec<_> = partition_leaves
+            .into_iter()
+            .map(MerkleTree::from_leaf_hashes)
+            .collect();
+        let partition_roots =
+            MerkleTree::from_leaf_hashes(partitions.iter().map(MerkleTree::root).collect());
+        Ok(Self {
+            seed: *seed,
+            partitions,
+            partition_roots,
+            positions,
+        })
+    }
+
+    /// The root of the tree.
+    pub fn root(&self) -> Hash {
+        self.partition_roots.root()
+    }
+
+    /// The seed used to assign addresses to partitions.
+    pub fn seed(&self) -> &Hash {
+        &self.seed
+    }
+
+    pub fn num_partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// The tree of the leaves in `partition`.
+    pub fn partition(&self, partition: usize) -> Option<&MerkleTree<H>> {
+        self.partitions.get(partition)
+    }
+
+    /// The partition `address` is assigned to, whether or not it has a leaf.
+    pub fn partition_of(&self, address: &Address) -> usize {
+        EpochRewardsHasher::new(self.num_partitions(), &self.seed)
+            .hash_address_to_partition(address)
+    }
+
+    /// Prove that the leaf of `address` is part of the tree.
+    pub fn prove(&self, address: &Address) -> Option<PartitionedProof> {
+        let (partition, index) = *self.positions.get(address)?;
+        Some(PartitionedProof {
+            leaf_proof: self.partitions[partition as usize].prove(index)?,
+            partition_proof: self.partition_roots.prove(partition)?,
+        })
+    }
+}
+
+/// Compute the root of a [`PartitionedMerkleTree`] from its partition roots,
+/// for example after updating the root of one partition.
+pub fn root_from_partition_roots<H: Hasher>(partition_roots: &[Hash]) -> Hash {
+    MerkleTree::<H>::from_leaf_hashes(partition_roots.to_vec()).root()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{hash_node, hash_root},
+        trezoa_sha256_hasher::Hasher as Sha256,
+    };
+
+    #[test]
+    fn test_partitioned_merkle_tree() {
+        let seed = Hash::new_from_array([7; 32]);
+        let leaves: Vec<_> = (0..100u8)
+            .map(|i| (Address::new_unique(), vec![i; 8]))
+            .collect();
+        let tree = PartitionedMerkleTree::<Sha256>::new(5, &seed, leaves.clone()).unwrap();
+        assert_eq!(tree.num_partitions(), 5);
+        assert_eq!(tree.seed(), &seed);
+
+        let partition_roots: Vec<_> = (0..5)
+            .map(|partition| tree.partition(partition).unwrap().root())
+            .collect();
+        assert_eq!(
+            root_from_partition_roots::<Sha256>(&partition_roots),
+            tree.root()
+        );
+        assert_eq!(
+            (0..5)
+                .map(|partition| tree.partition(partition).unwrap().leaf_count())
+                .sum::<u32>(),
+            100
+        );
+
+        let root = tree.root();
+        for (address, data) in &leaves {
+            let leaf = hash_leaf::<Sha256>(address, data);
+            let proof = tree.prove(address).unwrap();
+            assert_eq!(proof.partition() as usize, tree.partition_of(address));
+            assert!(tree
+                .partition(tree.partition_of(address))
+                .unwrap()
+                .leaves()
+                .contains(&leaf));
+            assert!(proof.verify::<Sha256>(&root, &leaf));
+
+            // the leaf is bound to its address
+            let other_leaf = hash_leaf::<Sha256>(&Address::new_unique(), data);
+            assert!(!proof.verify::<Sha256>(&root, &other_leaf));
+
+            // and to its partition and the number of partitions
+            for num_partitions in 1..=6 {
+                for partition in 0..num_partitions {
+                    if (partition, num_partitions) == (proof.partition(), 5) {
+                        continue;
+                    }
+                    let mut moved = proof.clone();
+                    moved.partition_proof.index = partition;
+                    moved.partition_proof.leaf_count = num_partitions;
+                    assert!(!moved.verify::<Sha256>(&root, &leaf));
+                }
+            }
+        }
+        assert_eq!(tree.prove(&Address::new_unique()), None);
+
+        // the partitions depend on the seed
+        let other_tree =
+            PartitionedMerkleTree::<Sha256>::new(5, &Hash::new_from_array([8; 32]), leaves)
+                .unwrap();
+        assert_ne!(other_tree.root(), tree.root());
+    }
+
+    #[test]
+    fn test_duplicate_address() {
+        let address = Address::new_from_array([1; 32]);
+        let leaves = [
+            (Address::new_from_array([1; 32]), b"first"),
+            (Address::new_from_array([2; 32]), b"other"),
+            (Address::new_from_array([1; 32]), b"again"),
+        ];
+        assert_eq!(
+            PartitionedMerkleTree::<Sha256>::new(3, &Hash::default(), leaves).err(),
+            Some(DuplicateAddressError(address))
+        );
+    }
+
+    #[test]
+    fn test_single_partition() {
+        let address = Address::new_from_array([1; 32]);
+        let tree = PartitionedMerkleTree::<Sha256>::new(
+            1,
+            &Hash::default(),
+            [(Address::new_from_array([1; 32]), b"data")],
+        )
+        .unwrap();
+        let leaf = hash_leaf::<Sha256>(&address, b"data");
+        assert_eq!(
+            tree.root(),
+            hash_root::<Sha256>(1, &hash_root::<Sha256>(1, &leaf))
+        );
+        let proof = tree.prove(&address).unwrap();
+        assert!(proof.leaf_proof.siblings.is_empty());
+        assert!(proof.partition_proof.siblings.is_empty());
+        assert!(proof.verify::<Sha256>(&tree.root(), &leaf));
+
+        let empty = PartitionedMerkleTree::<Sha256>::new::<&[u8]>(2, &Hash::default(), []).unwrap();
+        assert_eq!(
+            empty.root(),
+            hash_root::<Sha256>(2, &hash_node::<Sha256>(&Hash::default(), &Hash::default()))
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use {
+    crate::{hash_leaf, hash_node, hash_root, root_from_proof, verify_multiproof, verify_proof},
+    alloc::{vec, vec::Vec},
+    core::marker::PhantomData,
+    trezoa_hash::{hasher::Hasher, Hash},
+};
+
+/// A proof that a leaf is part of a [`MerkleTree`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Proof {
+    /// Index of the leaf.
+    pub index: u32,
+    /// Number of leaves in the tree.
+    pub leaf_count: u32,
+    /// Hashes of the siblings on the path from the leaf to the root.
+    pub siblings: Vec<Hash>,
+}
+
+impl Proof {
+    /// Compute the root of the tree from the hash of the proven leaf.
+    pub fn root<H: Hasher>(&self, leaf: &Hash) -> Option<Hash> {
+        root_from_proof::<H>(leaf, self.index, self.leaf_count, &self.siblings)
+    }
+
+    /// Verify that the proven leaf hashes to `leaf` in the tree with `root`.
+    pub fn verify<H: Hasher>(&self, root: &Hash, leaf: &Hash) -> bool {
+        verify_proof::<H>(root, leaf, self.index, self.leaf_count, &self.siblings)
+    }
+}
+
+/// A proof that several leaves are part of a [`MerkleTree`], which includes
+/// each hash needed to compute the root only once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MultiProof {
+    /// Indexes of the leaves, sorted and without duplicates.
+    pub indexes: Vec<u32>,
+    /// Number of leaves in the tree.
+    pub leaf_count: u32,
+    /// Hashes needed to compute the root, in the order they are consumed by
+    /// [`verify_multiproof`].
+    pub proof: Vec<Hash>,
+}
+
+impl MultiProof {
+    /// Verify that the proven leaves hash to `leaves`, given in the order of
+    /// [`MultiProof::indexes`], in the tree with `root`.
+    pub fn verify<H: Hasher>(&self, root: &Hash, leaves: &[Hash]) -> bool {
+        if leaves.len() != self.indexes.len() {
+            return false;
+        }
+        let mut leaves: Vec<_> = self
+            .indexes
+            .iter()
+            .copied()
+            .zip(leaves.iter().copied())
+            .collect();
+        verify_multiproof::<H>(root, self.leaf_count, &mut leaves, &self.proof)
+    }
+}
+
+/// A Merkle tree with every node kept in memory, for computing its root and
+/// proofs off-chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleTree<H> {
+    // The leaf hashes followed by each level above them, up to the root
+    levels: Vec<Vec<Hash>>,
+    hasher: PhantomData<H>,
+}
+
+impl<H: Hasher> MerkleTree<H> {
+    /// Build a tree over the data of each leaf.
+    pub fn new<T: AsRef<[u8]>>(leaves: &[T]) -> Self {
+        Self::from_leaf_hashes(
+            leaves
+                .iter()
+                .map(|leaf| hash_leaf::<H>(leaf.as_ref()))
+                .collect(),
+        )
+    }
+
+    /// Build a tree over leaves already hashed with [`hash_leaf`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than `u32::MAX` leaves.
+    pub fn from_leaf_hashes(leaves: Vec<Hash>) -> Self {
+        assert!(u32::try_from(leaves.len()).is_ok(), "too many leaves");
+        let mut levels = vec![leaves];
+        while let Some(level) = levels.last().filter(|level| level.len() > 1) {
+            let next_level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_node::<H>(left, right),
+                    _ => pair[0],
+                })
+                .collect();
+            levels.push(next_level);
+        }
+        Self {
+            levels,
+            hasher: PhantomData,
+        }
+    }
+
+    /// The root of the tree, or `Hash::default()` if it has no leaves.
+    pub fn root(&self) -> Hash {
+        self.levels
+            .last()
+            .and_then(|level| level.first())
+            .map(|node| hash_root::<H>(self.leaf_count(), node))
+            .unwrap_or_default()
+    }
+
+    /// The leaf hashes.
+    pub fn leaves(&self) -> &[Hash] {
+        &self.levels[0]
+    }
+
+    pub fn leaf_count(&self) -> u32 {
+        // the number of leaves was checked when building the tree
+        self.leaves().len() as u32
+    }
+
+    /// Prove that leaf `index` is part of the tree.
+    #[allow(clippy::arithmetic_side_effects)]
+    pub fn prove(&self, index: u32) -> Option<Proof> {
+        let mut position = index as usize;
+        if position >= self.leaves().len() {
+            return None;
+        }
+        let mut siblings = Vec::new();
+        for level in &self.levels {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            position /= 2;
+        }
+        Some(Proof {
+            index,
+            leaf_count: self.leaf_count(),
+            siblings,
+        })
+    }
+
+    /// Prove that the leaves at `indexes` are part of the tree.
+    ///
+    /// Returns `None` if `indexes` is empty or any index is out of bounds.
+    #[allow(clippy::arithmetic_side_effects)]
+    pub fn prove_multiple(&self, indexes: &[u32]) -> Option<MultiProof> {
+        let mut indexes = indexes.to_vec();
+        indexes.sort_unstable();
+        indexes.dedup();
+        match indexes.last() {
+            Some(&index) if index < self.leaf_count() => {}
+            _ => return None,
+        }
+        let mut positions: Vec<usize> = indexes.iter().map(|index| *index as usize).collect();
+        let mut proof = Vec::new();
+        for level in &self.levels {
+            let mut next_positions = Vec::with_capacity(positions.len());
+            let mut i = 0;
+            while i < positions.len() {
+                let position = positions[i];
+                let sibling = position ^ 1;
+                if sibling < level.len() {
+                    if position % 2 == 0 && positions.get(i + 1) == Some(&sibling) {
+                        // the sibling is proven too
+                        i += 1;
+                    } else {
+                        proof.push(level[sibling]);
+                    }
+                }
+                next_positions.push(position / 2);
+                i += 1;
+            }
+            positions = next_positions;
+        }
+        Some(MultiProof {
+            indexes,
+            leaf_count: self.leaf_count(),
+            proof,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{depth, proof_len},
+        trezoa_keccak_hasher::Hasher as Keccak256,
+        trezoa_sha256_hasher::Hasher as Sha256,
+    };
+
+    fn leaves(count: u8) -> Vec<Vec<u8>> {
+        (0..count).map(|i| vec![i; 3]).collect()
+    }
+
+    #[test]
+    fn test_root() {
+        assert_eq!(
+            MerkleTree::<Sha256>::new::<&[u8]>(&[]).root(),
+            Hash::default()
+        );
+
+        let tree = MerkleTree::<Sha256>::new(&leaves(3));
+        let [a, b, c] = [0, 1, 2].map(|i| hash_leaf::<Sha256>(&[i; 3]));
+        assert_eq!(tree.leaves(), [a, b, c]);
+        assert_eq!(
+            tree.root(),
+            hash_root::<Sha256>(3, &hash_node::<Sha256>(&hash_node::<Sha256>(&a, &b), &c))
+        );
+
+        let tree = MerkleTree::<Sha256>::new(&leaves(1));
+        assert_eq!(tree.root(), hash_root::<Sha256>(1, &a));
+
+        // leaves and inner nodes are hashed differently
+        let node = hash_node::<Sha256>(&a, &b);
+        let mut data = a.as_ref().to_vec();
+        data.extend_from_slice(b.as_ref());
+        assert_ne!(hash_leaf::<Sha256>(&data), node);
+    }
+
+    #[test]
+    fn test_prove() {
+        for count in 1..=17 {
+            let tree = MerkleTree::<Keccak256>::new(&leaves(count));
+            let root = tree.root();
+            let leaf_count = tree.leaf_count();
+            for index in 0..leaf_count {
+                let proof = tree.prove(index).unwrap();
+                let leaf = tree.leaves()[index as usize];
+                assert_eq!(proof.siblings.len(), proof_len(index, leaf_count).unwrap());
+                assert!(proof.siblings.len() <= depth(leaf_count) as usize);
+                assert!(proof.verify::<Keccak256>(&root, &leaf));
+
+                // the proof is bound to the leaf, its index and the tree size
+                assert!(!proof.verify::<Keccak256>(&root, &Hash::default()));
+                if let Some(sibling) = proof.siblings.first() {
+                    let mut swapped = proof.clone();
+                    swapped.index ^= 1;
+                    assert!(!swapped.verify::<Keccak256>(&root, &leaf));
+                    assert!(!swapped.verify::<Keccak256>(&root, sibling));
+                }
+                let mut wrong_size = proof.clone();
+                wrong_size.leaf_count = leaf_count.saturating_add(1);
+                assert!(!wrong_size.verify::<Keccak256>(&root, &leaf));
+                for other_count in 1..=17 {
+                    for other_index in 0..other_count {
+                        if (other_index, other_count) == (index, leaf_count) {
+                            continue;
+                        }
+                        let mut moved = proof.clone();
+                        moved.index = other_index;
+                        moved.leaf_count = other_count;
+                        assert!(!moved.verify::<Keccak256>(&root, &leaf));
+                    }
+                }
+            }
+            assert_eq!(tree.prove(leaf_count), None);
+        }
+    }
+
+    #[test]
+    fn test_proof_bound_to_tree_size() {
+        // leaf 4 of 5 has a single sibling, like leaf 2 of 3
+        let tree = MerkleTree::<Sha256>::new(&leaves(5));
+        let proof = tree.prove(4).unwrap();
+        assert_eq!(proof_len(2, 3), Some(proof.siblings.len()));
+        let leaf = tree.leaves()[4];
+        let moved = Proof {
+            index: 2,
+            leaf_count: 3,
+            ..proof.clone()
+        };
+        assert!(proof.verify::<Sha256>(&tree.root(), &leaf));
+        assert!(!moved.verify::<Sha256>(&tree.root(), &leaf));
+    }
+
+    #[test]
+    fn test_prove_multiple() {
+        let tree = MerkleTree::<Sha256>::new(&leaves(11));
+        let root = tree.root();
+        for indexes in [
+            vec![0],
+            vec![10],
+            vec![0, 1],
+            vec![3, 4, 10],
+            vec![10, 2, 2, 5],
+            (0..11).collect(),
+        ] {
+            let proof = tree.prove_multiple(&indexes).unwrap();
+            let leaves: Vec<_> = proof
+                .indexes
+                .iter()
+                .map(|index| tree.leaves()[*index as usize])
+                .collect();
+            assert!(proof.verify::<Sha256>(&root, &leaves));
+
+            let mut wrong_leaves = leaves.clone();
+            wrong_leaves[0] = Hash::default();
+            assert!(!proof.verify::<Sha256>(&root, &wrong_leaves));
+            assert!(!proof.verify::<Sha256>(&root, &leaves[1..]));
+        }
+
+        // every leaf proven needs no other hashes
+        assert!(tree
+            .prove_multiple(&(0..11).collect::<Vec<_>>())
+            .unwrap()
+            .proof
+            .is_empty());
+        // a single leaf needs the same hashes as its proof
+        assert_eq!(
+            tree.prove_multiple(&[6]).unwrap().proof,
+            tree.prove(6).unwrap().siblings
+        );
+        assert_eq!(tree.prove_multiple(&[]), None);
+        assert_eq!(tree.prove_multiple(&[11]), None);
+
+        let proof = tree.prove_multiple(&[1, 2]).unwrap();
+        let mut leaves = [(2, tree.leaves()[2]), (1, tree.leaves()[1])];
+        assert!(!verify_multiproof::<Sha256>(
+            &root,
+            proof.leaf_count,
+            &mut leaves,
+            &proof.proof
+        ));
+    }
+}
@@ -37,6 +37,17 @@ pub struct CompiledInstruction {
 impl Sanitize for CompiledInstruction {}
 
 impl CompiledInstruction {
+    /// Returns the number of bytes the instruction takes in a serialized
+    /// message.
+    pub fn serialized_size(&self) -> usize {
+        // program id index, then the short-vec encoded accounts and data
+        1usize
+            .saturating_add(trezoa_short_vec::encoded_len(self.accounts.len()))
+            .saturating_add(self.accounts.len())
+            .saturating_add(trezoa_short_vec::encoded_len(self.data.len()))
+            .saturating_add(self.data.len())
+    }
+
     #[cfg(feature = "bincode")]
     pub fn new<T: serde::Serialize>(program_ids_index: u8, data: &T, accounts: Vec<u8>) -> Self {
         let data = bincode::serialize(data).unwrap();
@@ -1,6 +1,6 @@
 #[cfg(not(target_os = "trezoa"))]
 use crate::{
-    v0::{LoadedAddresses, MessageAddressTableLookup},
+    v0::{LoadedAddresses, MessageAddressTableLookup, StaticAccountReason},
     AddressLookupTableAccount,
 };
 use {
@@ -139,6 +139,27 @@ impl CompiledKeys {
         Ok((header, static_account_keys))
     }
 
+    /// Returns every compiled key with whether it can be loaded from an
+    /// address lookup table. Keys that can be loaded map to whether they are
+    /// writable, keys that cannot map to the reason they must stay static.
+    #[cfg(not(target_os = "trezoa"))]
+    pub(crate) fn lookup_table_eligibility(
+        &self,
+    ) -> impl Iterator<Item = (&Address, Result<bool, StaticAccountReason>)> + '_ {
+        self.key_meta_map.iter().map(|(key, meta)| {
+            let eligibility = if meta.is_signer {
+                Err(StaticAccountReason::Signer)
+            } else if meta.is_invoked {
+                Err(StaticAccountReason::InvokedProgram)
+            } else if meta.is_nonce {
+                Err(StaticAccountReason::NonceAccount)
+            } else {
+                Ok(meta.is_writable)
+            };
+            (key, eligibility)
+        })
+    }
+
     #[cfg(not(target_os = "trezoa"))]
     pub(crate) fn try_extract_table_lookup(
         &mut self,
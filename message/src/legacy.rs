@@ -20,7 +20,7 @@ use wincode::{containers, len::ShortU16Len, SchemaRead, SchemaWrite};
 use {
     crate::{
         compiled_instruction::CompiledInstruction, compiled_keys::CompiledKeys,
-        inline_nonce::advance_nonce_account_instruction, MessageHeader,
+        inline_nonce::advance_nonce_account_instruction, size::MessageSize, MessageHeader,
     },
     trezoa_address::Address,
     trezoa_hash::Hash,
@@ -464,6 +464,18 @@ impl Message {
         bincode::serialize(self).unwrap()
     }
 
+    /// Returns the serialized size of the message broken down by section,
+    /// without serializing it.
+    pub fn size_breakdown(&self) -> MessageSize {
+        MessageSize::new(false, &self.account_keys, &self.instructions)
+    }
+
+    /// Returns the number of bytes the message takes when serialized, without
+    /// serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.size_breakdown().total()
+    }
+
     pub fn program_id(&self, instruction_index: usize) -> Option<&Address> {
         Some(
             &self.account_keys[self.instructions.get(instruction_index)?.program_id_index as usize],
@@ -352,13 +352,51 @@ impl SanitizedMessage {
 
     /// return detailed signature counts
     pub fn get_signature_details(&self) -> TransactionSignatureDetails {
+        TransactionSignatureDetails::from_program_instructions(
+            u64::from(self.header().num_required_signatures),
+            self.program_instructions_iter(),
+        )
+    }
+}
+
+/// Transaction signature details including the number of transaction signatures
+/// and precompile signatures.
+#[derive(Clone, Debug, Default)]
+pub struct TransactionSignatureDetails {
+    num_transaction_signatures: u64,
+    num_secp256k1_instruction_signatures: u64,
+    num_ed25519_instruction_signatures: u64,
+    num_secp256r1_instruction_signatures: u64,
+}
+
+impl TransactionSignatureDetails {
+    pub const fn new(
+        num_transaction_signatures: u64,
+        num_secp256k1_instruction_signatures: u64,
+        num_ed25519_instruction_signatures: u64,
+        num_secp256r1_instruction_signatures: u64,
+    ) -> Self {
+        Self {
+            num_transaction_signatures,
+            num_secp256k1_instruction_signatures,
+            num_ed25519_instruction_signatures,
+            num_secp256r1_instruction_signatures,
+        }
+    }
+
+    /// Count the transaction signatures plus the signatures verified by the
+    /// precompile instructions among `program_instructions`.
+    pub(crate) fn from_program_instructions<'a>(
+        num_transaction_signatures: u64,
+        program_instructions: impl Iterator<Item = (&'a Address, &'a CompiledInstruction)>,
+    ) -> Self {
         let mut transaction_signature_details = TransactionSignatureDetails {
-            num_transaction_signatures: u64::from(self.header().num_required_signatures),
+            num_transaction_signatures,
             ..TransactionSignatureDetails::default()
         };
 
         // counting the number of pre-processor operations separately
-        for (program_id, instruction) in self.program_instructions_iter() {
+        for (program_id, instruction) in program_instructions {
             if secp256k1_program::check_id(program_id) {
                 if let Some(num_verifies) = instruction.data.first() {
                     transaction_signature_details.num_secp256k1_instruction_signatures =
@@ -385,32 +423,6 @@ impl SanitizedMessage {
 
         transaction_signature_details
     }
-}
-
-/// Transaction signature details including the number of transaction signatures
-/// and precompile signatures.
-#[derive(Clone, Debug, Default)]
-pub struct TransactionSignatureDetails {
-    num_transaction_signatures: u64,
-    num_secp256k1_instruction_signatures: u64,
-    num_ed25519_instruction_signatures: u64,
-    num_secp256r1_instruction_signatures: u64,
-}
-
-impl TransactionSignatureDetails {
-    pub const fn new(
-        num_transaction_signatures: u64,
-        num_secp256k1_instruction_signatures: u64,
-        num_ed25519_instruction_signatures: u64,
-        num_secp256r1_instruction_signatures: u64,
-    ) -> Self {
-        Self {
-            num_transaction_signatures,
-            num_secp256k1_instruction_signatures,
-            num_ed25519_instruction_signatures,
-            num_secp256r1_instruction_signatures,
-        }
-    }
 
     /// return total number of signature, treating pre-processor operations as signature
     pub fn total_signatures(&self) -> u64 {
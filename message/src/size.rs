@@ -0,0 +1,66 @@
+//! The serialized size of a message, broken down by section.
+
+use {
+    crate::{compiled_instruction::CompiledInstruction, MESSAGE_HEADER_LENGTH},
+    trezoa_address::Address,
+    trezoa_hash::Hash,
+};
+
+// inlined to avoid trezoa-signature dep
+const SIGNATURE_BYTES: usize = 64;
+
+/// The serialized size of a message, broken down by section.
+///
+/// Each section includes its short-vec length prefix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageSize {
+    /// Size of the message header, the version prefix of versioned messages
+    /// and the recent blockhash.
+    pub header: usize,
+    /// Size of the static account keys.
+    pub static_account_keys: usize,
+    /// Size of the compiled instructions, including their program id and
+    /// account indexes.
+    pub instructions: usize,
+    /// Size of the address table lookups, zero for legacy messages.
+    pub address_table_lookups: usize,
+}
+
+impl MessageSize {
+    /// Returns the size of the sections shared by all message versions, with
+    /// no address table lookups.
+    pub(crate) fn new(
+        is_versioned: bool,
+        account_keys: &[Address],
+        instructions: &[CompiledInstruction],
+    ) -> Self {
+        let instructions_size = instructions.iter().fold(0usize, |size, instruction| {
+            size.saturating_add(instruction.serialized_size())
+        });
+        Self {
+            header: usize::from(is_versioned)
+                .saturating_add(MESSAGE_HEADER_LENGTH)
+                .saturating_add(size_of::<Hash>()),
+            static_account_keys: trezoa_short_vec::encoded_len(account_keys.len())
+                .saturating_add(account_keys.len().saturating_mul(size_of::<Address>())),
+            instructions: trezoa_short_vec::encoded_len(instructions.len())
+                .saturating_add(instructions_size),
+            address_table_lookups: 0,
+        }
+    }
+
+    /// Returns the total serialized size of the message.
+    pub fn total(&self) -> usize {
+        self.header
+            .saturating_add(self.static_account_keys)
+            .saturating_add(self.instructions)
+            .saturating_add(self.address_table_lookups)
+    }
+}
+
+/// Returns the serialized size of `num_signatures` signatures, including
+/// their short-vec length prefix.
+pub fn signatures_size(num_signatures: usize) -> usize {
+    trezoa_short_vec::encoded_len(num_signatures)
+        .saturating_add(num_signatures.saturating_mul(SIGNATURE_BYTES))
+}
@@ -0,0 +1,508 @@
+//! Splits a long list of instructions into as few messages as possible.
+
+use {
+    crate::{
+        legacy, transaction_size, v0, AddressLookupTableAccount, CompileError, VersionedMessage,
+        PACKET_DATA_SIZE,
+    },
+    core::{fmt, ops::Range},
+    trezoa_address::Address,
+    trezoa_hash::Hash,
+    trezoa_instruction::Instruction,
+    std::borrow::Cow,
+};
+
+/// A constraint on how [`InstructionBatcher`] may place instructions.
+///
+/// Instructions are referred to by their index in the list passed to
+/// [`InstructionBatcher::split`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchConstraint {
+    /// The instruction at `before` executes before the instruction at `after`,
+    /// either in an earlier message or earlier in the same message. `before`
+    /// must be less than `after`.
+    Before { before: usize, after: usize },
+    /// The instructions in the range are placed in the same message, in
+    /// order.
+    Atomic(Range<usize>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BatchError {
+    CompileError(CompileError),
+    /// The instruction at the index, or the atomic group starting at it, does
+    /// not fit in a transaction on its own.
+    InstructionTooLarge(usize),
+    /// The constraint is out of bounds, orders an instruction after a later
+    /// one or overlaps another atomic group.
+    InvalidConstraint(BatchConstraint),
+}
+
+impl core::error::Error for BatchError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::CompileError(e) => Some(e),
+            Self::InstructionTooLarge(_) | Self::InvalidConstraint(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CompileError(e) => write!(f, "failed to compile message: {e}"),
+            Self::InstructionTooLarge(index) => {
+                write!(f, "instruction {index} does not fit in a transaction")
+            }
+            Self::InvalidConstraint(constraint) => {
+                write!(f, "invalid batch constraint {constraint:?}")
+            }
+        }
+    }
+}
+
+impl From<CompileError> for BatchError {
+    fn from(e: CompileError) -> Self {
+        Self::CompileError(e)
+    }
+}
+
+type ComputeBudgetInstructions<'a> = dyn Fn(&[Instruction]) -> Vec<Instruction> + 'a;
+
+/// Packs instructions into the fewest messages whose signed transactions fit
+/// in a packet.
+///
+/// Instructions are placed in order, each into the first message that still
+/// has room for it and that does not break a [`BatchConstraint`]. Unless
+/// [`InstructionBatcher::preserve_order`] is set, an instruction may
+/// therefore land in an earlier message than one that precedes it in the
+/// input. Within a message, instructions always keep their input order.
+///
+/// Messages are compiled as v0 messages when lookup tables are given and as
+/// legacy messages otherwise.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     trezoa_address::Address,
+///     trezoa_hash::Hash,
+///     trezoa_message::InstructionBatcher,
+///     trezoa_system_interface::instruction::transfer,
+/// };
+///
+/// let payer = Address::new_unique();
+/// let instructions: Vec<_> = (0..100)
+///     .map(|_| transfer(&payer, &Address::new_unique(), 1))
+///     .collect();
+/// let messages = InstructionBatcher::new(payer)
+///     .preserve_order()
+///     .split(instructions, Hash::default())?;
+/// assert!(messages.len() > 1);
+/// # Ok::<(), trezoa_message::BatchError>(())
+/// ```
+pub struct InstructionBatcher<'a> {
+    payer: Address,
+    address_lookup_table_accounts: Vec<AddressLookupTableAccount>,
+    constraints: Vec<BatchConstraint>,
+    preserve_order: bool,
+    compute_budget_instructions: Option<Box<ComputeBudgetInstructions<'a>>>,
+}
+
+impl<'a> InstructionBatcher<'a> {
+    /// Creates a batcher for messages whose fees are paid by `payer`.
+    pub fn new(payer: Address) -> Self {
+        Self {
+            payer,
+            address_lookup_table_accounts: vec![],
+            constraints: vec![],
+            preserve_order: false,
+            compute_budget_instructions: None,
+        }
+    }
+
+    /// Sets the address lookup tables used to compile v0 messages.
+    pub fn address_lookup_table_accounts(
+        mut self,
+        address_lookup_table_accounts: impl IntoIterator<Item = AddressLookupTableAccount>,
+    ) -> Self {
+        self.address_lookup_table_accounts = address_lookup_table_accounts.into_iter().collect();
+        self
+    }
+
+    /// Adds a constraint on the placement of instructions.
+    pub fn constraint(mut self, constraint: BatchConstraint) -> Self {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Keeps every instruction in a message no earlier than the instructions
+    /// preceding it, so that the messages execute the instructions in input
+    /// order when sent one after another.
+    pub fn preserve_order(mut self) -> Self {
+        self.preserve_order = true;
+        self
+    }
+
+    /// Prepends the instructions returned by `compute_budget_instructions` to
+    /// every message. It is called with the other instructions of a message,
+    /// for example to request a compute unit limit that covers them, and the
+    /// instructions it returns count towards the size of the message.
+    pub fn compute_budget_instructions(
+        mut self,
+        compute_budget_instructions: impl Fn(&[Instruction]) -> Vec<Instruction> + 'a,
+    ) -> Self {
+        self.compute_budget_instructions = Some(Box::new(compute_budget_instructions));
+        self
+    }
+
+    /// Splits `instructions` into messages.
+    pub fn split(
+        &self,
+        instructions: Vec<Instruction>,
+        recent_blockhash: Hash,
+    ) -> Result<Vec<VersionedMessage>, BatchError> {
+        let units = self.units(instructions.len())?;
+        let mut unit_of_instruction = Vec::with_capacity(instructions.len());
+        for (unit, range) in units.iter().enumerate() {
+            unit_of_instruction.extend(range.clone().map(|_| unit));
+        }
+
+        let mut batch_of_unit: Vec<usize> = Vec::with_capacity(units.len());
+        let mut batches: Vec<(Vec<Instruction>, VersionedMessage)> = vec![];
+        let mut instructions = instructions.into_iter();
+        for range in units {
+            let mut earliest = if self.preserve_order {
+                batch_of_unit.last().copied().unwrap_or(0)
+            } else {
+                0
+            };
+            for constraint in &self.constraints {
+                if let BatchConstraint::Before { before, after } = constraint {
+                    if range.contains(after) && !range.contains(before) {
+                        earliest = earliest.max(batch_of_unit[unit_of_instruction[*before]]);
+                    }
+                }
+            }
+
+            let mut unit: Vec<_> = instructions.by_ref().take(range.len()).collect();
+            let mut placement = None;
+            for (index, (batch, message)) in batches.iter_mut().enumerate().skip(earliest) {
+                let len = batch.len();
+                batch.append(&mut unit);
+                if let Some(compiled) = self.try_compile(batch, recent_blockhash)? {
+                    *message = compiled;
+                    placement = Some(index);
+                    break;
+                }
+                unit = batch.split_off(len);
+            }
+            let placement = match placement {
+                Some(index) => index,
+                None => {
+                    let Some(message) = self.try_compile(&unit, recent_blockhash)? else {
+                        return Err(BatchError::InstructionTooLarge(range.start));
+                    };
+                    batches.push((unit, message));
+                    batches.len().saturating_sub(1)
+                }
+            };
+            batch_of_unit.push(placement);
+        }
+
+        Ok(batches.into_iter().map(|(_, message)| message).collect())
+    }
+
+    /// Returns the ranges of instructions that are placed as a whole, in
+    /// order, after checking the constraints.
+    fn units(&self, num_instructions: usize) -> Result<Vec<Range<usize>>, BatchError> {
+        let mut atomic_ranges = vec![];
+        for constraint in &self.constraints {
+            let is_valid = match constraint {
+                BatchConstraint::Before { before, after } => {
+                    before < after && *after < num_instructions
+                }
+                BatchConstraint::Atomic(range) => {
+                    atomic_ranges.push(range.clone());
+                    range.start < range.end && range.end <= num_instructions
+                }
+            };
+            if !is_valid {
+                return Err(BatchError::InvalidConstraint(constraint.clone()));
+            }
+        }
+        atomic_ranges.sort_by_key(|range| range.start);
+
+        let mut units = Vec::with_capacity(num_instructions);
+        let mut next = 0;
+        for range in atomic_ranges {
+            if range.start < next {
+                return Err(BatchError::InvalidConstraint(BatchConstraint::Atomic(
+                    range,
+                )));
+            }
+            units.extend((next..range.start).map(|index| index..index.saturating_add(1)));
+            next = range.end;
+            units.push(range);
+        }
+        units.extend((next..num_instructions).map(|index| index..index.saturating_add(1)));
+        Ok(units)
+    }
+
+    /// Compiles a message for `instructions`, or returns `None` if its
+    /// transaction does not fit in a packet.
+    fn try_compile(
+        &self,
+        instructions: &[Instruction],
+        recent_blockhash: Hash,
+    ) -> Result<Option<VersionedMessage>, CompileError> {
+        let instructions = match &self.compute_budget_instructions {
+            Some(compute_budget_instructions) => {
+                let mut all_instructions = compute_budget_instructions(instructions);
+                all_instructions.extend_from_slice(instructions);
+                Cow::Owned(all_instructions)
+            }
+            None => Cow::Borrowed(instructions),
+        };
+        let message = match v0::Message::try_compile(
+            &self.payer,
+            &instructions,
+            &self.address_lookup_table_accounts,
+            recent_blockhash,
+        ) {
+            Ok(message) => message,
+            Err(CompileError::AccountIndexOverflow) => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let message = if self.address_lookup_table_accounts.is_empty() {
+            VersionedMessage::Legacy(legacy::Message {
+                header: message.header,
+                account_keys: message.account_keys,
+                recent_blockhash: message.recent_blockhash,
+                instructions: message.instructions,
+            })
+        } else {
+            VersionedMessage::V0(message)
+        };
+        let size = transaction_size(
+            message.header().num_required_signatures,
+            message.serialized_size(),
+        );
+        Ok((size <= PACKET_DATA_SIZE).then_some(message))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        trezoa_instruction::AccountMeta,
+        trezoa_system_interface::instruction::{transfer, SystemInstruction},
+    };
+
+    fn transfers(payer: &Address, count: usize) -> Vec<Instruction> {
+        (0..count)
+            .map(|lamports| transfer(payer, &Address::new_unique(), lamports as u64))
+            .collect()
+    }
+
+    fn transfer_lamports(message: &VersionedMessage) -> Vec<u64> {
+        message
+            .instructions()
+            .iter()
+            .filter_map(
+                |instruction| match bincode::deserialize(&instruction.data).ok()? {
+                    SystemInstruction::Transfer { lamports } => Some(lamports),
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    fn assert_fits(message: &VersionedMessage) {
+        let size = transaction_size(
+            message.header().num_required_signatures,
+            message.serialize().len(),
+        );
+        assert!(size <= PACKET_DATA_SIZE);
+    }
+
+    #[test]
+    fn test_split_fills_messages() {
+        let payer = Address::new_unique();
+        let messages = InstructionBatcher::new(payer)
+            .preserve_order()
+            .split(transfers(&payer, 60), Hash::default())
+            .unwrap();
+
+        assert_eq!(messages.len(), 3);
+        let mut lamports = vec![];
+        for message in &messages {
+            assert!(matches!(message, VersionedMessage::Legacy(_)));
+            assert_fits(message);
+            lamports.extend(transfer_lamports(message));
+        }
+        assert_eq!(lamports, (0..60).collect::<Vec<_>>());
+
+        // Adding another transfer to any full message overflows it
+        for message in &messages[..2] {
+            let mut instructions = transfers(&payer, transfer_lamports(message).len());
+            instructions.push(transfer(&payer, &Address::new_unique(), 0));
+            let message = legacy::Message::new(&instructions, Some(&payer));
+            assert!(
+                transaction_size(1, VersionedMessage::Legacy(message).serialize().len())
+                    > PACKET_DATA_SIZE
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_with_lookup_table() {
+        let payer = Address::new_unique();
+        let instructions = transfers(&payer, 50);
+        let lookup_table = AddressLookupTableAccount {
+            key: Address::new_unique(),
+            addresses: instructions
+                .iter()
+                .map(|instruction| instruction.accounts[1].pubkey)
+                .collect(),
+        };
+        let messages = InstructionBatcher::new(payer)
+            .address_lookup_table_accounts([lookup_table])
+            .split(instructions, Hash::default())
+            .unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(messages[0], VersionedMessage::V0(_)));
+        assert_fits(&messages[0]);
+    }
+
+    #[test]
+    fn test_split_fills_earlier_messages() {
+        let payer = Address::new_unique();
+        let large = Instruction::new_with_bytes(Address::new_unique(), &[0; 900], vec![]);
+        let instructions = vec![
+            large.clone(),
+            large,
+            transfer(&payer, &Address::new_unique(), 1),
+        ];
+
+        let messages = InstructionBatcher::new(payer)
+            .split(instructions.clone(), Hash::default())
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(transfer_lamports(&messages[0]), vec![1]);
+
+        let messages = InstructionBatcher::new(payer)
+            .constraint(BatchConstraint::Before {
+                before: 1,
+                after: 2,
+            })
+            .split(instructions.clone(), Hash::default())
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(transfer_lamports(&messages[1]), vec![1]);
+
+        let messages = InstructionBatcher::new(payer)
+            .preserve_order()
+            .split(instructions, Hash::default())
+            .unwrap();
+        assert_eq!(transfer_lamports(&messages[1]), vec![1]);
+    }
+
+    #[test]
+    fn test_split_atomic() {
+        let payer = Address::new_unique();
+        let messages = InstructionBatcher::new(payer)
+            .preserve_order()
+            .constraint(BatchConstraint::Atomic(15..30))
+            .split(transfers(&payer, 30), Hash::default())
+            .unwrap();
+
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            transfer_lamports(&messages[1]),
+            (15..30).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_split_compute_budget_instructions() {
+        let payer = Address::new_unique();
+        let compute_budget_program = Address::new_unique();
+        let messages = InstructionBatcher::new(payer)
+            .preserve_order()
+            .compute_budget_instructions(|instructions| {
+                vec![Instruction::new_with_bytes(
+                    compute_budget_program,
+                    &(instructions.len() as u32).to_le_bytes(),
+                    vec![],
+                )]
+            })
+            .split(transfers(&payer, 60), Hash::default())
+            .unwrap();
+
+        let mut lamports = vec![];
+        for message in &messages {
+            assert_fits(message);
+            let first = &message.instructions()[0];
+            assert_eq!(
+                message.static_account_keys()[usize::from(first.program_id_index)],
+                compute_budget_program
+            );
+            let transfers = transfer_lamports(message);
+            assert_eq!(first.data, (transfers.len() as u32).to_le_bytes());
+            lamports.extend(transfers);
+        }
+        assert_eq!(lamports, (0..60).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_split_errors() {
+        let payer = Address::new_unique();
+        let batcher = InstructionBatcher::new(payer);
+        assert_eq!(batcher.split(vec![], Hash::default()), Ok(vec![]));
+
+        let too_large = Instruction::new_with_bytes(
+            Address::new_unique(),
+            &[0; 1200],
+            vec![AccountMeta::new(payer, true)],
+        );
+        assert_eq!(
+            batcher.split(
+                vec![transfer(&payer, &Address::new_unique(), 1), too_large],
+                Hash::default()
+            ),
+            Err(BatchError::InstructionTooLarge(1))
+        );
+
+        for constraint in [
+            BatchConstraint::Before {
+                before: 1,
+                after: 0,
+            },
+            BatchConstraint::Before {
+                before: 0,
+                after: 2,
+            },
+            BatchConstraint::Atomic(1..1),
+            BatchConstraint::Atomic(0..3),
+        ] {
+            assert_eq!(
+                InstructionBatcher::new(payer)
+                    .constraint(constraint.clone())
+                    .split(transfers(&payer, 2), Hash::default()),
+                Err(BatchError::InvalidConstraint(constraint))
+            );
+        }
+
+        let overlapping = BatchConstraint::Atomic(1..3);
+        assert_eq!(
+            InstructionBatcher::new(payer)
+                .constraint(BatchConstraint::Atomic(0..2))
+                .constraint(overlapping.clone())
+                .split(transfers(&payer, 3), Hash::default()),
+            Err(BatchError::InvalidConstraint(overlapping))
+        );
+    }
+}
@@ -2,8 +2,11 @@
 use trezoa_frozen_abi_macro::{frozen_abi, AbiEnumVisitor, AbiExample};
 use {
     crate::{
-        compiled_instruction::CompiledInstruction, legacy::Message as LegacyMessage,
-        v0::MessageAddressTableLookup, MessageHeader,
+        compiled_instruction::CompiledInstruction,
+        legacy::Message as LegacyMessage,
+        size::{signatures_size, MessageSize},
+        v0::MessageAddressTableLookup,
+        MessageHeader, TransactionSignatureDetails,
     },
     trezoa_address::Address,
     trezoa_hash::Hash,
@@ -31,14 +34,28 @@ use {
     std::fmt,
 };
 
+mod instruction_batcher;
 mod sanitized;
 pub mod v0;
 
-pub use sanitized::*;
+pub use {instruction_batcher::*, sanitized::*};
 
 /// Bit mask that indicates whether a serialized message is versioned.
 pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
 
+// inlined to avoid trezoa-packet dep
+#[cfg(test)]
+static_assertions::const_assert_eq!(PACKET_DATA_SIZE, trezoa_packet::PACKET_DATA_SIZE);
+/// Maximum over-the-wire size of a transaction, the same as
+/// `trezoa_packet::PACKET_DATA_SIZE`.
+pub const PACKET_DATA_SIZE: usize = 1280 - 40 - 8;
+
+/// Returns the serialized size of a transaction carrying `num_signatures`
+/// signatures and a message of `message_size` bytes.
+pub(crate) fn transaction_size(num_signatures: u8, message_size: usize) -> usize {
+    signatures_size(usize::from(num_signatures)).saturating_add(message_size)
+}
+
 /// Either a legacy message or a v0 message.
 ///
 /// # Serialization
@@ -156,11 +173,65 @@ impl VersionedMessage {
         }
     }
 
+    /// Program instructions iterator which includes each instruction's program
+    /// id. Instructions whose program id index is not a static account key are
+    /// skipped, which can only happen for unsanitized messages.
+    pub fn program_instructions_iter(
+        &self,
+    ) -> impl Iterator<Item = (&Address, &CompiledInstruction)> + Clone {
+        self.instructions().iter().filter_map(move |ix| {
+            self.static_account_keys()
+                .get(usize::from(ix.program_id_index))
+                .map(|program_id| (program_id, ix))
+        })
+    }
+
+    /// Returns the number of requested write-locks in this message, including
+    /// the writable accounts loaded from address lookup tables.
+    /// This does not consider if write-locks are demoted.
+    pub fn num_write_locks(&self) -> u64 {
+        let header = self.header();
+        let num_static_write_locks = self.static_account_keys().len().saturating_sub(
+            usize::from(header.num_readonly_signed_accounts)
+                .saturating_add(usize::from(header.num_readonly_unsigned_accounts)),
+        );
+        let num_loaded_write_locks = self
+            .address_table_lookups()
+            .unwrap_or_default()
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len())
+            .sum::<usize>();
+        num_static_write_locks.saturating_add(num_loaded_write_locks) as u64
+    }
+
+    /// return detailed signature counts
+    pub fn get_signature_details(&self) -> TransactionSignatureDetails {
+        TransactionSignatureDetails::from_program_instructions(
+            u64::from(self.header().num_required_signatures),
+            self.program_instructions_iter(),
+        )
+    }
+
     #[cfg(feature = "bincode")]
     pub fn serialize(&self) -> Vec<u8> {
         bincode::serialize(self).unwrap()
     }
 
+    /// Returns the serialized size of the message broken down by section,
+    /// without serializing it.
+    pub fn size_breakdown(&self) -> MessageSize {
+        match self {
+            Self::Legacy(message) => message.size_breakdown(),
+            Self::V0(message) => message.size_breakdown(),
+        }
+    }
+
+    /// Returns the number of bytes the message takes when serialized, without
+    /// serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.size_breakdown().total()
+    }
+
     #[cfg(all(feature = "bincode", feature = "blake3"))]
     /// Compute the blake3 hash of this transaction's message
     pub fn hash(&self) -> Hash {
@@ -422,6 +493,37 @@ mod tests {
         trezoa_instruction::{AccountMeta, Instruction},
     };
 
+    #[test]
+    fn test_serialized_size() {
+        let payer = Address::new_unique();
+        let accounts: Vec<_> = (0..130)
+            .map(|_| AccountMeta::new(Address::new_unique(), false))
+            .collect();
+        let instructions = vec![
+            Instruction::new_with_bytes(Address::new_unique(), &[], vec![]),
+            Instruction::new_with_bytes(Address::new_unique(), &[7; 200], accounts),
+        ];
+
+        let legacy = VersionedMessage::Legacy(LegacyMessage::new(&instructions, Some(&payer)));
+        assert_eq!(legacy.serialized_size(), legacy.serialize().len());
+        assert_eq!(legacy.size_breakdown().address_table_lookups, 0);
+
+        let v0 = VersionedMessage::V0(v0::Message {
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Address::new_unique(),
+                writable_indexes: (0..200).collect(),
+                readonly_indexes: vec![0],
+            }],
+            ..v0::Message::try_compile(&payer, &instructions, &[], Hash::default()).unwrap()
+        });
+        assert_eq!(v0.serialized_size(), v0.serialize().len());
+        // one lookup of 200 writable and one readonly accounts
+        assert_eq!(
+            v0.size_breakdown().address_table_lookups,
+            1 + 32 + 2 + 200 + 1 + 1
+        );
+    }
+
     #[test]
     fn test_legacy_message_serialization() {
         let program_id0 = Address::new_unique();
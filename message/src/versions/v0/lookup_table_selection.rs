@@ -0,0 +1,375 @@
+use {
+    super::Message,
+    crate::{
+        compiled_keys::CompiledKeys, transaction_size, AddressLookupTableAccount, CompileError,
+        PACKET_DATA_SIZE,
+    },
+    core::fmt,
+    trezoa_address::Address,
+    trezoa_hash::Hash,
+    trezoa_instruction::Instruction,
+    std::collections::{BTreeSet, HashMap},
+};
+
+/// Why an account referenced by the instructions cannot be loaded from an
+/// address lookup table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StaticAccountReason {
+    /// The account signs the transaction.
+    Signer,
+    /// The account is a program invoked by an instruction.
+    InvokedProgram,
+    /// The account is the durable nonce advanced by the first instruction.
+    NonceAccount,
+}
+
+/// An account found in a candidate lookup table that must nonetheless be
+/// included in the static account keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StaticAccount {
+    pub address: Address,
+    pub reason: StaticAccountReason,
+}
+
+/// The result of compiling a message with the lookup tables chosen by
+/// [`Message::try_compile_with_lookup_table_selection`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LookupTableSelection {
+    /// The compiled message.
+    pub message: Message,
+    /// The selected lookup tables, in the order the message uses them.
+    pub address_lookup_table_accounts: Vec<AddressLookupTableAccount>,
+    /// Accounts present in a candidate table that cannot be looked up.
+    pub static_accounts: Vec<StaticAccount>,
+    /// Size in bytes of a transaction carrying the message and all of its
+    /// signatures.
+    pub transaction_size: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LookupTableSelectionError {
+    CompileError(CompileError),
+    /// The best selection still does not fit in a packet.
+    TransactionTooLarge(Box<LookupTableSelection>),
+}
+
+impl core::error::Error for LookupTableSelectionError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::CompileError(e) => Some(e),
+            Self::TransactionTooLarge(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for LookupTableSelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CompileError(e) => write!(f, "failed to compile message: {e}"),
+            Self::TransactionTooLarge(selection) => write!(
+                f,
+                "transaction of {} bytes exceeds the packet data size of {PACKET_DATA_SIZE} bytes",
+                selection.transaction_size
+            ),
+        }
+    }
+}
+
+impl From<CompileError> for LookupTableSelectionError {
+    fn from(e: CompileError) -> Self {
+        Self::CompileError(e)
+    }
+}
+
+/// Accounts of one candidate table that the instructions can load from it.
+struct CandidateTable<'a> {
+    account: &'a AddressLookupTableAccount,
+    // first index of each address in the table
+    indexes: HashMap<&'a Address, usize>,
+}
+
+impl CandidateTable<'_> {
+    /// Returns the number of bytes saved by loading the `uncovered` keys found
+    /// in this table, or `None` if the table cannot be used.
+    fn savings(&self, uncovered: &BTreeSet<(&Address, bool)>) -> Option<(usize, usize)> {
+        let mut num_writable = 0usize;
+        let mut num_readonly = 0usize;
+        for (key, is_writable) in uncovered {
+            let Some(index) = self.indexes.get(key) else {
+                continue;
+            };
+            if u8::try_from(*index).is_err() {
+                return None;
+            }
+            if *is_writable {
+                num_writable = num_writable.saturating_add(1);
+            } else {
+                num_readonly = num_readonly.saturating_add(1);
+            }
+        }
+        let num_keys = num_writable.saturating_add(num_readonly);
+        // Each key moves from a 32 byte static key to a 1 byte index, at the
+        // cost of the lookup itself.
+        let saved = num_keys.saturating_mul(size_of::<Address>());
+        let cost = size_of::<Address>()
+            .saturating_add(trezoa_short_vec::encoded_len(num_writable))
+            .saturating_add(trezoa_short_vec::encoded_len(num_readonly))
+            .saturating_add(num_keys);
+        Some((saved.saturating_sub(cost), num_keys))
+    }
+}
+
+impl Message {
+    /// Compile a message like [`Message::try_compile`], choosing the subset
+    /// of `candidate_lookup_table_accounts` that minimizes the serialized
+    /// size of the transaction.
+    ///
+    /// Tables are chosen greedily, each time picking the one that saves the
+    /// most bytes over the accounts not yet covered by earlier picks, until
+    /// no table saves anything. A table is never used for fewer than two
+    /// accounts, since a single lookup costs more than it saves.
+    ///
+    /// Signers, invoked programs and the advanced nonce account can never be
+    /// loaded from a table. Those found in a candidate table are reported in
+    /// [`LookupTableSelection::static_accounts`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LookupTableSelectionError::TransactionTooLarge`], carrying
+    /// the best selection, if the signed transaction would not fit in a
+    /// packet.
+    pub fn try_compile_with_lookup_table_selection(
+        payer: &Address,
+        instructions: &[Instruction],
+        candidate_lookup_table_accounts: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+    ) -> Result<LookupTableSelection, LookupTableSelectionError> {
+        let compiled_keys = CompiledKeys::compile(instructions, Some(*payer));
+        let candidates: Vec<_> = candidate_lookup_table_accounts
+            .iter()
+            .map(|account| {
+                let mut indexes = HashMap::with_capacity(account.addresses.len());
+                for (index, address) in account.addresses.iter().enumerate() {
+                    indexes.entry(address).or_insert(index);
+                }
+                CandidateTable { account, indexes }
+            })
+            .collect();
+
+        let mut uncovered = BTreeSet::new();
+        let mut static_accounts = vec![];
+        for (address, eligibility) in compiled_keys.lookup_table_eligibility() {
+            match eligibility {
+                Ok(is_writable) => {
+                    uncovered.insert((address, is_writable));
+                }
+                Err(reason) => {
+                    if candidates
+                        .iter()
+                        .any(|candidate| candidate.indexes.contains_key(address))
+                    {
+                        static_accounts.push(StaticAccount {
+                            address: *address,
+                            reason,
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut selected = vec![];
+        let mut remaining: Vec<_> = candidates.iter().collect();
+        loop {
+            // Ties go to the earliest candidate
+            let best = remaining
+                .iter()
+                .enumerate()
+                .filter_map(|(position, candidate)| {
+                    let (saved, num_keys) = candidate.savings(&uncovered)?;
+                    (saved > 0).then_some((position, saved, num_keys))
+                })
+                .fold(
+                    None,
+                    |best: Option<(usize, usize, usize)>, current| match best {
+                        Some(best) if best.1 >= current.1 => Some(best),
+                        _ => Some(current),
+                    },
+                );
+            let Some((position, _, _)) = best else {
+                break;
+            };
+            let candidate = remaining.remove(position);
+            uncovered.retain(|(key, _)| !candidate.indexes.contains_key(key));
+            selected.push(candidate.account.clone());
+        }
+
+        let message = Self::try_compile(payer, instructions, &selected, recent_blockhash)?;
+        let transaction_size = transaction_size(
+            message.header.num_required_signatures,
+            message.serialized_size(),
+        );
+        let selection = LookupTableSelection {
+            message,
+            address_lookup_table_accounts: selected,
+            static_accounts,
+            transaction_size,
+        };
+        if transaction_size > PACKET_DATA_SIZE {
+            return Err(LookupTableSelectionError::TransactionTooLarge(Box::new(
+                selection,
+            )));
+        }
+        Ok(selection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::VersionedMessage, trezoa_instruction::AccountMeta,
+        trezoa_system_interface::instruction::advance_nonce_account,
+    };
+
+    fn table(addresses: Vec<Address>) -> AddressLookupTableAccount {
+        AddressLookupTableAccount {
+            key: Address::new_unique(),
+            addresses,
+        }
+    }
+
+    fn instruction(accounts: &[Address]) -> Instruction {
+        Instruction::new_with_bytes(
+            Address::new_unique(),
+            &[],
+            accounts
+                .iter()
+                .map(|address| AccountMeta::new(*address, false))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_serialized_size() {
+        let accounts: Vec<_> = (0..4).map(|_| Address::new_unique()).collect();
+        let lookup_table = table(accounts[2..].to_vec());
+        let message = Message::try_compile(
+            &Address::new_unique(),
+            &[instruction(&accounts)],
+            &[lookup_table],
+            Hash::new_unique(),
+        )
+        .unwrap();
+        assert_eq!(
+            message.serialized_size(),
+            VersionedMessage::V0(message).serialize().len()
+        );
+    }
+
+    #[test]
+    fn test_prefers_largest_saving_table() {
+        let payer = Address::new_unique();
+        let accounts: Vec<_> = (0..6).map(|_| Address::new_unique()).collect();
+        let small = table(accounts[..2].to_vec());
+        let large = table(accounts.clone());
+        let useless = table(vec![Address::new_unique(), Address::new_unique()]);
+        let single = table(vec![accounts[0]]);
+
+        let selection = Message::try_compile_with_lookup_table_selection(
+            &payer,
+            &[instruction(&accounts)],
+            &[single, small, useless, large.clone()],
+            Hash::default(),
+        )
+        .unwrap();
+        assert_eq!(selection.address_lookup_table_accounts, vec![large]);
+        assert_eq!(selection.message.account_keys.len(), 2);
+        assert_eq!(
+            selection.transaction_size,
+            selection.message.serialized_size().saturating_add(1 + 64)
+        );
+        assert!(selection.static_accounts.is_empty());
+    }
+
+    #[test]
+    fn test_combines_disjoint_tables() {
+        let payer = Address::new_unique();
+        let accounts: Vec<_> = (0..6).map(|_| Address::new_unique()).collect();
+        let first = table(accounts[..3].to_vec());
+        let overlapping = table(accounts[2..4].to_vec());
+        let second = table(accounts[3..].to_vec());
+
+        let selection = Message::try_compile_with_lookup_table_selection(
+            &payer,
+            &[instruction(&accounts)],
+            &[first.clone(), overlapping, second.clone()],
+            Hash::default(),
+        )
+        .unwrap();
+        assert_eq!(selection.address_lookup_table_accounts, vec![first, second]);
+        assert_eq!(selection.message.address_table_lookups.len(), 2);
+    }
+
+    #[test]
+    fn test_reports_static_accounts() {
+        let payer = Address::new_unique();
+        let signer = Address::new_unique();
+        let nonce_account = Address::new_unique();
+        let mut invoke = instruction(&[Address::new_unique()]);
+        invoke
+            .accounts
+            .push(AccountMeta::new_readonly(signer, true));
+        let program_id = invoke.program_id;
+        let lookup_table = table(vec![payer, signer, program_id, nonce_account]);
+
+        let selection = Message::try_compile_with_lookup_table_selection(
+            &payer,
+            &[advance_nonce_account(&nonce_account, &payer), invoke],
+            &[lookup_table],
+            Hash::default(),
+        )
+        .unwrap();
+        assert!(selection.address_lookup_table_accounts.is_empty());
+        let mut expected = vec![
+            StaticAccount {
+                address: payer,
+                reason: StaticAccountReason::Signer,
+            },
+            StaticAccount {
+                address: signer,
+                reason: StaticAccountReason::Signer,
+            },
+            StaticAccount {
+                address: program_id,
+                reason: StaticAccountReason::InvokedProgram,
+            },
+            StaticAccount {
+                address: nonce_account,
+                reason: StaticAccountReason::NonceAccount,
+            },
+        ];
+        expected.sort_by_key(|account| account.address);
+        assert_eq!(selection.static_accounts, expected);
+    }
+
+    #[test]
+    fn test_transaction_too_large() {
+        let payer = Address::new_unique();
+        let instructions: Vec<_> = (0..10)
+            .map(|_| {
+                let mut ix = instruction(&[Address::new_unique(), Address::new_unique()]);
+                ix.data = vec![0; 100];
+                ix
+            })
+            .collect();
+        let result = Message::try_compile_with_lookup_table_selection(
+            &payer,
+            &instructions,
+            &[],
+            Hash::default(),
+        );
+        let Err(LookupTableSelectionError::TransactionTooLarge(selection)) = result else {
+            panic!("expected the transaction to be too large");
+        };
+        assert!(selection.transaction_size > PACKET_DATA_SIZE);
+    }
+}
@@ -9,7 +9,7 @@
 //! [`v0`]: crate::v0
 //! [future message format]: https://docs.trezoateam.com/proposals/versioned-transactions
 
-pub use loaded::*;
+pub use {loaded::*, lookup_table_selection::*};
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "frozen-abi")]
@@ -20,6 +20,7 @@ use {
     crate::{
         compiled_instruction::CompiledInstruction,
         compiled_keys::{CompileError, CompiledKeys},
+        size::MessageSize,
         AccountKeys, AddressLookupTableAccount, MessageHeader,
     },
     trezoa_address::Address,
@@ -31,6 +32,7 @@ use {
 };
 
 mod loaded;
+mod lookup_table_selection;
 
 /// Address table lookups describe an on-chain address lookup table to use
 /// for loading more readonly and writable accounts in a single tx.
@@ -55,6 +57,17 @@ pub struct MessageAddressTableLookup {
     pub readonly_indexes: Vec<u8>,
 }
 
+impl MessageAddressTableLookup {
+    /// Returns the number of bytes the lookup takes in a serialized message.
+    pub fn serialized_size(&self) -> usize {
+        size_of::<Address>()
+            .saturating_add(trezoa_short_vec::encoded_len(self.writable_indexes.len()))
+            .saturating_add(self.writable_indexes.len())
+            .saturating_add(trezoa_short_vec::encoded_len(self.readonly_indexes.len()))
+            .saturating_add(self.readonly_indexes.len())
+    }
+}
+
 /// A Trezoa transaction message (v0).
 ///
 /// This message format supports succinct account loading with
@@ -311,6 +324,28 @@ impl Message {
         bincode::serialize(&(crate::MESSAGE_VERSION_PREFIX, self)).unwrap()
     }
 
+    /// Returns the serialized size of the message with its version prefix
+    /// broken down by section, without serializing it.
+    pub fn size_breakdown(&self) -> MessageSize {
+        let lookups_size = self
+            .address_table_lookups
+            .iter()
+            .fold(0usize, |size, lookup| {
+                size.saturating_add(lookup.serialized_size())
+            });
+        MessageSize {
+            address_table_lookups: trezoa_short_vec::encoded_len(self.address_table_lookups.len())
+                .saturating_add(lookups_size),
+            ..MessageSize::new(true, &self.account_keys, &self.instructions)
+        }
+    }
+
+    /// Returns the number of bytes the message takes when serialized with its
+    /// version prefix, without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.size_breakdown().total()
+    }
+
     /// Returns true if the account at the specified index is called as a program by an instruction
     pub fn is_key_called_as_program(&self, key_index: usize) -> bool {
         if let Ok(key_index) = u8::try_from(key_index) {
@@ -3,8 +3,10 @@
 use {
     num_enum::{IntoPrimitive, TryFromPrimitive},
     trezoa_hash::Hash,
+    trezoa_pubkey::Pubkey,
     trezoa_sanitize::SanitizeError,
-    trezoa_signature::Signature,
+    trezoa_sha256_hasher::Hasher,
+    trezoa_signature::{Signature, SIGNATURE_BYTES},
     trezoa_signer::Signer,
 };
 
@@ -14,6 +16,8 @@ static_assertions::const_assert_eq!(OffchainMessage::HEADER_LEN, 17);
 static_assertions::const_assert_eq!(v0::OffchainMessage::MAX_LEN, 65515);
 #[cfg(test)]
 static_assertions::const_assert_eq!(v0::OffchainMessage::MAX_LEN_LEDGER, 1212);
+#[cfg(test)]
+static_assertions::const_assert_eq!(v1::OffchainMessage::HEADER_LEN, 36);
 
 /// Check if given bytes contain only printable ASCII characters
 pub fn is_printable_ascii(data: &[u8]) -> bool {
@@ -30,6 +34,13 @@ pub fn is_utf8(data: &[u8]) -> bool {
     std::str::from_utf8(data).is_ok()
 }
 
+/// Compute the SHA256 hash of a serialized off-chain message of any version
+fn hash_serialized_message(serialized_message: &[u8]) -> Hash {
+    let mut hasher = Hasher::default();
+    hasher.hash(serialized_message);
+    hasher.result()
+}
+
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Copy, Clone, TryFromPrimitive, IntoPrimitive)]
 pub enum MessageFormat {
@@ -38,14 +49,53 @@ pub enum MessageFormat {
     ExtendedUtf8,
 }
 
+/// Identifies the application an off-chain message is intended for, so that
+/// a signature obtained by one application cannot be replayed to another.
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone, Hash)]
+pub struct ApplicationDomain([u8; 32]);
+
+impl ApplicationDomain {
+    pub const LEN: usize = 32;
+
+    pub const fn new(bytes: [u8; Self::LEN]) -> Self {
+        Self(bytes)
+    }
+
+    /// Construct an application domain from a name such as `example.com`,
+    /// padded with zeros to 32 bytes
+    pub fn from_name(name: &str) -> Result<Self, SanitizeError> {
+        if name.is_empty() {
+            return Err(SanitizeError::InvalidValue);
+        }
+        let mut bytes = [0; Self::LEN];
+        bytes
+            .get_mut(..name.len())
+            .ok_or(SanitizeError::ValueOutOfBounds)?
+            .copy_from_slice(name.as_bytes());
+        Ok(Self(bytes))
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; Self::LEN] {
+        &self.0
+    }
+}
+
+impl From<[u8; ApplicationDomain::LEN]> for ApplicationDomain {
+    fn from(bytes: [u8; ApplicationDomain::LEN]) -> Self {
+        Self(bytes)
+    }
+}
+
 #[allow(clippy::arithmetic_side_effects)]
 pub mod v0 {
     use {
-        super::{is_printable_ascii, is_utf8, MessageFormat, OffchainMessage as Base},
+        super::{
+            hash_serialized_message, is_printable_ascii, is_utf8, MessageFormat,
+            OffchainMessage as Base,
+        },
         trezoa_hash::Hash,
         trezoa_packet::PACKET_DATA_SIZE,
         trezoa_sanitize::SanitizeError,
-        trezoa_sha256_hasher::Hasher,
     };
 
     /// OffchainMessage Version 0.
@@ -143,15 +193,204 @@ pub mod v0 {
 
         /// Compute the SHA256 hash of the serialized off-chain message
         pub fn hash(serialized_message: &[u8]) -> Result<Hash, SanitizeError> {
-            let mut hasher = Hasher::default();
-            hasher.hash(serialized_message);
-            Ok(hasher.result())
+            Ok(hash_serialized_message(serialized_message))
+        }
+
+        pub fn get_format(&self) -> MessageFormat {
+            self.format
+        }
+
+        pub fn get_message(&self) -> &Vec<u8> {
+            &self.message
+        }
+    }
+}
+
+#[allow(clippy::arithmetic_side_effects)]
+pub mod v1 {
+    use {
+        super::{
+            is_printable_ascii, is_utf8, ApplicationDomain, MessageFormat, OffchainMessage as Base,
+        },
+        trezoa_packet::PACKET_DATA_SIZE,
+        trezoa_pubkey::{Pubkey, PUBKEY_BYTES},
+        trezoa_sanitize::SanitizeError,
+    };
+
+    /// OffchainMessage Version 1.
+    /// Struct always contains a non-empty valid message and at least one
+    /// signer, with no signer repeated.
+    #[derive(Debug, PartialEq, Eq, Clone)]
+    pub struct OffchainMessage {
+        application_domain: ApplicationDomain,
+        format: MessageFormat,
+        signers: Vec<Pubkey>,
+        message: Vec<u8>,
+    }
+
+    impl OffchainMessage {
+        // Header Length = Application Domain (32) + Message Format (1) + Signer Count (1)
+        //     + Message Length (2), excluding the signers (32 each)
+        pub const HEADER_LEN: usize = ApplicationDomain::LEN + 4;
+        // Max number of signers, limited by the one byte signer count
+        pub const MAX_SIGNERS: usize = u8::MAX as usize;
+
+        /// Max length of the message signed by `num_signers` signers
+        pub const fn max_len(num_signers: usize) -> usize {
+            (u16::MAX as usize)
+                .saturating_sub(Base::HEADER_LEN)
+                .saturating_sub(Self::header_len(num_signers))
+        }
+
+        /// Max length of the message signed by `num_signers` signers that is
+        /// supported by the Ledger
+        pub const fn max_len_ledger(num_signers: usize) -> usize {
+            PACKET_DATA_SIZE
+                .saturating_sub(Base::HEADER_LEN)
+                .saturating_sub(Self::header_len(num_signers))
+        }
+
+        const fn header_len(num_signers: usize) -> usize {
+            Self::HEADER_LEN.saturating_add(num_signers.saturating_mul(PUBKEY_BYTES))
+        }
+
+        /// Construct a new OffchainMessage object for the given application
+        /// domain, signers and message
+        pub fn new(
+            application_domain: ApplicationDomain,
+            signers: &[Pubkey],
+            message: &[u8],
+        ) -> Result<Self, SanitizeError> {
+            Self::check_signers(signers)?;
+            let format = if message.is_empty() {
+                return Err(SanitizeError::InvalidValue);
+            } else if message.len() <= Self::max_len_ledger(signers.len()) {
+                if is_printable_ascii(message) {
+                    MessageFormat::RestrictedAscii
+                } else if is_utf8(message) {
+                    MessageFormat::LimitedUtf8
+                } else {
+                    return Err(SanitizeError::InvalidValue);
+                }
+            } else if message.len() <= Self::max_len(signers.len()) {
+                if is_utf8(message) {
+                    MessageFormat::ExtendedUtf8
+                } else {
+                    return Err(SanitizeError::InvalidValue);
+                }
+            } else {
+                return Err(SanitizeError::ValueOutOfBounds);
+            };
+            Ok(Self {
+                application_domain,
+                format,
+                signers: signers.to_vec(),
+                message: message.to_vec(),
+            })
+        }
+
+        fn check_signers(signers: &[Pubkey]) -> Result<(), SanitizeError> {
+            if signers.is_empty() || signers.len() > Self::MAX_SIGNERS {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+            for (i, signer) in signers.iter().enumerate() {
+                if signers[..i].contains(signer) {
+                    return Err(SanitizeError::InvalidValue);
+                }
+            }
+            Ok(())
+        }
+
+        /// Serialize the message to bytes, including the full header
+        pub fn serialize(&self, data: &mut Vec<u8>) -> Result<(), SanitizeError> {
+            // invalid messages shouldn't be possible, but a quick sanity check never hurts
+            assert!(
+                !self.message.is_empty() && self.message.len() <= Self::max_len(self.signers.len())
+            );
+            data.reserve(Self::header_len(self.signers.len()).saturating_add(self.message.len()));
+            // application domain
+            data.extend_from_slice(self.application_domain.as_bytes());
+            // format
+            data.push(self.format.into());
+            // signers
+            data.push(self.signers.len() as u8);
+            for signer in &self.signers {
+                data.extend_from_slice(signer.as_ref());
+            }
+            // message length
+            data.extend_from_slice(&(self.message.len() as u16).to_le_bytes());
+            // message
+            data.extend_from_slice(&self.message);
+            Ok(())
+        }
+
+        /// Deserialize the message from bytes that include a full header
+        pub fn deserialize(data: &[u8]) -> Result<Self, SanitizeError> {
+            // validate data length
+            if data.len() <= Self::HEADER_LEN {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+            // decode header
+            let (application_domain, data) = data.split_at(ApplicationDomain::LEN);
+            let application_domain = ApplicationDomain::new(application_domain.try_into().unwrap());
+            let format =
+                MessageFormat::try_from(data[0]).map_err(|_| SanitizeError::InvalidValue)?;
+            let num_signers = data[1] as usize;
+            let data = &data[2..];
+            let signers_len = num_signers * PUBKEY_BYTES;
+            if data.len() <= signers_len.saturating_add(2) {
+                return Err(SanitizeError::ValueOutOfBounds);
+            }
+            let (signers, data) = data.split_at(signers_len);
+            let signers: Vec<Pubkey> = signers
+                .chunks_exact(PUBKEY_BYTES)
+                .map(|signer| Pubkey::try_from(signer).unwrap())
+                .collect();
+            Self::check_signers(&signers)?;
+            let message_len = u16::from_le_bytes([data[0], data[1]]) as usize;
+            // check header
+            if message_len.saturating_add(2) != data.len() {
+                return Err(SanitizeError::InvalidValue);
+            }
+            let message = &data[2..];
+            // check format
+            let is_valid = match format {
+                MessageFormat::RestrictedAscii => {
+                    (message.len() <= Self::max_len_ledger(signers.len()))
+                        && is_printable_ascii(message)
+                }
+                MessageFormat::LimitedUtf8 => {
+                    (message.len() <= Self::max_len_ledger(signers.len())) && is_utf8(message)
+                }
+                MessageFormat::ExtendedUtf8 => {
+                    (message.len() <= Self::max_len(signers.len())) && is_utf8(message)
+                }
+            };
+
+            if is_valid {
+                Ok(Self {
+                    application_domain,
+                    format,
+                    signers,
+                    message: message.to_vec(),
+                })
+            } else {
+                Err(SanitizeError::InvalidValue)
+            }
+        }
+
+        pub fn get_application_domain(&self) -> &ApplicationDomain {
+            &self.application_domain
         }
 
         pub fn get_format(&self) -> MessageFormat {
             self.format
         }
 
+        pub fn get_signers(&self) -> &[Pubkey] {
+            &self.signers
+        }
+
         pub fn get_message(&self) -> &Vec<u8> {
             &self.message
         }
@@ -161,6 +400,7 @@ pub mod v0 {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum OffchainMessage {
     V0(v0::OffchainMessage),
+    V1(v1::OffchainMessage),
 }
 
 impl OffchainMessage {
@@ -169,6 +409,9 @@ impl OffchainMessage {
     pub const HEADER_LEN: usize = Self::SIGNING_DOMAIN.len() + 1;
 
     /// Construct a new OffchainMessage object from the given version and message
+    ///
+    /// Version 1 messages name their application domain and signers, and are
+    /// constructed with [`OffchainMessage::new_v1`].
     pub fn new(version: u8, message: &[u8]) -> Result<Self, SanitizeError> {
         match version {
             0 => Ok(Self::V0(v0::OffchainMessage::new(message)?)),
@@ -176,6 +419,20 @@ impl OffchainMessage {
         }
     }
 
+    /// Construct a new version 1 OffchainMessage object for the given
+    /// application domain, signers and message
+    pub fn new_v1(
+        application_domain: ApplicationDomain,
+        signers: &[Pubkey],
+        message: &[u8],
+    ) -> Result<Self, SanitizeError> {
+        Ok(Self::V1(v1::OffchainMessage::new(
+            application_domain,
+            signers,
+            message,
+        )?))
+    }
+
     /// Serialize the off-chain message to bytes including full header
     pub fn serialize(&self) -> Result<Vec<u8>, SanitizeError> {
         // serialize signing domain
@@ -187,6 +444,10 @@ impl OffchainMessage {
                 data.push(0);
                 msg.serialize(&mut data)?;
             }
+            Self::V1(msg) => {
+                data.push(1);
+                msg.serialize(&mut data)?;
+            }
         }
         Ok(data)
     }
@@ -196,53 +457,207 @@ impl OffchainMessage {
         if data.len() <= Self::HEADER_LEN {
             return Err(SanitizeError::ValueOutOfBounds);
         }
+        let has_signing_domain = data.starts_with(Self::SIGNING_DOMAIN);
         let version = data[Self::SIGNING_DOMAIN.len()];
         let data = &data[Self::SIGNING_DOMAIN.len().saturating_add(1)..];
         match version {
+            // version 0 messages were never checked for the signing domain
             0 => Ok(Self::V0(v0::OffchainMessage::deserialize(data)?)),
+            1 if has_signing_domain => Ok(Self::V1(v1::OffchainMessage::deserialize(data)?)),
+            1 => Err(SanitizeError::InvalidValue),
             _ => Err(SanitizeError::ValueOutOfBounds),
         }
     }
 
     /// Compute the hash of the off-chain message
     pub fn hash(&self) -> Result<Hash, SanitizeError> {
-        match self {
-            Self::V0(_) => v0::OffchainMessage::hash(&self.serialize()?),
-        }
+        // both versions hash the full serialized message
+        Ok(hash_serialized_message(&self.serialize()?))
     }
 
     pub fn get_version(&self) -> u8 {
         match self {
             Self::V0(_) => 0,
+            Self::V1(_) => 1,
         }
     }
 
     pub fn get_format(&self) -> MessageFormat {
         match self {
             Self::V0(msg) => msg.get_format(),
+            Self::V1(msg) => msg.get_format(),
         }
     }
 
     pub fn get_message(&self) -> &Vec<u8> {
         match self {
             Self::V0(msg) => msg.get_message(),
+            Self::V1(msg) => msg.get_message(),
+        }
+    }
+
+    /// The application domain of a version 1 message
+    pub fn get_application_domain(&self) -> Option<&ApplicationDomain> {
+        match self {
+            Self::V0(_) => None,
+            Self::V1(msg) => Some(msg.get_application_domain()),
+        }
+    }
+
+    /// The signers of a version 1 message; empty for version 0 messages,
+    /// which do not name their signer
+    pub fn get_signers(&self) -> &[Pubkey] {
+        match self {
+            Self::V0(_) => &[],
+            Self::V1(msg) => msg.get_signers(),
         }
     }
 
     /// Sign the message with provided keypair
+    ///
+    /// Fails if the message is version 1 and does not name the signer.
     pub fn sign(&self, signer: &dyn Signer) -> Result<Signature, SanitizeError> {
+        if let Self::V1(msg) = self {
+            if !msg.get_signers().contains(&signer.pubkey()) {
+                return Err(SanitizeError::InvalidValue);
+            }
+        }
         Ok(signer.sign_message(&self.serialize()?))
     }
 
     #[cfg(feature = "verify")]
     /// Verify that the message signature is valid for the given public key
-    pub fn verify(
-        &self,
-        signer: &trezoa_pubkey::Pubkey,
-        signature: &Signature,
-    ) -> Result<bool, SanitizeError> {
+    ///
+    /// A version 1 message is only valid for the signers it names.
+    pub fn verify(&self, signer: &Pubkey, signature: &Signature) -> Result<bool, SanitizeError> {
+        if let Self::V1(msg) = self {
+            if !msg.get_signers().contains(signer) {
+                return Ok(false);
+            }
+        }
         Ok(signature.verify(signer.as_ref(), &self.serialize()?))
     }
+
+    #[cfg(feature = "verify")]
+    /// Verify that a version 1 message is signed by all of its signers, with
+    /// `signatures` in the same order as the signers
+    pub fn verify_all(&self, signatures: &[Signature]) -> Result<bool, SanitizeError> {
+        let Self::V1(msg) = self else {
+            return Err(SanitizeError::InvalidValue);
+        };
+        if signatures.len() != msg.get_signers().len() {
+            return Ok(false);
+        }
+        let data = self.serialize()?;
+        Ok(msg
+            .get_signers()
+            .iter()
+            .zip(signatures)
+            .all(|(signer, signature)| signature.verify(signer.as_ref(), &data)))
+    }
+}
+
+/// A version 1 off-chain message together with the signatures of its
+/// signers, for transmitting the message detached from the context in which
+/// it was signed.
+///
+/// The envelope is serialized as the signature count (1), the signatures
+/// (64 each) in the order of the message signers, and the serialized message.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SignedOffchainMessage {
+    signatures: Vec<Signature>,
+    message: OffchainMessage,
+}
+
+impl SignedOffchainMessage {
+    /// Construct an envelope from a version 1 message and one signature per
+    /// message signer
+    ///
+    /// The signatures are not verified.
+    pub fn new(
+        message: OffchainMessage,
+        signatures: Vec<Signature>,
+    ) -> Result<Self, SanitizeError> {
+        match &message {
+            OffchainMessage::V0(_) => Err(SanitizeError::InvalidValue),
+            OffchainMessage::V1(msg) if msg.get_signers().len() != signatures.len() => {
+                Err(SanitizeError::InvalidValue)
+            }
+            OffchainMessage::V1(_) => Ok(Self {
+                signatures,
+                message,
+            }),
+        }
+    }
+
+    /// Sign a version 1 message with every signer it names, each of which
+    /// must be among `signers`
+    pub fn sign(message: OffchainMessage, signers: &[&dyn Signer]) -> Result<Self, SanitizeError> {
+        let data = message.serialize()?;
+        let signatures = message
+            .get_signers()
+            .iter()
+            .map(|pubkey| {
+                signers
+                    .iter()
+                    .find(|signer| signer.pubkey() == *pubkey)
+                    .map(|signer| signer.sign_message(&data))
+                    .ok_or(SanitizeError::InvalidValue)
+            })
+            .collect::<Result<_, _>>()?;
+        Self::new(message, signatures)
+    }
+
+    /// Serialize the envelope to bytes
+    pub fn serialize(&self) -> Result<Vec<u8>, SanitizeError> {
+        let message = self.message.serialize()?;
+        let mut data = Vec::with_capacity(
+            self.signatures
+                .len()
+                .saturating_mul(SIGNATURE_BYTES)
+                .saturating_add(message.len())
+                .saturating_add(1),
+        );
+        // the message has at most u8::MAX signers
+        data.push(self.signatures.len() as u8);
+        for signature in &self.signatures {
+            data.extend_from_slice(signature.as_ref());
+        }
+        data.extend_from_slice(&message);
+        Ok(data)
+    }
+
+    /// Deserialize the envelope from bytes
+    pub fn deserialize(data: &[u8]) -> Result<Self, SanitizeError> {
+        let (&num_signatures, data) = data.split_first().ok_or(SanitizeError::ValueOutOfBounds)?;
+        let signatures_len = usize::from(num_signatures).saturating_mul(SIGNATURE_BYTES);
+        if data.len() < signatures_len {
+            return Err(SanitizeError::ValueOutOfBounds);
+        }
+        let (signatures, data) = data.split_at(signatures_len);
+        let signatures = signatures
+            .chunks_exact(SIGNATURE_BYTES)
+            .map(|signature| Signature::try_from(signature).unwrap())
+            .collect();
+        Self::new(OffchainMessage::deserialize(data)?, signatures)
+    }
+
+    pub fn get_message(&self) -> &OffchainMessage {
+        &self.message
+    }
+
+    pub fn get_signatures(&self) -> &[Signature] {
+        &self.signatures
+    }
+
+    #[cfg(feature = "verify")]
+    /// Verify that the message is signed by all of its signers
+    ///
+    /// Callers must also check that the message's application domain is
+    /// their own.
+    pub fn verify(&self) -> Result<bool, SanitizeError> {
+        self.message.verify_all(&self.signatures)
+    }
 }
 
 #[cfg(test)]
@@ -299,4 +714,150 @@ mod tests {
         let signature = message.sign(&keypair).unwrap();
         assert!(message.verify(&keypair.pubkey(), &signature).unwrap());
     }
+
+    #[test]
+    fn test_offchain_message_v1() {
+        let domain = ApplicationDomain::from_name("example.com").unwrap();
+        let signers = [Keypair::new().pubkey(), Keypair::new().pubkey()];
+        let message = OffchainMessage::new_v1(domain, &signers, b"Sign in").unwrap();
+        assert_eq!(message.get_version(), 1);
+        assert_eq!(message.get_format(), MessageFormat::RestrictedAscii);
+        assert_eq!(message.get_application_domain(), Some(&domain));
+        assert_eq!(message.get_signers(), signers);
+        assert_eq!(message.get_message().as_slice(), b"Sign in");
+
+        let mut serialized = OffchainMessage::SIGNING_DOMAIN.to_vec();
+        serialized.push(1);
+        serialized.extend_from_slice(b"example.com");
+        serialized.extend_from_slice(&[0; 21]);
+        serialized.extend_from_slice(&[0, 2]);
+        serialized.extend_from_slice(signers[0].as_ref());
+        serialized.extend_from_slice(signers[1].as_ref());
+        serialized.extend_from_slice(&[7, 0]);
+        serialized.extend_from_slice(b"Sign in");
+        assert_eq!(message.serialize().unwrap(), serialized);
+        assert_eq!(message, OffchainMessage::deserialize(&serialized).unwrap());
+
+        // the signer count must match the signers that follow it
+        // (after the base header, application domain and message format)
+        serialized[50] = 3;
+        assert!(OffchainMessage::deserialize(&serialized).is_err());
+    }
+
+    #[test]
+    fn test_offchain_message_v1_invalid() {
+        let domain = ApplicationDomain::new([1; 32]);
+        let signer = Keypair::new().pubkey();
+        assert_eq!(
+            OffchainMessage::new_v1(domain, &[], b"Test Message"),
+            Err(SanitizeError::ValueOutOfBounds)
+        );
+        assert_eq!(
+            OffchainMessage::new_v1(domain, &[signer, signer], b"Test Message"),
+            Err(SanitizeError::InvalidValue)
+        );
+        assert_eq!(
+            OffchainMessage::new_v1(domain, &[signer], b""),
+            Err(SanitizeError::InvalidValue)
+        );
+        assert_eq!(
+            ApplicationDomain::from_name(&"a".repeat(33)),
+            Err(SanitizeError::ValueOutOfBounds)
+        );
+
+        let mut serialized = OffchainMessage::new_v1(domain, &[signer], b"Test Message")
+            .unwrap()
+            .serialize()
+            .unwrap();
+        serialized[0] = 0;
+        assert_eq!(
+            OffchainMessage::deserialize(&serialized),
+            Err(SanitizeError::InvalidValue)
+        );
+
+        // version 0 messages are still accepted without the signing domain
+        let message = OffchainMessage::new(0, b"Test Message").unwrap();
+        let mut serialized = message.serialize().unwrap();
+        serialized[0] = 0;
+        assert_eq!(OffchainMessage::deserialize(&serialized), Ok(message));
+    }
+
+    #[test]
+    fn test_offchain_message_v1_sign_and_verify() {
+        let keypairs = [Keypair::new(), Keypair::new()];
+        let signers = [keypairs[0].pubkey(), keypairs[1].pubkey()];
+        let domain = ApplicationDomain::from_name("example.com").unwrap();
+        let message = OffchainMessage::new_v1(domain, &signers, b"Test Message").unwrap();
+        let signatures = [
+            message.sign(&keypairs[0]).unwrap(),
+            message.sign(&keypairs[1]).unwrap(),
+        ];
+        assert!(message.verify(&signers[0], &signatures[0]).unwrap());
+        assert!(message.verify_all(&signatures).unwrap());
+        assert!(!message.verify_all(&[signatures[1], signatures[0]]).unwrap());
+        assert!(!message.verify_all(&signatures[..1]).unwrap());
+
+        // a signer that is not named by the message
+        let outsider = Keypair::new();
+        assert_eq!(message.sign(&outsider), Err(SanitizeError::InvalidValue));
+        let signature = outsider.sign_message(&message.serialize().unwrap());
+        assert!(!message.verify(&outsider.pubkey(), &signature).unwrap());
+
+        // signatures do not carry over to another application domain
+        let other = OffchainMessage::new_v1(
+            ApplicationDomain::from_name("example.org").unwrap(),
+            &signers,
+            b"Test Message",
+        )
+        .unwrap();
+        assert!(!other.verify_all(&signatures).unwrap());
+
+        let v0_message = OffchainMessage::new(0, b"Test Message").unwrap();
+        assert_eq!(
+            v0_message.verify_all(&signatures[..1]),
+            Err(SanitizeError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn test_signed_offchain_message() {
+        let keypairs = [Keypair::new(), Keypair::new()];
+        let signers = [keypairs[0].pubkey(), keypairs[1].pubkey()];
+        let domain = ApplicationDomain::from_name("example.com").unwrap();
+        let message = OffchainMessage::new_v1(domain, &signers, b"Test Message").unwrap();
+        // the signers may be given in any order
+        let signed =
+            SignedOffchainMessage::sign(message.clone(), &[&keypairs[1], &keypairs[0]]).unwrap();
+        assert_eq!(signed.get_message(), &message);
+        assert_eq!(signed.get_signatures().len(), 2);
+        assert!(signed.verify().unwrap());
+
+        let mut serialized = signed.serialize().unwrap();
+        assert_eq!(serialized[0], 2);
+        assert!(serialized.ends_with(&message.serialize().unwrap()));
+        assert_eq!(
+            SignedOffchainMessage::deserialize(&serialized).unwrap(),
+            signed
+        );
+
+        // tampering with the message invalidates the signatures
+        *serialized.last_mut().unwrap() = b'!';
+        let tampered = SignedOffchainMessage::deserialize(&serialized).unwrap();
+        assert!(!tampered.verify().unwrap());
+
+        // a signature must be present for every signer
+        assert_eq!(
+            SignedOffchainMessage::sign(message.clone(), &[&keypairs[0]]),
+            Err(SanitizeError::InvalidValue)
+        );
+        serialized[0] = 1;
+        assert!(SignedOffchainMessage::deserialize(&serialized).is_err());
+        assert_eq!(
+            SignedOffchainMessage::new(
+                OffchainMessage::new(0, b"Test Message").unwrap(),
+                vec![Signature::default()]
+            ),
+            Err(SanitizeError::InvalidValue)
+        );
+    }
 }
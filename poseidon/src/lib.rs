@@ -3,7 +3,7 @@
 //!
 //! [Poseidon]: https://www.poseidon-hash.info/
 
-use thiserror::Error;
+use {thiserror::Error, std::io};
 
 #[doc(hidden)]
 pub mod legacy;
@@ -131,6 +131,7 @@ impl From<Parameters> for u64 {
 
 /// Endianness of inputs and result.
 #[repr(u64)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Endianness {
     /// Big-endian inputs and result.
     BigEndian = 0,
@@ -282,6 +283,74 @@ pub fn hashv(
     }
 }
 
+/// Hashes data incrementally with the [`Parameters::Bn254X5`] configuration.
+///
+/// The appended data is split into 32-byte inputs when the hash is computed,
+/// so it must be between 1 and 12 inputs long and each input must be smaller
+/// than the field modulus. Since computing the hash can fail, it implements
+/// [`trezoa_hash::hasher::TryHasher`] rather than
+/// [`trezoa_hash::hasher::Hasher`].
+#[derive(Clone, Debug)]
+pub struct Hasher {
+    endianness: Endianness,
+    data: Vec<u8>,
+}
+
+impl Default for Hasher {
+    fn default() -> Self {
+        Self::new(Endianness::BigEndian)
+    }
+}
+
+impl Hasher {
+    pub fn new(endianness: Endianness) -> Self {
+        Self {
+            endianness,
+            data: Vec::new(),
+        }
+    }
+
+    pub fn hash(&mut self, val: &[u8]) {
+        self.data.extend_from_slice(val);
+    }
+
+    pub fn hashv(&mut self, vals: &[&[u8]]) {
+        for val in vals {
+            self.hash(val);
+        }
+    }
+
+    /// Return the Poseidon hash of the appended data.
+    pub fn try_result(self) -> Result<PoseidonHash, PoseidonSyscallError> {
+        let inputs: Vec<&[u8]> = self.data.chunks(HASH_BYTES).collect();
+        hashv(Parameters::Bn254X5, self.endianness, &inputs)
+    }
+}
+
+impl trezoa_hash::hasher::TryHasher for Hasher {
+    type Error = PoseidonSyscallError;
+
+    fn hash(&mut self, val: &[u8]) {
+        self.hash(val);
+    }
+
+    fn try_result(self) -> Result<trezoa_hash::Hash, Self::Error> {
+        self.try_result()
+            .map(|hash| trezoa_hash::Hash::new_from_array(hash.to_bytes()))
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.hash(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 /// Return a Poseidon hash for the given data with the given elliptic curve and
 /// endianness.
 ///
@@ -322,7 +391,7 @@ pub fn hash(
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use {super::*, trezoa_hash::hasher::TryHasher};
 
     #[test]
     fn test_poseidon_input_ones_be() {
@@ -469,4 +538,41 @@ mod tests {
             assert!(res.is_err());
         }
     }
+
+    #[test]
+    fn test_poseidon_hasher() {
+        let input1 = [1u8; 32];
+        let input2 = [2u8; 32];
+        let expected = hashv(
+            Parameters::Bn254X5,
+            Endianness::LittleEndian,
+            &[&input1, &input2],
+        )
+        .unwrap()
+        .to_bytes();
+
+        let mut hasher = Hasher::new(Endianness::LittleEndian);
+        hasher.hashv(&[&input1, &input2[..16]]);
+        io::Write::write_all(&mut hasher, &input2[16..]).unwrap();
+        assert_eq!(hasher.try_result().unwrap().to_bytes(), expected);
+
+        let mut hasher = Hasher::default();
+        hasher.hash(&[0xff; 32]);
+        assert!(hasher.try_result().is_err());
+        assert!(Hasher::default().try_result().is_err());
+
+        let expected = hashv(
+            Parameters::Bn254X5,
+            Endianness::BigEndian,
+            &[&input1, &input2],
+        )
+        .unwrap()
+        .to_bytes();
+        let hash = <Hasher as TryHasher>::try_hashv_once(&[&input1, &input2]).unwrap();
+        assert_eq!(hash.to_bytes(), expected);
+        assert!(matches!(
+            <Hasher as TryHasher>::try_hash_once(&[0xff; 32]),
+            Err(PoseidonSyscallError::InputLargerThanModulus)
+        ));
+    }
 }
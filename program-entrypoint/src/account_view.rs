@@ -0,0 +1,308 @@
+//! Zero-copy program input deserialization into [`AccountView`]s.
+
+use {
+    crate::{BPF_ALIGN_OF_U128, NON_DUP_MARKER},
+    trezoa_account_view::{AccountView, RuntimeAccount, MAX_PERMITTED_DATA_INCREASE},
+    trezoa_pubkey::Pubkey,
+    std::{
+        mem::{size_of, MaybeUninit},
+        slice::from_raw_parts,
+    },
+};
+
+/// Declare the program entrypoint, passing the accounts to the program as
+/// [`AccountView`]s, and set up global handlers.
+///
+/// This is similar to the [`entrypoint_no_alloc!`] macro, except that each
+/// account is an [`AccountView`] pointing directly into the input buffer
+/// instead of an `AccountInfo`. Account data borrows are tracked in the
+/// input buffer, so nothing is allocated and no `Rc<RefCell>` is created for
+/// any account.
+///
+/// For more information about how the program entrypoint behaves and what it
+/// does, please see the documentation for [`entrypoint!`].
+///
+/// The argument is the name of a function with this type signature:
+///
+/// ```ignore
+/// fn process_instruction(
+///     program_id: &Pubkey,      // Public key of the account the program was loaded into
+///     accounts: &[AccountView], // All accounts required to process the instruction
+///     instruction_data: &[u8],  // Serialized instruction-specific data
+/// ) -> ProgramResult;
+/// ```
+///
+/// The accounts are written into an array of 64 accounts by default. An
+/// instruction with more accounts causes a panic, unless a larger maximum is
+/// given as a second argument:
+///
+/// ```ignore
+/// entrypoint_account_view!(process_instruction, 128);
+/// ```
+///
+/// [`entrypoint!`]: crate::entrypoint
+/// [`entrypoint_no_alloc!`]: crate::entrypoint_no_alloc
+#[macro_export]
+macro_rules! entrypoint_account_view {
+    ($process_instruction:ident) => {
+        $crate::entrypoint_account_view!($process_instruction, 64);
+    };
+    ($process_instruction:ident, $max_accounts:expr) => {
+        /// # Safety
+        #[no_mangle]
+        pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+            const UNINIT_ACCOUNT_VIEW: core::mem::MaybeUninit<$crate::__AccountView> =
+                core::mem::MaybeUninit::<$crate::__AccountView>::uninit();
+            let mut accounts = [UNINIT_ACCOUNT_VIEW; $max_accounts];
+            let (program_id, num_accounts, instruction_data) =
+                unsafe { $crate::deserialize_account_views(input, &mut accounts) };
+            // Use `slice_assume_init_ref` once it's stabilized
+            let accounts = unsafe {
+                core::slice::from_raw_parts(
+                    accounts.as_ptr() as *const $crate::__AccountView,
+                    num_accounts,
+                )
+            };
+            match $process_instruction(program_id, accounts, instruction_data) {
+                Ok(()) => $crate::SUCCESS,
+                Err(error) => error.into(),
+            }
+        }
+        $crate::custom_heap_default!();
+        $crate::custom_panic_default!();
+    };
+}
+
+/// Create the [`AccountView`] of the non-duplicate account serialized at
+/// `offset`, which is the offset of its duplicate marker, and return it with
+/// the offset of the next account.
+///
+/// The integer arithmetic in this method is safe when called on a buffer that
+/// was serialized by runtime.
+#[allow(clippy::arithmetic_side_effects)]
+#[inline(always)]
+pub(crate) unsafe fn deserialize_account_view(
+    input: *mut u8,
+    mut offset: usize,
+) -> (AccountView, usize) {
+    // The duplicate marker is the first byte of the `RuntimeAccount` and is
+    // reused as its borrow state, which starts as `NOT_BORROWED`.
+    let account = input.add(offset) as *mut RuntimeAccount;
+    let data_len = (*account).data_len as usize;
+    offset += size_of::<RuntimeAccount>();
+
+    // rent epoch is not deserialized, so skip it
+    offset += data_len + MAX_PERMITTED_DATA_INCREASE + size_of::<u64>();
+    offset += (offset as *const u8).align_offset(BPF_ALIGN_OF_U128); // padding
+
+    (AccountView::new_unchecked(account), offset)
+}
+
+/// Deserialize the instruction data and program id, which follow the
+/// accounts at `offset`.
+#[allow(clippy::arithmetic_side_effects)]
+#[inline(always)]
+pub(crate) unsafe fn deserialize_instruction_data_and_program_id<'a>(
+    input: *mut u8,
+    mut offset: usize,
+) -> (&'a [u8], &'a Pubkey) {
+    #[allow(clippy::cast_ptr_alignment)]
+    let instruction_data_len = *(input.add(offset) as *const u64) as usize;
+    offset += size_of::<u64>();
+
+    let instruction_data = from_raw_parts(input.add(offset), instruction_data_len);
+    offset += instruction_data_len;
+
+    let program_id: &Pubkey = &*(input.add(offset) as *const Pubkey);
+
+    (instruction_data, program_id)
+}
+
+/// Deserialize the input arguments into [`AccountView`]s
+///
+/// Differs from [`deserialize_into`] by writing views of the accounts in the
+/// input buffer instead of `AccountInfo`s. A duplicate account is a copy of
+/// the view of the account it duplicates, so both share the same borrow
+/// state. Nothing is allocated.
+///
+/// Panics if `accounts` is not large enough.
+///
+/// The integer arithmetic in this method is safe when called on a buffer that was
+/// serialized by runtime. Use with buffers serialized otherwise is unsupported and
+/// done at one's own risk.
+///
+/// # Safety
+///
+/// `input` must point to the serialized program input, and the returned views
+/// must not outlive it.
+///
+/// [`deserialize_into`]: crate::deserialize_into
+#[allow(clippy::arithmetic_side_effects, clippy::clone_on_copy)]
+pub unsafe fn deserialize_account_views<'a, const MAX_ACCOUNTS: usize>(
+    input: *mut u8,
+    accounts: &mut [MaybeUninit<AccountView>; MAX_ACCOUNTS],
+) -> (&'a Pubkey, usize, &'a [u8]) {
+    let mut offset: usize = 0;
+
+    // Number of accounts present
+
+    #[allow(clippy::cast_ptr_alignment)]
+    let num_accounts = *(input.add(offset) as *const u64) as usize;
+    offset += size_of::<u64>();
+
+    if num_accounts > MAX_ACCOUNTS {
+        panic!(
+            "{} accounts provided, but only {} are supported",
+            num_accounts, MAX_ACCOUNTS
+        );
+    }
+
+    // Account Views
+
+    for i in 0..num_accounts {
+        let dup_info = *input.add(offset);
+        if dup_info == NON_DUP_MARKER {
+            let (account_view, new_offset) = deserialize_account_view(input, offset);
+            offset = new_offset;
+            accounts[i].write(account_view);
+        } else {
+            offset += size_of::<u64>(); // marker and padding
+
+            // Duplicate account, copy the view of the original
+            accounts[i].write(accounts[dup_info as usize].assume_init_ref().clone());
+        }
+    }
+
+    // Instruction data and Program Id
+
+    let (instruction_data, program_id) = deserialize_instruction_data_and_program_id(input, offset);
+
+    (program_id, num_accounts, instruction_data)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use {super::*, trezoa_account_view::NOT_BORROWED};
+
+    pub(crate) struct TestAccount {
+        pub(crate) key: Pubkey,
+        pub(crate) owner: Pubkey,
+        pub(crate) lamports: u64,
+        pub(crate) data: Vec<u8>,
+        pub(crate) is_signer: bool,
+        pub(crate) is_writable: bool,
+    }
+
+    /// Serialize program input the way the runtime does, into a buffer of
+    /// `u64`s to get the alignment of the runtime's input region.
+    #[allow(clippy::arithmetic_side_effects)]
+    pub(crate) fn serialize(
+        accounts: &[Result<TestAccount, u8>],
+        instruction_data: &[u8],
+        program_id: &Pubkey,
+    ) -> Vec<u64> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(accounts.len() as u64).to_le_bytes());
+        for account in accounts {
+            match account {
+                Ok(account) => {
+                    bytes.push(NON_DUP_MARKER);
+                    bytes.push(account.is_signer.into());
+                    bytes.push(account.is_writable.into());
+                    bytes.push(0); // executable
+                    bytes.extend_from_slice(&[0; 4]); // resize delta
+                    bytes.extend_from_slice(account.key.as_ref());
+                    bytes.extend_from_slice(account.owner.as_ref());
+                    bytes.extend_from_slice(&account.lamports.to_le_bytes());
+                    bytes.extend_from_slice(&(account.data.len() as u64).to_le_bytes());
+                    bytes.extend_from_slice(&account.data);
+                    bytes.resize(bytes.len() + MAX_PERMITTED_DATA_INCREASE, 0);
+                    bytes.resize(bytes.len().next_multiple_of(BPF_ALIGN_OF_U128), 0);
+                    bytes.extend_from_slice(&u64::MAX.to_le_bytes()); // rent epoch
+                }
+                Err(dup_index) => {
+                    bytes.push(*dup_index);
+                    bytes.extend_from_slice(&[0; 7]);
+                }
+            }
+        }
+        bytes.extend_from_slice(&(instruction_data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(instruction_data);
+        bytes.extend_from_slice(program_id.as_ref());
+        bytes.resize(bytes.len().next_multiple_of(size_of::<u64>()), 0);
+        bytes
+            .chunks_exact(size_of::<u64>())
+            .map(|chunk| u64::from_ne_bytes(chunk.try_into().unwrap()))
+            .collect()
+    }
+
+    pub(crate) fn test_accounts() -> Vec<Result<TestAccount, u8>> {
+        vec![
+            Ok(TestAccount {
+                key: Pubkey::new_from_array([1; 32]),
+                owner: Pubkey::new_from_array([2; 32]),
+                lamports: 42,
+                data: vec![1, 2, 3],
+                is_signer: true,
+                is_writable: true,
+            }),
+            Ok(TestAccount {
+                key: Pubkey::new_from_array([3; 32]),
+                owner: Pubkey::new_from_array([4; 32]),
+                lamports: 7,
+                data: vec![],
+                is_signer: false,
+                is_writable: false,
+            }),
+            Err(0),
+        ]
+    }
+
+    #[test]
+    fn test_deserialize_account_views() {
+        let program_id = Pubkey::new_from_array([9; 32]);
+        let mut input = serialize(&test_accounts(), &[5, 6], &program_id);
+
+        const UNINIT_ACCOUNT_VIEW: MaybeUninit<AccountView> = MaybeUninit::uninit();
+        let mut accounts = [UNINIT_ACCOUNT_VIEW; 4];
+        let (deserialized_program_id, num_accounts, instruction_data) =
+            unsafe { deserialize_account_views(input.as_mut_ptr() as *mut u8, &mut accounts) };
+        assert_eq!(deserialized_program_id, &program_id);
+        assert_eq!(num_accounts, 3);
+        assert_eq!(instruction_data, &[5, 6]);
+
+        let accounts =
+            unsafe { from_raw_parts(accounts.as_ptr() as *const AccountView, num_accounts) };
+        assert_eq!(accounts[0].address(), &Pubkey::new_from_array([1; 32]));
+        assert!(accounts[0].owned_by(&Pubkey::new_from_array([2; 32])));
+        assert_eq!(accounts[0].lamports(), 42);
+        assert_eq!(&*accounts[0].try_borrow().unwrap(), &[1, 2, 3]);
+        assert!(accounts[0].is_signer());
+        assert!(accounts[0].is_writable());
+        assert_eq!(accounts[1].address(), &Pubkey::new_from_array([3; 32]));
+        assert_eq!(accounts[1].lamports(), 7);
+        assert!(accounts[1].is_data_empty());
+        assert!(!accounts[1].is_signer());
+        assert!(!accounts[1].is_writable());
+
+        // the duplicate shares the borrow state of the original
+        assert_eq!(accounts[2], accounts[0]);
+        let data = accounts[0].try_borrow_mut().unwrap();
+        assert!(accounts[2].try_borrow().is_err());
+        drop(data);
+        assert!(!accounts[2].is_borrowed());
+        assert_eq!(
+            unsafe { (*accounts[2].account_ptr()).borrow_state },
+            NOT_BORROWED
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "3 accounts provided, but only 2 are supported")]
+    fn test_deserialize_account_views_too_many_accounts() {
+        let mut input = serialize(&test_accounts(), &[], &Pubkey::default());
+        const UNINIT_ACCOUNT_VIEW: MaybeUninit<AccountView> = MaybeUninit::uninit();
+        let mut accounts = [UNINIT_ACCOUNT_VIEW; 2];
+        unsafe { deserialize_account_views(input.as_mut_ptr() as *mut u8, &mut accounts) };
+    }
+}
@@ -0,0 +1,269 @@
+//! Program input deserialization that parses accounts on demand.
+
+use {
+    crate::{
+        account_view::{deserialize_account_view, deserialize_instruction_data_and_program_id},
+        NON_DUP_MARKER,
+    },
+    trezoa_account_view::AccountView,
+    trezoa_program_error::ProgramError,
+    trezoa_pubkey::Pubkey,
+    std::mem::size_of,
+};
+
+/// Declare the program entrypoint, passing the program an
+/// [`InstructionContext`] that parses accounts on demand, and set up global
+/// handlers.
+///
+/// Unlike [`entrypoint_account_view!`], no account is parsed before the
+/// program runs. The program takes accounts one at a time with
+/// [`InstructionContext::next_account`], so the accounts after the last one
+/// it uses cost nothing.
+///
+/// For more information about how the program entrypoint behaves and what it
+/// does, please see the documentation for [`entrypoint!`].
+///
+/// The argument is the name of a function with this type signature:
+///
+/// ```ignore
+/// fn process_instruction(
+///     context: InstructionContext, // Accounts, instruction data and program id
+/// ) -> ProgramResult;
+/// ```
+///
+/// # Examples
+///
+/// ```ignore
+/// use trezoa_program_entrypoint::{lazy_entrypoint, InstructionContext, ProgramResult};
+///
+/// lazy_entrypoint!(process_instruction);
+///
+/// pub fn process_instruction(mut context: InstructionContext) -> ProgramResult {
+///     let payer = context.next_account()?;
+///     let destination = context.next_account()?;
+///     let instruction_data = context.instruction_data()?;
+///     // ...
+///     Ok(())
+/// }
+/// ```
+///
+/// [`entrypoint!`]: crate::entrypoint
+/// [`entrypoint_account_view!`]: crate::entrypoint_account_view
+#[macro_export]
+macro_rules! lazy_entrypoint {
+    ($process_instruction:ident) => {
+        /// # Safety
+        #[no_mangle]
+        pub unsafe extern "C" fn entrypoint(input: *mut u8) -> u64 {
+            let context = unsafe { $crate::InstructionContext::new(input) };
+            match $process_instruction(context) {
+                Ok(()) => $crate::SUCCESS,
+                Err(error) => error.into(),
+            }
+        }
+        $crate::custom_heap_default!();
+        $crate::custom_panic_default!();
+    };
+}
+
+/// A cursor over the serialized program input, which parses each account
+/// when it is requested.
+///
+/// The instruction data and program id are serialized after the accounts,
+/// so they are available once every account has been parsed.
+#[derive(Debug)]
+pub struct InstructionContext {
+    input: *mut u8,
+    num_accounts: usize,
+    // Index and offset of the next account to parse
+    next_index: usize,
+    offset: usize,
+}
+
+impl InstructionContext {
+    /// Create a context over the serialized program input.
+    ///
+    /// # Safety
+    ///
+    /// `input` must point to the serialized program input, and the context
+    /// and the views it returns must not outlive it.
+    #[inline(always)]
+    pub unsafe fn new(input: *mut u8) -> Self {
+        #[allow(clippy::cast_ptr_alignment)]
+        let num_accounts = *(input as *const u64) as usize;
+        Self {
+            input,
+            num_accounts,
+            next_index: 0,
+            offset: size_of::<u64>(),
+        }
+    }
+
+    /// Number of accounts of the instruction that have not been parsed yet.
+    #[inline(always)]
+    pub fn remaining(&self) -> usize {
+        self.num_accounts.saturating_sub(self.next_index)
+    }
+
+    /// Parse the next account.
+    ///
+    /// A duplicate account is resolved to a view of the account it
+    /// duplicates, which shares its borrow state. Finding that account walks
+    /// the input from the first account.
+    ///
+    /// Returns [`ProgramError::NotEnoughAccountKeys`] if every account has
+    /// been parsed.
+    #[allow(clippy::arithmetic_side_effects)]
+    #[inline(always)]
+    pub fn next_account(&mut self) -> Result<AccountView, ProgramError> {
+        if self.remaining() == 0 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        // SAFETY: There is an account at the offset, and the input was
+        // serialized by the runtime.
+        let (account_view, offset) = unsafe {
+            let dup_info = *self.input.add(self.offset);
+            if dup_info == NON_DUP_MARKER {
+                deserialize_account_view(self.input, self.offset)
+            } else {
+                (
+                    self.duplicated_account(dup_info as usize),
+                    self.offset + size_of::<u64>(),
+                )
+            }
+        };
+        self.next_index += 1;
+        self.offset = offset;
+        Ok(account_view)
+    }
+
+    /// The instruction data.
+    ///
+    /// Returns [`ProgramError::InvalidInstructionData`] if some accounts have
+    /// not been parsed yet.
+    #[inline(always)]
+    pub fn instruction_data(&self) -> Result<&[u8], ProgramError> {
+        if self.remaining() != 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        // SAFETY: Every account has been parsed.
+        Ok(unsafe { self.instruction_data_unchecked() })
+    }
+
+    /// The instruction data, without checking that every account has been
+    /// parsed.
+    ///
+    /// # Safety
+    ///
+    /// [`InstructionContext::remaining`] must be zero.
+    #[inline(always)]
+    pub unsafe fn instruction_data_unchecked(&self) -> &[u8] {
+        deserialize_instruction_data_and_program_id(self.input, self.offset).0
+    }
+
+    /// The address of the program being executed.
+    ///
+    /// Returns [`ProgramError::InvalidInstructionData`] if some accounts have
+    /// not been parsed yet.
+    #[inline(always)]
+    pub fn program_id(&self) -> Result<&Pubkey, ProgramError> {
+        if self.remaining() != 0 {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        // SAFETY: Every account has been parsed.
+        Ok(unsafe { self.program_id_unchecked() })
+    }
+
+    /// The address of the program being executed, without checking that
+    /// every account has been parsed.
+    ///
+    /// # Safety
+    ///
+    /// [`InstructionContext::remaining`] must be zero.
+    #[inline(always)]
+    pub unsafe fn program_id_unchecked(&self) -> &Pubkey {
+        deserialize_instruction_data_and_program_id(self.input, self.offset).1
+    }
+
+    /// The offset of the account after the account at `offset`.
+    #[allow(clippy::arithmetic_side_effects)]
+    #[inline(always)]
+    unsafe fn skip_account(&self, offset: usize) -> usize {
+        if *self.input.add(offset) == NON_DUP_MARKER {
+            deserialize_account_view(self.input, offset).1
+        } else {
+            offset + size_of::<u64>()
+        }
+    }
+
+    /// The view of the account at `index`, which the runtime guarantees to be
+    /// the first occurrence of a duplicated account.
+    unsafe fn duplicated_account(&self, index: usize) -> AccountView {
+        let offset = (0..index).fold(size_of::<u64>(), |offset, _| self.skip_account(offset));
+        deserialize_account_view(self.input, offset).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::account_view::tests::{serialize, test_accounts},
+    };
+
+    #[test]
+    fn test_instruction_context() {
+        let program_id = Pubkey::new_from_array([9; 32]);
+        let mut input = serialize(&test_accounts(), &[5, 6], &program_id);
+        let mut context = unsafe { InstructionContext::new(input.as_mut_ptr() as *mut u8) };
+
+        // instruction data and program id are only read after the accounts
+        assert_eq!(context.remaining(), 3);
+        assert_eq!(
+            context.instruction_data(),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            context.program_id(),
+            Err(ProgramError::InvalidInstructionData)
+        );
+
+        let first = context.next_account().unwrap();
+        assert_eq!(first.address(), &Pubkey::new_from_array([1; 32]));
+        assert_eq!(&*first.try_borrow().unwrap(), &[1, 2, 3]);
+        assert_eq!(context.remaining(), 2);
+        assert!(context.instruction_data().is_err());
+
+        let second = context.next_account().unwrap();
+        assert_eq!(second.address(), &Pubkey::new_from_array([3; 32]));
+        assert_eq!(second.lamports(), 7);
+
+        // the duplicate is resolved to the first account
+        let duplicate = context.next_account().unwrap();
+        assert_eq!(duplicate, first);
+        let data = first.try_borrow_mut().unwrap();
+        assert!(duplicate.try_borrow().is_err());
+        drop(data);
+
+        assert_eq!(context.remaining(), 0);
+        assert_eq!(
+            context.next_account(),
+            Err(ProgramError::NotEnoughAccountKeys)
+        );
+        assert_eq!(context.instruction_data(), Ok(&[5, 6][..]));
+        assert_eq!(context.program_id(), Ok(&program_id));
+        assert_eq!(unsafe { context.instruction_data_unchecked() }, &[5, 6]);
+        assert_eq!(unsafe { context.program_id_unchecked() }, &program_id);
+    }
+
+    #[test]
+    fn test_instruction_context_without_accounts() {
+        let program_id = Pubkey::new_from_array([9; 32]);
+        let mut input = serialize(&[], &[], &program_id);
+        let mut context = unsafe { InstructionContext::new(input.as_mut_ptr() as *mut u8) };
+        assert_eq!(context.remaining(), 0);
+        assert!(context.next_account().is_err());
+        assert!(context.instruction_data().unwrap().is_empty());
+        assert_eq!(context.program_id(), Ok(&program_id));
+    }
+}
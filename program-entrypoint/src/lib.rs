@@ -2,6 +2,16 @@
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
 extern crate alloc;
+#[cfg(feature = "account-view")]
+mod account_view;
+#[cfg(feature = "account-view")]
+mod lazy;
+
+#[cfg(feature = "account-view")]
+pub use {
+    account_view::deserialize_account_views, lazy::InstructionContext,
+    trezoa_account_view::AccountView as __AccountView,
+};
 use {
     alloc::vec::Vec,
     trezoa_account_info::AccountInfo,
@@ -0,0 +1,104 @@
+//! Derive macro for `trezoa-program-event`.
+//!
+//! See the documentation of `trezoa-program-event` for details.
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+extern crate proc_macro;
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::TokenStream as TokenStream2,
+    quote::quote,
+    sha2::{Digest, Sha256},
+    syn::{parse_macro_input, Attribute, DeriveInput, Error, LitStr, Result},
+};
+
+/// The encoding of an event.
+enum Encoding {
+    Borsh,
+    Wincode,
+}
+
+/// The arguments of the `#[event(...)]` attribute.
+struct EventArgs {
+    name: Option<LitStr>,
+    encoding: Encoding,
+}
+
+impl EventArgs {
+    fn from_attributes(attrs: &[Attribute]) -> Result<Self> {
+        let mut args = Self {
+            name: None,
+            encoding: Encoding::Borsh,
+        };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("event")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    args.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("encoding") {
+                    let encoding: LitStr = meta.value()?.parse()?;
+                    args.encoding = match encoding.value().as_str() {
+                        "borsh" => Encoding::Borsh,
+                        "wincode" => Encoding::Wincode,
+                        _ => {
+                            return Err(Error::new_spanned(
+                                encoding,
+                                "unknown encoding, expected `borsh` or `wincode`",
+                            ))
+                        }
+                    };
+                } else {
+                    return Err(meta.error("unknown argument, expected `name` or `encoding`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(args)
+    }
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let krate = quote! { ::trezoa_program_event };
+    let ident = &input.ident;
+    let EventArgs { name, encoding } = EventArgs::from_attributes(&input.attrs)?;
+    let name = name.map_or_else(|| ident.to_string(), |name| name.value());
+    let hash = Sha256::digest(format!("event:{name}"));
+    let discriminator = &hash[..8];
+    let (encode, decode) = match encoding {
+        Encoding::Borsh => (quote! { borsh_encode }, quote! { borsh_decode }),
+        Encoding::Wincode => (quote! { wincode_encode }, quote! { wincode_decode }),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #krate::Event for #ident #ty_generics #where_clause {
+            const DISCRIMINATOR: [u8; #krate::DISCRIMINATOR_LEN] = [#(#discriminator),*];
+
+            fn encode(&self) -> #krate::__private::Vec<u8> {
+                #krate::__private::#encode(self)
+            }
+
+            fn decode(
+                data: &[u8],
+            ) -> ::core::result::Result<Self, #krate::EventError> {
+                #krate::__private::#decode(data)
+            }
+        }
+    })
+}
+
+/// Derive `Event` for a struct or an enum.
+///
+/// The `#[event(name = "...", encoding = "...")]` attribute sets the name
+/// the discriminator is derived from and the encoding, `borsh` by default.
+/// The traits of the encoding, such as `BorshSerialize` and
+/// `BorshDeserialize`, must be derived separately.
+///
+/// See the documentation of `trezoa-program-event` for details.
+#[proc_macro_derive(Event, attributes(event))]
+pub fn derive_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
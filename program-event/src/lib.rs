@@ -0,0 +1,157 @@
+//! Typed events emitted by Trezoa programs.
+//!
+//! An [`Event`] is logged with [`emit!`], which encodes it after its 8-byte
+//! discriminator and passes it to `sol_log_data`. The runtime records the
+//! data as a `Program data: <base64>` log line, from which clients decode
+//! the events with the `parse` feature.
+//!
+//! The [`Event`] derive macro implements the trait with the `borsh`
+//! encoding, which is enabled by default along with the macro, or the
+//! `wincode` encoding with `#[event(encoding = "wincode")]`. Either requires
+//! its feature and the matching derives on the event. The discriminator is
+//! the first 8 bytes of the SHA-256 hash of `event:<name>`, where the name
+//! defaults to the name of the struct and is set with
+//! `#[event(name = "...")]`.
+//!
+//! ```ignore
+//! use {
+//!     borsh::{BorshDeserialize, BorshSerialize},
+//!     trezoa_program_event::{emit, Event},
+//! };
+//!
+//! #[derive(BorshSerialize, BorshDeserialize, Event)]
+//! struct Deposit {
+//!     depositor: [u8; 32],
+//!     amount: u64,
+//! }
+//!
+//! emit!(Deposit {
+//!     depositor: [1; 32],
+//!     amount: 100,
+//! });
+//! ```
+//!
+//! And in a client, with the `parse` feature:
+//!
+//! ```ignore
+//! for deposit in trezoa_program_event::parse_events::<Deposit, _>(&logs, &program_id) {
+//!     let deposit = deposit?;
+//!     // ...
+//! }
+//! ```
+#![no_std]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+extern crate alloc;
+
+#[cfg(feature = "parse")]
+mod parse;
+
+#[cfg(feature = "parse")]
+pub use parse::{decode_program_data, parse_events, program_data, PROGRAM_DATA};
+#[cfg(feature = "macro")]
+pub use trezoa_program_event_macro::Event;
+use {alloc::vec::Vec, core::fmt};
+
+/// Length of the discriminator at the start of the event data.
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// An event emitted by a program.
+pub trait Event: Sized {
+    /// The discriminator identifying the event.
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN];
+
+    /// Encode the event after its discriminator.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the fields of the event fail to encode.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decode an event encoded by [`Event::encode`].
+    fn decode(data: &[u8]) -> Result<Self, EventError>;
+}
+
+/// Errors decoding an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventError {
+    /// The data does not start with the discriminator of the event.
+    InvalidDiscriminator,
+    /// The data after the discriminator is not an encoded event.
+    InvalidData,
+}
+
+impl core::error::Error for EventError {}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EventError::InvalidDiscriminator => f.write_str("Invalid event discriminator"),
+            EventError::InvalidData => f.write_str("Invalid event data"),
+        }
+    }
+}
+
+/// Log an event with `sol_log_data`.
+#[inline]
+pub fn emit<E: Event>(event: &E) {
+    trezoa_program_log::log_data(&[event.encode().as_slice()]);
+}
+
+/// Log an event with `sol_log_data`.
+///
+/// # Examples
+///
+/// ```ignore
+/// emit!(Deposit { depositor, amount });
+/// ```
+#[macro_export]
+macro_rules! emit {
+    ($event:expr) => {
+        $crate::emit(&$event)
+    };
+}
+
+/// The data after the discriminator of the event, if `data` starts with it.
+#[cfg(any(feature = "borsh", feature = "wincode"))]
+#[inline]
+fn payload<E: Event>(data: &[u8]) -> Result<&[u8], EventError> {
+    data.strip_prefix(&E::DISCRIMINATOR[..])
+        .ok_or(EventError::InvalidDiscriminator)
+}
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(any(feature = "borsh", feature = "wincode"))]
+    use super::{payload, Event, EventError};
+    pub use alloc::vec::Vec;
+
+    #[cfg(feature = "borsh")]
+    pub fn borsh_encode<E: Event + borsh::BorshSerialize>(event: &E) -> Vec<u8> {
+        let mut data = E::DISCRIMINATOR.to_vec();
+        event
+            .serialize(&mut data)
+            .expect("event fields must encode");
+        data
+    }
+
+    #[cfg(feature = "borsh")]
+    pub fn borsh_decode<E: Event + borsh::BorshDeserialize>(data: &[u8]) -> Result<E, EventError> {
+        E::try_from_slice(payload::<E>(data)?).map_err(|_| EventError::InvalidData)
+    }
+
+    #[cfg(feature = "wincode")]
+    pub fn wincode_encode<E: Event + wincode::SchemaWrite<Src = E>>(event: &E) -> Vec<u8> {
+        let mut data = E::DISCRIMINATOR.to_vec();
+        data.extend(wincode::serialize(event).expect("event fields must encode"));
+        data
+    }
+
+    #[cfg(feature = "wincode")]
+    pub fn wincode_decode<E>(data: &[u8]) -> Result<E, EventError>
+    where
+        E: Event + for<'de> wincode::SchemaRead<'de, Dst = E>,
+    {
+        wincode::deserialize(payload::<E>(data)?).map_err(|_| EventError::InvalidData)
+    }
+}
@@ -0,0 +1,114 @@
+//! Decoding of the events in the logs of a transaction.
+
+use {
+    crate::{Event, EventError},
+    alloc::{string::ToString, vec::Vec},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    trezoa_address::Address,
+};
+
+/// Prefix of the log lines written by `sol_log_data`.
+pub const PROGRAM_DATA: &str = "Program data: ";
+
+/// Decode the data of a `Program data:` log line, concatenating its
+/// base64 fields.
+///
+/// Returns `None` if the line is not a `Program data:` line or is not
+/// valid base64.
+pub fn decode_program_data(log: &str) -> Option<Vec<u8>> {
+    log.strip_prefix(PROGRAM_DATA)?
+        .split(' ')
+        .try_fold(Vec::new(), |mut data, field| {
+            BASE64_STANDARD.decode_vec(field, &mut data).ok()?;
+            Some(data)
+        })
+}
+
+/// The data of the `Program data:` log lines written by `program_id`, in
+/// order.
+///
+/// The program that wrote each line is tracked with the `invoke`, `success`
+/// and `failed` lines of the logs, so the data written by the programs that
+/// `program_id` invokes, or by programs that invoke it, is skipped.
+pub fn program_data<'a, S: AsRef<str>>(
+    logs: &'a [S],
+    program_id: &Address,
+) -> impl Iterator<Item = Vec<u8>> + 'a {
+    let program_id = program_id.to_string();
+    // Whether each program of the invocation stack is `program_id`.
+    let mut stack = Vec::new();
+    logs.iter().filter_map(move |log| {
+        let log = log.as_ref();
+        if log.starts_with(PROGRAM_DATA) {
+            return if stack.last() == Some(&true) {
+                decode_program_data(log)
+            } else {
+                None
+            };
+        }
+        let (program, status) = log.strip_prefix("Program ")?.split_once(' ')?;
+        if status.starts_with("invoke [") {
+            stack.push(program == program_id);
+        } else if status == "success" || status.starts_with("failed") {
+            stack.pop();
+        }
+        None
+    })
+}
+
+/// The events of type `E` written by `program_id`, in order.
+///
+/// Data with another discriminator is skipped, and data with the
+/// discriminator of `E` that fails to decode is returned as an error.
+pub fn parse_events<'a, E: Event + 'a, S: AsRef<str>>(
+    logs: &'a [S],
+    program_id: &Address,
+) -> impl Iterator<Item = Result<E, EventError>> + 'a {
+    program_data(logs, program_id)
+        .filter(|data| data.starts_with(&E::DISCRIMINATOR))
+        .map(|data| E::decode(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, alloc::vec};
+
+    #[test]
+    fn test_decode_program_data() {
+        assert_eq!(
+            decode_program_data("Program data: AQI= Aw=="),
+            Some(vec![1, 2, 3])
+        );
+        assert_eq!(decode_program_data("Program data: "), Some(vec![]));
+        assert_eq!(decode_program_data("Program data: A"), None);
+        assert_eq!(decode_program_data("Program log: AQI="), None);
+    }
+
+    #[test]
+    fn test_program_data() {
+        let program_id = Address::new_from_array([1; 32]);
+        let other = Address::new_from_array([2; 32]);
+        let logs = [
+            alloc::format!("Program {program_id} invoke [1]"),
+            "Program log: Instruction: Deposit".into(),
+            "Program data: AQ==".into(),
+            alloc::format!("Program {other} invoke [2]"),
+            "Program data: Ag==".into(),
+            alloc::format!("Program {other} consumed 100 of 200000 compute units"),
+            alloc::format!("Program {other} success"),
+            "Program data: Aw==".into(),
+            alloc::format!("Program {program_id} success"),
+            alloc::format!("Program {other} invoke [1]"),
+            "Program data: BA==".into(),
+            alloc::format!("Program {other} failed: custom program error: 0x1"),
+        ];
+        assert_eq!(
+            program_data(&logs, &program_id).collect::<Vec<_>>(),
+            vec![vec![1], vec![3]]
+        );
+        assert_eq!(
+            program_data(&logs, &other).collect::<Vec<_>>(),
+            vec![vec![2], vec![4]]
+        );
+    }
+}
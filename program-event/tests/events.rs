@@ -0,0 +1,107 @@
+use {
+    base64::{prelude::BASE64_STANDARD, Engine},
+    borsh::{BorshDeserialize, BorshSerialize},
+    trezoa_address::Address,
+    trezoa_program_event::{emit, parse_events, program_data, Event, EventError},
+    wincode::{SchemaRead, SchemaWrite},
+};
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize, Event)]
+struct Deposit {
+    depositor: [u8; 32],
+    amount: u64,
+}
+
+#[derive(Debug, PartialEq, SchemaWrite, SchemaRead, Event)]
+#[event(name = "Withdrawal", encoding = "wincode")]
+struct WithdrawalV2 {
+    amount: u64,
+    fee: u64,
+}
+
+#[derive(Debug, PartialEq, BorshSerialize, BorshDeserialize, Event)]
+#[event(name = "Withdrawal")]
+struct Withdrawal {
+    amount: u64,
+}
+
+fn data_log(event: &impl Event) -> String {
+    format!("Program data: {}", BASE64_STANDARD.encode(event.encode()))
+}
+
+#[test]
+fn test_discriminator() {
+    assert_ne!(Deposit::DISCRIMINATOR, Withdrawal::DISCRIMINATOR);
+    // the discriminator only depends on the name
+    assert_eq!(Withdrawal::DISCRIMINATOR, WithdrawalV2::DISCRIMINATOR);
+}
+
+#[test]
+fn test_encode_decode() {
+    let deposit = Deposit {
+        depositor: [1; 32],
+        amount: 100,
+    };
+    let data = deposit.encode();
+    assert_eq!(data[..8], Deposit::DISCRIMINATOR);
+    assert_eq!(data[8..40], [1; 32]);
+    assert_eq!(data[40..], 100u64.to_le_bytes());
+    assert_eq!(Deposit::decode(&data), Ok(deposit));
+    assert_eq!(
+        Withdrawal::decode(&data),
+        Err(EventError::InvalidDiscriminator)
+    );
+    assert_eq!(
+        Deposit::decode(&data[..data.len().saturating_sub(1)]),
+        Err(EventError::InvalidData)
+    );
+
+    let withdrawal = WithdrawalV2 { amount: 5, fee: 1 };
+    let data = withdrawal.encode();
+    assert_eq!(data[..8], WithdrawalV2::DISCRIMINATOR);
+    assert_eq!(WithdrawalV2::decode(&data), Ok(withdrawal));
+    assert_eq!(Withdrawal::decode(&data), Err(EventError::InvalidData));
+
+    // logging is a no-op off-chain
+    emit!(Deposit {
+        depositor: [2; 32],
+        amount: 1,
+    });
+}
+
+#[test]
+fn test_parse_events() {
+    let program_id = Address::new_from_array([1; 32]);
+    let other = Address::new_from_array([2; 32]);
+    let deposit = Deposit {
+        depositor: [3; 32],
+        amount: 7,
+    };
+    let logs = [
+        format!("Program {program_id} invoke [1]"),
+        data_log(&deposit),
+        data_log(&Withdrawal { amount: 2 }),
+        format!("Program {other} invoke [2]"),
+        data_log(&Withdrawal { amount: 3 }),
+        format!("Program {other} success"),
+        format!(
+            "Program data: {}",
+            BASE64_STANDARD.encode(Withdrawal::DISCRIMINATOR)
+        ),
+        format!("Program {program_id} success"),
+    ];
+
+    assert_eq!(program_data(&logs, &program_id).count(), 3);
+    assert_eq!(
+        parse_events::<Deposit, _>(&logs, &program_id).collect::<Vec<_>>(),
+        vec![Ok(deposit)]
+    );
+    assert_eq!(
+        parse_events::<Withdrawal, _>(&logs, &program_id).collect::<Vec<_>>(),
+        vec![Ok(Withdrawal { amount: 2 }), Err(EventError::InvalidData)]
+    );
+    assert_eq!(
+        parse_events::<Withdrawal, _>(&logs, &other).collect::<Vec<_>>(),
+        vec![Ok(Withdrawal { amount: 3 })]
+    );
+}
@@ -0,0 +1,164 @@
+//! Reference signing daemon serving a keypair file.
+//!
+//! ```text
+//! trezoa-remote-signer-daemon [--allow-remote --allow-peer <IP>...] <KEYPAIR_FILE> <ENDPOINT>
+//! ```
+//!
+//! `ENDPOINT` is `tcp://<address>:<port>` or `unix://<path>`. A Unix socket
+//! is created with permissions that only allow the current user to connect.
+//! Signing requests are logged to stderr.
+//!
+//! The protocol does not authenticate clients, so a TCP endpoint must be a
+//! loopback address unless `--allow-remote` is passed. Listening on any other
+//! address also requires at least one `--allow-peer`, and only connections
+//! from the listed peer addresses are then served.
+
+#[cfg(unix)]
+use std::{
+    fs::{self, DirBuilder, Permissions},
+    io,
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::UnixListener,
+    },
+    path::Path,
+};
+use {
+    log::{LevelFilter, Log, Metadata, Record},
+    trezoa_keypair::read_keypair_file,
+    trezoa_remote_signer::{server::SigningServer, Endpoint},
+    trezoa_signer::Signer,
+    std::{
+        env, error,
+        net::{IpAddr, TcpListener},
+        process,
+    },
+};
+
+struct StderrLogger;
+
+impl Log for StderrLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        eprintln!("[{}] {}", record.level(), record.args());
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// Bind a Unix socket at `path` that only the current user can connect to.
+///
+/// The socket is bound in a directory only the current user can enter and
+/// restricted before it is linked at `path`, so other users can never connect
+/// to it, whatever the umask.
+#[cfg(unix)]
+fn bind_private_unix(path: &Path) -> io::Result<UnixListener> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let dir = parent.join(format!(".trezoa-remote-signer-{}", process::id()));
+    DirBuilder::new().mode(0o700).create(&dir)?;
+    let socket = dir.join("socket");
+    let result = UnixListener::bind(&socket).and_then(|listener| {
+        fs::set_permissions(&socket, Permissions::from_mode(0o600))?;
+        // Unlike a rename, linking fails if `path` already exists
+        fs::hard_link(&socket, path)?;
+        Ok(listener)
+    });
+    fs::remove_dir_all(&dir)?;
+    result
+}
+
+const USAGE: &str = "usage: trezoa-remote-signer-daemon [--allow-remote --allow-peer <IP>...] \
+                     <KEYPAIR_FILE> <ENDPOINT>";
+
+struct Args {
+    keypair_file: String,
+    endpoint: Endpoint,
+    allow_remote: bool,
+    allowed_peers: Vec<IpAddr>,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut allow_remote = false;
+    let mut allowed_peers = vec![];
+    let mut positional = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--allow-remote" => allow_remote = true,
+            "--allow-peer" => {
+                let peer = args.next().ok_or("`--allow-peer` requires an address")?;
+                let peer = peer
+                    .parse()
+                    .map_err(|err| format!("invalid peer address `{peer}`: {err}"))?;
+                allowed_peers.push(peer);
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let [keypair_file, endpoint] = <[String; 2]>::try_from(positional)
+        .map_err(|_| "expected a keypair file and an endpoint".to_string())?;
+    Ok(Args {
+        keypair_file,
+        endpoint: endpoint.parse()?,
+        allow_remote,
+        allowed_peers,
+    })
+}
+
+fn run(args: Args) -> Result<(), Box<dyn error::Error>> {
+    let Args {
+        keypair_file,
+        endpoint,
+        allow_remote,
+        allowed_peers,
+    } = args;
+    if let Endpoint::Tcp(addr) = &endpoint {
+        if !addr.ip().is_loopback() {
+            if !allow_remote {
+                return Err(format!(
+                    "refusing to listen on non-loopback address {addr} without `--allow-remote`"
+                )
+                .into());
+            }
+            if allowed_peers.is_empty() {
+                return Err("`--allow-remote` requires at least one `--allow-peer`".into());
+            }
+        }
+    }
+    let keypair = read_keypair_file(&keypair_file)
+        .map_err(|err| format!("failed to read keypair file `{keypair_file}`: {err}"))?;
+    eprintln!("serving {} on {endpoint}", keypair.pubkey());
+    let mut server = SigningServer::new(keypair);
+    if !allowed_peers.is_empty() {
+        server = server.with_allowed_peers(allowed_peers);
+    }
+    match endpoint {
+        Endpoint::Tcp(addr) => server.serve_tcp(TcpListener::bind(addr)?)?,
+        #[cfg(unix)]
+        Endpoint::Unix(path) => server.serve_unix(bind_private_unix(&path)?)?,
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n{USAGE}");
+            process::exit(2);
+        }
+    };
+    log::set_logger(&LOGGER).expect("logger is only set once");
+    log::set_max_level(LevelFilter::Info);
+    if let Err(err) = run(args) {
+        eprintln!("error: {err}");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,353 @@
+//! A [`Signer`] that forwards signing requests to a signing daemon.
+//!
+//! [`RemoteSigner`] keeps private keys off the machine that builds
+//! transactions. Each call to [`Signer::try_sign_message`] opens a connection
+//! to a daemon over TCP or a Unix socket, sends the message together with a
+//! [`MessageInspection`] describing it, and returns the signature once the
+//! daemon has verified the inspection and applied its signing policy. The
+//! signature is verified before it is returned.
+//!
+//! # Protocol
+//!
+//! Every request and response is a frame: a little-endian `u32` length
+//! followed by that many bytes holding the bincode encoding of a
+//! [`Request`] or [`Response`]. Frames are at most [`MAX_FRAME_LEN`] bytes
+//! long. A connection carries any number of request and response pairs, in
+//! order, until the client closes it.
+//!
+//! | Request | Response |
+//! |---|---|
+//! | [`Request::GetPubkey`] | [`Response::Pubkey`] |
+//! | [`Request::SignMessage`] | [`Response::Signature`], or [`Response::Rejected`] if the policy or inspection check fails |
+//!
+//! Any request may instead be answered with [`Response::Error`].
+//!
+//! The protocol does not authenticate clients. The daemon should listen on a
+//! Unix socket whose permissions restrict who can connect, on a loopback
+//! address, or behind an authenticated tunnel. A TCP daemon can additionally
+//! restrict the peers it serves with
+//! [`SigningServer::with_allowed_peers`](server::SigningServer::with_allowed_peers).
+//!
+//! [`server::SigningServer`] is a reference implementation of the daemon,
+//! and the `trezoa-remote-signer-daemon` binary serves a keypair file with
+//! it.
+//!
+//! [`MAX_FRAME_LEN`]: protocol::MAX_FRAME_LEN
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+pub mod protocol;
+pub mod server;
+
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::PathBuf};
+use {
+    crate::protocol::{read_frame, write_frame, MessageInspection, Request, Response},
+    core::{fmt, str::FromStr, time::Duration},
+    trezoa_pubkey::Pubkey,
+    trezoa_signature::Signature,
+    trezoa_signer::{Signer, SignerError},
+    std::{
+        io::{self, Read, Write},
+        net::{SocketAddr, TcpStream},
+    },
+};
+
+/// Default time to wait for the daemon to connect and respond.
+///
+/// Signing may wait for a human to approve the request, so this is generous.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Where a signing daemon listens.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Endpoint {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Tcp(addr) => write!(f, "tcp://{addr}"),
+            #[cfg(unix)]
+            Self::Unix(path) => write!(f, "unix://{}", path.display()),
+        }
+    }
+}
+
+/// Parses `tcp://<address>:<port>` or `unix://<path>`.
+impl FromStr for Endpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(addr) = s.strip_prefix("tcp://") {
+            return addr
+                .parse()
+                .map(Self::Tcp)
+                .map_err(|err| format!("invalid tcp address `{addr}`: {err}"));
+        }
+        #[cfg(unix)]
+        if let Some(path) = s.strip_prefix("unix://") {
+            return Ok(Self::Unix(PathBuf::from(path)));
+        }
+        Err(format!("unsupported endpoint `{s}`"))
+    }
+}
+
+trait Connection: Read + Write {}
+
+impl<T: Read + Write> Connection for T {}
+
+impl Endpoint {
+    fn connect(&self, timeout: Duration) -> io::Result<Box<dyn Connection>> {
+        match self {
+            Self::Tcp(addr) => {
+                let stream = TcpStream::connect_timeout(addr, timeout)?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                Ok(Box::new(stream))
+            }
+            #[cfg(unix)]
+            Self::Unix(path) => {
+                let stream = UnixStream::connect(path)?;
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                Ok(Box::new(stream))
+            }
+        }
+    }
+}
+
+/// A [`Signer`] backed by a signing daemon.
+#[derive(Clone, Debug)]
+pub struct RemoteSigner {
+    endpoint: Endpoint,
+    pubkey: Pubkey,
+    context: Option<String>,
+    timeout: Duration,
+}
+
+impl RemoteSigner {
+    /// Connects to the daemon at `endpoint` and fetches its public key.
+    pub fn connect(endpoint: Endpoint) -> Result<Self, SignerError> {
+        let mut signer = Self {
+            endpoint,
+            pubkey: Pubkey::default(),
+            context: None,
+            timeout: DEFAULT_TIMEOUT,
+        };
+        signer.pubkey = match signer.request(&Request::GetPubkey)? {
+            Response::Pubkey(pubkey) => pubkey,
+            response => return Err(unexpected_response(response)),
+        };
+        Ok(signer)
+    }
+
+    /// Sets the label sent with every signing request, for the daemon's
+    /// policy and audit log.
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// Sets how long to wait for the daemon to connect and to respond.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn endpoint(&self) -> &Endpoint {
+        &self.endpoint
+    }
+
+    fn request(&self, request: &Request) -> Result<Response, SignerError> {
+        let connection_error =
+            |err: io::Error| SignerError::Connection(format!("{}: {err}", self.endpoint));
+        let mut connection = self
+            .endpoint
+            .connect(self.timeout)
+            .map_err(connection_error)?;
+        write_frame(&mut connection, request).map_err(connection_error)?;
+        read_frame(&mut connection)
+            .map_err(connection_error)?
+            .ok_or_else(|| SignerError::Protocol("connection closed without a response".into()))
+    }
+}
+
+fn unexpected_response(response: Response) -> SignerError {
+    match response {
+        Response::Rejected(reason) => SignerError::UserCancel(reason),
+        Response::Error(err) => SignerError::Custom(err),
+        response => SignerError::Protocol(format!("unexpected response: {response:?}")),
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let request = Request::SignMessage {
+            message: message.to_vec(),
+            inspection: MessageInspection::new(message, self.context.clone()),
+        };
+        let signature = match self.request(&request)? {
+            Response::Signature(signature) => signature,
+            response => return Err(unexpected_response(response)),
+        };
+        if !signature.verify(self.pubkey.as_ref(), message) {
+            return Err(SignerError::Protocol(
+                "daemon returned an invalid signature".to_string(),
+            ));
+        }
+        Ok(signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+impl<T> PartialEq<T> for RemoteSigner
+where
+    T: Signer,
+{
+    fn eq(&self, other: &T) -> bool {
+        self.pubkey == other.pubkey()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::server::SigningServer,
+        trezoa_keypair::Keypair,
+        trezoa_message::Message,
+        trezoa_system_interface::instruction::transfer,
+        std::{net::TcpListener, thread},
+    };
+
+    fn spawn_tcp_server(server: SigningServer<Keypair>) -> Endpoint {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = Endpoint::Tcp(listener.local_addr().unwrap());
+        thread::spawn(move || server.serve_tcp(listener));
+        endpoint
+    }
+
+    #[test]
+    fn test_endpoint_from_str() {
+        assert_eq!(
+            "tcp://127.0.0.1:9000".parse(),
+            Ok(Endpoint::Tcp("127.0.0.1:9000".parse().unwrap()))
+        );
+        assert!("tcp://localhost".parse::<Endpoint>().is_err());
+        assert!("http://127.0.0.1:9000".parse::<Endpoint>().is_err());
+        #[cfg(unix)]
+        assert_eq!(
+            "unix:///run/signer.sock".parse(),
+            Ok(Endpoint::Unix(PathBuf::from("/run/signer.sock")))
+        );
+    }
+
+    #[test]
+    fn test_remote_signer_tcp() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let endpoint = spawn_tcp_server(SigningServer::new(keypair));
+
+        let signer = RemoteSigner::connect(endpoint).unwrap();
+        assert_eq!(signer.pubkey(), pubkey);
+        let message = Message::new(
+            &[transfer(&pubkey, &Pubkey::new_unique(), 1)],
+            Some(&pubkey),
+        );
+        let message = message.serialize();
+        let signature = signer.try_sign_message(&message).unwrap();
+        assert!(signature.verify(pubkey.as_ref(), &message));
+    }
+
+    #[test]
+    fn test_remote_signer_rejected() {
+        let server = SigningServer::new(Keypair::new()).with_policy(|inspection| {
+            if inspection.context.as_deref() == Some("release") {
+                Ok(())
+            } else {
+                Err("only release jobs may sign".to_string())
+            }
+        });
+        let endpoint = spawn_tcp_server(server);
+
+        let signer = RemoteSigner::connect(endpoint).unwrap();
+        assert_eq!(
+            signer.try_sign_message(b"message"),
+            Err(SignerError::UserCancel(
+                "only release jobs may sign".to_string()
+            ))
+        );
+        let signer = signer.with_context("release");
+        assert!(signer.try_sign_message(b"message").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_signer_unix() {
+        use std::os::unix::net::UnixListener;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        thread::spawn(move || SigningServer::new(keypair).serve_unix(listener));
+
+        let signer = RemoteSigner::connect(Endpoint::Unix(path)).unwrap();
+        assert_eq!(signer.pubkey(), pubkey);
+        assert!(signer.try_sign_message(b"message").is_ok());
+    }
+
+    #[test]
+    fn test_idle_connection() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let server = SigningServer::new(keypair).with_timeout(Duration::from_millis(100));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || server.serve_tcp(listener));
+
+        // a client that never sends a request doesn't block the others
+        let mut idle = TcpStream::connect(addr).unwrap();
+        let signer = RemoteSigner::connect(Endpoint::Tcp(addr)).unwrap();
+        assert_eq!(signer.pubkey(), pubkey);
+
+        // and is disconnected once the timeout expires
+        idle.set_read_timeout(Some(Duration::from_secs(10)))
+            .unwrap();
+        assert_eq!(idle.read(&mut [0; 1]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_allowed_peers() {
+        let server =
+            SigningServer::new(Keypair::new()).with_allowed_peers(["127.0.0.2".parse().unwrap()]);
+        assert!(RemoteSigner::connect(spawn_tcp_server(server)).is_err());
+
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let server = SigningServer::new(keypair).with_allowed_peers(["127.0.0.1".parse().unwrap()]);
+        let signer = RemoteSigner::connect(spawn_tcp_server(server)).unwrap();
+        assert_eq!(signer.pubkey(), pubkey);
+    }
+
+    #[test]
+    fn test_connection_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = Endpoint::Tcp(listener.local_addr().unwrap());
+        drop(listener);
+        assert!(matches!(
+            RemoteSigner::connect(endpoint),
+            Err(SignerError::Connection(_))
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+//! Wire format shared by [`RemoteSigner`] and [`SigningServer`].
+//!
+//! [`RemoteSigner`]: crate::RemoteSigner
+//! [`SigningServer`]: crate::server::SigningServer
+
+use {
+    serde_derive::{Deserialize, Serialize},
+    trezoa_hash::Hash,
+    trezoa_instruction_decoder::decode_compiled_instruction,
+    trezoa_message::VersionedMessage,
+    trezoa_pubkey::Pubkey,
+    trezoa_signature::Signature,
+    std::io::{self, Read, Write},
+};
+
+/// Maximum length in bytes of a frame body.
+///
+/// Large enough for any transaction message or off-chain message and its
+/// inspection.
+pub const MAX_FRAME_LEN: usize = 128 * 1024;
+
+/// A request sent by a client to the signing daemon.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Request {
+    /// Asks for the public key of the daemon's signer.
+    GetPubkey,
+    /// Asks the daemon to sign `message`.
+    SignMessage {
+        message: Vec<u8>,
+        inspection: MessageInspection,
+    },
+}
+
+/// The daemon's reply to a [`Request`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Response {
+    Pubkey(Pubkey),
+    Signature(Signature),
+    /// The daemon refused to sign, for the given reason.
+    Rejected(String),
+    /// The daemon failed to handle the request.
+    Error(String),
+}
+
+/// A description of a message to be signed, sent alongside it so that the
+/// daemon can apply a policy and keep an audit log without decoding the
+/// message itself.
+///
+/// The daemon recomputes the inspection from the message and rejects the
+/// request if the two differ, so only `context` is under the client's
+/// control.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MessageInspection {
+    /// Free-form label supplied by the client, e.g. the name of a build job.
+    pub context: Option<String>,
+    pub kind: MessageKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MessageKind {
+    /// A legacy or versioned transaction message.
+    Transaction(TransactionSummary),
+    /// Bytes that do not decode as a transaction message, such as an
+    /// off-chain message.
+    Unknown { len: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionSummary {
+    /// `None` for legacy messages.
+    pub version: Option<u8>,
+    pub fee_payer: Option<Pubkey>,
+    pub num_required_signatures: u8,
+    pub recent_blockhash: Hash,
+    pub instructions: Vec<InstructionSummary>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstructionSummary {
+    /// `None` if the program id index is out of bounds.
+    pub program_id: Option<Pubkey>,
+    /// A human-readable rendering of the instruction, if it belongs to a
+    /// builtin program and only references static account keys.
+    pub description: Option<String>,
+}
+
+impl MessageInspection {
+    /// Inspects the serialized `message`.
+    pub fn new(message: &[u8], context: Option<String>) -> Self {
+        let kind = bincode::deserialize::<VersionedMessage>(message)
+            .ok()
+            .filter(|decoded| decoded.serialize() == message)
+            .map_or(MessageKind::Unknown { len: message.len() }, |decoded| {
+                MessageKind::Transaction(TransactionSummary::new(&decoded))
+            });
+        Self { context, kind }
+    }
+}
+
+impl TransactionSummary {
+    fn new(message: &VersionedMessage) -> Self {
+        let account_keys = message.static_account_keys();
+        let instructions = message
+            .instructions()
+            .iter()
+            .map(|instruction| InstructionSummary {
+                program_id: account_keys
+                    .get(usize::from(instruction.program_id_index))
+                    .copied(),
+                description: decode_compiled_instruction(instruction, account_keys)
+                    .ok()
+                    .map(|decoded| decoded.to_string()),
+            })
+            .collect();
+        Self {
+            version: match message {
+                VersionedMessage::Legacy(_) => None,
+                VersionedMessage::V0(_) => Some(0),
+            },
+            fee_payer: account_keys.first().copied(),
+            num_required_signatures: message.header().num_required_signatures,
+            recent_blockhash: *message.recent_blockhash(),
+            instructions,
+        }
+    }
+}
+
+/// Writes `value` as a frame: its bincode encoding prefixed with the encoded
+/// length as a little-endian `u32`.
+pub fn write_frame<W: Write, T: serde::Serialize>(writer: &mut W, value: &T) -> io::Result<()> {
+    let body = bincode::serialize(value).map_err(io::Error::other)?;
+    if body.len() > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "frame of {} bytes exceeds {MAX_FRAME_LEN} bytes",
+                body.len()
+            ),
+        ));
+    }
+    // MAX_FRAME_LEN fits in a u32
+    writer.write_all(&(body.len() as u32).to_le_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+/// Reads a frame written by [`write_frame`].
+///
+/// Returns `Ok(None)` if the stream ends before the frame starts.
+pub fn read_frame<R: Read, T: serde::de::DeserializeOwned>(
+    reader: &mut R,
+) -> io::Result<Option<T>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let len = u32::from_le_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes exceeds {MAX_FRAME_LEN} bytes"),
+        ));
+    }
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body)?;
+    bincode::deserialize(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, trezoa_message::Message, trezoa_system_interface::instruction::transfer,
+        std::io::Cursor,
+    };
+
+    #[test]
+    fn test_frame_roundtrip() {
+        let request = Request::SignMessage {
+            message: vec![1, 2, 3],
+            inspection: MessageInspection::new(&[1, 2, 3], Some("job".to_string())),
+        };
+        let mut buffer = vec![];
+        write_frame(&mut buffer, &request).unwrap();
+        write_frame(&mut buffer, &Request::GetPubkey).unwrap();
+
+        let mut reader = Cursor::new(buffer);
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(request));
+        assert_eq!(read_frame(&mut reader).unwrap(), Some(Request::GetPubkey));
+        assert_eq!(read_frame::<_, Request>(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_frame_too_large() {
+        let mut buffer = u32::try_from(MAX_FRAME_LEN)
+            .unwrap()
+            .saturating_add(1)
+            .to_le_bytes()
+            .to_vec();
+        buffer.extend_from_slice(&[0; 16]);
+        let err = read_frame::<_, Request>(&mut Cursor::new(buffer)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let request = Request::SignMessage {
+            message: vec![0; MAX_FRAME_LEN],
+            inspection: MessageInspection::new(&[], None),
+        };
+        let err = write_frame(&mut vec![], &request).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_inspect_transaction() {
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let message = Message::new(&[transfer(&from, &to, 42)], Some(&from));
+        let inspection = MessageInspection::new(&message.serialize(), None);
+
+        let MessageKind::Transaction(summary) = inspection.kind else {
+            panic!("expected a transaction message");
+        };
+        assert_eq!(summary.version, None);
+        assert_eq!(summary.fee_payer, Some(from));
+        assert_eq!(summary.num_required_signatures, 1);
+        assert_eq!(summary.instructions.len(), 1);
+        let description = summary.instructions[0].description.as_ref().unwrap();
+        assert!(description.contains("Transfer { lamports: 42 }"));
+        assert!(description.contains(&to.to_string()));
+    }
+
+    #[test]
+    fn test_inspect_unknown() {
+        let inspection = MessageInspection::new(b"\xfftrezoa offchain", None);
+        assert_eq!(inspection.kind, MessageKind::Unknown { len: 16 });
+    }
+}
@@ -0,0 +1,324 @@
+//! A reference implementation of the signing daemon.
+//!
+//! [`SigningServer`] answers the requests of the [protocol](crate#protocol)
+//! with a local [`Signer`], serving each connection on its own thread.
+//! Signing requests are checked against their [`MessageInspection`] and an
+//! optional policy, and logged at the `info` level. TCP connections can be
+//! restricted to a set of peer addresses with
+//! [`SigningServer::with_allowed_peers`].
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use {
+    crate::{
+        protocol::{read_frame, write_frame, MessageInspection, MessageKind, Request, Response},
+        DEFAULT_TIMEOUT,
+    },
+    log::{info, warn},
+    trezoa_signer::Signer,
+    std::{
+        io::{self, Read, Write},
+        net::{IpAddr, TcpListener, TcpStream},
+        thread,
+        time::Duration,
+    },
+};
+
+type Policy = dyn Fn(&MessageInspection) -> Result<(), String> + Send + Sync;
+
+/// Serves signing requests with a local [`Signer`].
+pub struct SigningServer<S> {
+    signer: S,
+    policy: Option<Box<Policy>>,
+    allowed_peers: Option<Vec<IpAddr>>,
+    timeout: Duration,
+}
+
+impl<S: Signer> SigningServer<S> {
+    /// Creates a server that signs every well-formed request with `signer`.
+    pub fn new(signer: S) -> Self {
+        Self {
+            signer,
+            policy: None,
+            allowed_peers: None,
+            timeout: DEFAULT_TIMEOUT,
+        }
+    }
+
+    /// Sets a policy deciding which messages may be signed. Returning an
+    /// error rejects the request with the error as the reason.
+    pub fn with_policy<F>(mut self, policy: F) -> Self
+    where
+        F: Fn(&MessageInspection) -> Result<(), String> + Send + Sync + 'static,
+    {
+        self.policy = Some(Box::new(policy));
+        self
+    }
+
+    /// Only accepts TCP connections from `peers`. Connections from any other
+    /// address are closed before a request is read.
+    ///
+    /// By default, connections from every address are accepted.
+    pub fn with_allowed_peers<I: IntoIterator<Item = IpAddr>>(mut self, peers: I) -> Self {
+        self.allowed_peers = Some(peers.into_iter().collect());
+        self
+    }
+
+    /// Sets how long a connection accepted by [`SigningServer::serve_tcp`]
+    /// or [`SigningServer::serve_unix`] may wait for the client to send or
+    /// receive data before it is closed.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Answers a single request.
+    pub fn handle(&self, request: Request) -> Response {
+        match request {
+            Request::GetPubkey => match self.signer.try_pubkey() {
+                Ok(pubkey) => Response::Pubkey(pubkey),
+                Err(err) => Response::Error(err.to_string()),
+            },
+            Request::SignMessage {
+                message,
+                inspection,
+            } => self.sign(&message, &inspection),
+        }
+    }
+
+    fn sign(&self, message: &[u8], inspection: &MessageInspection) -> Response {
+        let context = inspection.context.as_deref().unwrap_or("-");
+        if *inspection != MessageInspection::new(message, inspection.context.clone()) {
+            warn!("rejected request from {context}: inspection does not match message");
+            return Response::Rejected("inspection does not match message".to_string());
+        }
+        if let Some(Err(reason)) = self.policy.as_ref().map(|policy| policy(inspection)) {
+            warn!("rejected request from {context}: {reason}");
+            return Response::Rejected(reason);
+        }
+        match &inspection.kind {
+            MessageKind::Transaction(summary) => {
+                info!(
+                    "signing transaction for {context}: fee payer {:?}, {} instructions",
+                    summary.fee_payer,
+                    summary.instructions.len()
+                );
+                for instruction in &summary.instructions {
+                    match &instruction.description {
+                        Some(description) => info!("  {description}"),
+                        None => info!("  instruction for program {:?}", instruction.program_id),
+                    }
+                }
+            }
+            MessageKind::Unknown { len } => {
+                info!("signing {len} byte message for {context}");
+            }
+        }
+        match self.signer.try_sign_message(message) {
+            Ok(signature) => Response::Signature(signature),
+            Err(err) => Response::Error(err.to_string()),
+        }
+    }
+
+    /// Answers requests read from `stream` until the client closes it.
+    pub fn serve_connection<T: Read + Write>(&self, mut stream: T) -> io::Result<()> {
+        while let Some(request) = read_frame(&mut stream)? {
+            write_frame(&mut stream, &self.handle(request))?;
+        }
+        Ok(())
+    }
+
+    /// Accepts connections from `listener` forever, serving each on its own
+    /// thread.
+    ///
+    /// A failed connection, or one from a peer that is not allowed, is logged
+    /// and does not stop the server.
+    pub fn serve_tcp(&self, listener: TcpListener) -> io::Result<()>
+    where
+        S: Sync,
+    {
+        self.serve(listener.incoming().map(|stream| {
+            let stream = stream?;
+            let peer = stream.peer_addr()?;
+            match &self.allowed_peers {
+                Some(allowed_peers) if !allowed_peers.contains(&peer.ip()) => Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    format!("peer {peer} is not allowed"),
+                )),
+                _ => Ok(stream),
+            }
+        }))
+    }
+
+    /// Accepts connections from `listener` forever, serving each on its own
+    /// thread.
+    ///
+    /// A failed connection is logged and does not stop the server.
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: UnixListener) -> io::Result<()>
+    where
+        S: Sync,
+    {
+        self.serve(listener.incoming())
+    }
+
+    fn serve<T: Connection>(&self, incoming: impl Iterator<Item = io::Result<T>>) -> io::Result<()>
+    where
+        S: Sync,
+    {
+        thread::scope(|scope| {
+            for stream in incoming {
+                match stream.and_then(|stream| stream.set_timeout(self.timeout).map(|()| stream)) {
+                    Ok(stream) => {
+                        scope.spawn(move || log_failure(self.serve_connection(stream)));
+                    }
+                    Err(err) => log_failure(Err(err)),
+                }
+            }
+        });
+        Ok(())
+    }
+}
+
+/// A stream accepted by [`SigningServer::serve`].
+trait Connection: Read + Write + Send {
+    /// Sets both the read and the write timeout of the stream.
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()>;
+}
+
+impl Connection for TcpStream {
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+fn log_failure(result: io::Result<()>) {
+    if let Err(err) = result {
+        warn!("connection failed: {err}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        trezoa_keypair::Keypair,
+        trezoa_message::Message,
+        trezoa_pubkey::Pubkey,
+        trezoa_system_interface::{instruction::transfer, program as system_program},
+        std::io::Cursor,
+    };
+
+    struct Duplex {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Read for Duplex {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_rejects_mismatched_inspection() {
+        let server = SigningServer::new(Keypair::new());
+        let response = server.handle(Request::SignMessage {
+            message: b"message".to_vec(),
+            inspection: MessageInspection::new(b"other message", None),
+        });
+        assert_eq!(
+            response,
+            Response::Rejected("inspection does not match message".to_string())
+        );
+    }
+
+    #[test]
+    fn test_policy() {
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let server = SigningServer::new(keypair).with_policy(|inspection| {
+            let MessageKind::Transaction(summary) = &inspection.kind else {
+                return Err("only transactions may be signed".to_string());
+            };
+            if summary
+                .instructions
+                .iter()
+                .all(|instruction| instruction.program_id == Some(system_program::id()))
+            {
+                Ok(())
+            } else {
+                Err("only system program instructions may be signed".to_string())
+            }
+        });
+
+        let message =
+            Message::new(&[transfer(&payer, &Pubkey::new_unique(), 1)], Some(&payer)).serialize();
+        let response = server.handle(Request::SignMessage {
+            inspection: MessageInspection::new(&message, None),
+            message,
+        });
+        assert!(matches!(response, Response::Signature(_)));
+
+        let response = server.handle(Request::SignMessage {
+            message: b"message".to_vec(),
+            inspection: MessageInspection::new(b"message", None),
+        });
+        assert_eq!(
+            response,
+            Response::Rejected("only transactions may be signed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_serve_connection() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let mut requests = vec![];
+        write_frame(&mut requests, &Request::GetPubkey).unwrap();
+        write_frame(
+            &mut requests,
+            &Request::SignMessage {
+                message: b"message".to_vec(),
+                inspection: MessageInspection::new(b"message", None),
+            },
+        )
+        .unwrap();
+
+        let mut stream = Duplex {
+            input: Cursor::new(requests),
+            output: vec![],
+        };
+        SigningServer::new(keypair)
+            .serve_connection(&mut stream)
+            .unwrap();
+        let mut responses = Cursor::new(stream.output);
+        assert_eq!(
+            read_frame(&mut responses).unwrap(),
+            Some(Response::Pubkey(pubkey))
+        );
+        let Some(Response::Signature(signature)) = read_frame(&mut responses).unwrap() else {
+            panic!("expected a signature");
+        };
+        assert!(signature.verify(pubkey.as_ref(), b"message"));
+    }
+}
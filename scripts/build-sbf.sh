@@ -11,10 +11,12 @@ build_sbf_excludes=(
   --exclude trezoa-example-mocks
   --exclude trezoa-file-download
   --exclude trezoa-genesis-config
+  --exclude trezoa-instruction-decoder
   --exclude trezoa-keypair
   --exclude trezoa-offchain-message
   --exclude trezoa-presigner
   --exclude trezoa-quic-definitions
+  --exclude trezoa-remote-signer
   --exclude trezoa-sdk-wasm-js
   --exclude trezoa-sdk-wasm-js-tests
   --exclude trezoa-secp256k1-program
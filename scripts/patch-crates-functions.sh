@@ -7,6 +7,8 @@ source "$base/read-cargo-variable.sh"
 
 all_crate_dirs=(
   account
+  account-constraints
+  account-constraints-macro
   account-info
   address-lookup-table-interface
   atomic-u64
@@ -43,6 +45,7 @@ all_crate_dirs=(
   hash
   inflation
   instruction
+  instruction-decoder
   instructions-sysvar
   keccak-hasher
   keypair
@@ -50,6 +53,7 @@ all_crate_dirs=(
   loader-v2-interface
   loader-v3-interface
   loader-v4-interface
+  merkle-tree
   message
   msg
   native-token
@@ -66,11 +70,14 @@ all_crate_dirs=(
   program
   program-entrypoint
   program-error
+  program-event
+  program-event-macro
   program-memory
   program-option
   program-pack
   pubkey
   quic-definitions
+  remote-signer
   rent
   reward-info
   sanitize
@@ -102,6 +109,8 @@ all_crate_dirs=(
   transaction-error
   validator-exit
   vote-interface
+  zero-copy-account
+  zero-copy-account-macro
 )
 
 update_trezoa_sdk_dependencies() {
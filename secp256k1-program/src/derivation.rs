@@ -0,0 +1,154 @@
+//! [SLIP-0010] hierarchical derivation of secp256k1 keys.
+//!
+//! [SLIP-0010]: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+//!
+//! Keys are derived from the same seed and [`DerivationPath`] as the ed25519
+//! keys of `trezoa-keypair`, so that one mnemonic backs up both. For
+//! secp256k1 the scheme is identical to BIP-32.
+//!
+//! ```
+//! use {
+//!     trezoa_derivation_path::DerivationPath,
+//!     trezoa_secp256k1_program::{
+//!         derivation::derive_signing_key, eth_address_from_pubkey, sign_message,
+//!     },
+//! };
+//!
+//! let seed = [7u8; 64];
+//! let signing_key = derive_signing_key(&seed, &DerivationPath::new_bip44(Some(0), Some(0)));
+//! let pubkey = signing_key.verifying_key().to_encoded_point(false);
+//! let eth_address = eth_address_from_pubkey(pubkey.as_bytes()[1..].try_into().unwrap());
+//! let (signature, recovery_id) = sign_message(&signing_key.to_bytes().into(), b"hello").unwrap();
+//! ```
+
+use {
+    hmac::{Hmac, Mac},
+    k256::{
+        ecdsa::SigningKey,
+        elliptic_curve::{sec1::ToEncodedPoint, PrimeField},
+        ProjectivePoint, Scalar,
+    },
+    sha2::Sha512,
+    trezoa_derivation_path::DerivationPath,
+};
+
+const MASTER_HMAC_KEY: &[u8] = b"Bitcoin seed";
+const HARDENED_BIT: u32 = 1 << 31;
+
+type ChainCode = [u8; 32];
+
+/// Derives the secp256k1 signing key at `derivation_path` from `seed`.
+pub fn derive_signing_key(seed: &[u8], derivation_path: &DerivationPath) -> SigningKey {
+    derive_signing_key_with_indexes(
+        seed,
+        derivation_path.path().iter().map(|index| index.to_bits()),
+    )
+}
+
+fn derive_signing_key_with_indexes<I: IntoIterator<Item = u32>>(
+    seed: &[u8],
+    indexes: I,
+) -> SigningKey {
+    let (mut key, mut chain_code) = master_key(seed);
+    for index in indexes {
+        (key, chain_code) = child_key(&key, &chain_code, index);
+    }
+    SigningKey::from_bytes(&key.to_repr()).expect("derived keys are non-zero")
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> ([u8; 32], ChainCode) {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    for data in data {
+        mac.update(data);
+    }
+    let output = mac.finalize().into_bytes();
+    let (left, right) = output.split_at(32);
+    (left.try_into().unwrap(), right.try_into().unwrap())
+}
+
+/// Parses `bytes` as a scalar, returning `None` if it is not below the curve
+/// order.
+fn parse_scalar(bytes: &[u8; 32]) -> Option<Scalar> {
+    Option::from(Scalar::from_repr((*bytes).into()))
+}
+
+fn master_key(seed: &[u8]) -> (Scalar, ChainCode) {
+    let (mut left, mut right) = hmac_sha512(MASTER_HMAC_KEY, &[seed]);
+    loop {
+        if let Some(key) = parse_scalar(&left).filter(|key| *key != Scalar::ZERO) {
+            return (key, right);
+        }
+        // SLIP-0010 retries with the output of the previous round
+        (left, right) = hmac_sha512(MASTER_HMAC_KEY, &[&left, &right]);
+    }
+}
+
+#[allow(clippy::arithmetic_side_effects)]
+fn child_key(key: &Scalar, chain_code: &ChainCode, index: u32) -> (Scalar, ChainCode) {
+    let index_bytes = index.to_be_bytes();
+    let (mut left, mut right) = if index & HARDENED_BIT != 0 {
+        hmac_sha512(chain_code, &[&[0], &key.to_repr(), &index_bytes])
+    } else {
+        let public_key = (ProjectivePoint::GENERATOR * key)
+            .to_affine()
+            .to_encoded_point(true);
+        hmac_sha512(chain_code, &[public_key.as_bytes(), &index_bytes])
+    };
+    loop {
+        if let Some(tweak) = parse_scalar(&left) {
+            let child = tweak + key;
+            if child != Scalar::ZERO {
+                return (child, right);
+            }
+        }
+        // SLIP-0010 retries where BIP-32 would skip to the next index
+        (left, right) = hmac_sha512(chain_code, &[&[1], &right, &index_bytes]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slip10_vector() {
+        // Test vector 1 for secp256k1 from SLIP-0010
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let expected = [
+            (
+                vec![],
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+            ),
+            (
+                vec![HARDENED_BIT],
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+            ),
+            (
+                vec![HARDENED_BIT, 1],
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+            ),
+            (
+                vec![HARDENED_BIT, 1, HARDENED_BIT | 2],
+                "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+            ),
+        ];
+        for (indexes, private_key) in expected {
+            let signing_key = derive_signing_key_with_indexes(&seed, indexes);
+            assert_eq!(hex::encode(signing_key.to_bytes()), private_key);
+        }
+    }
+
+    #[test]
+    fn test_derive_signing_key() {
+        let seed = [7u8; 64];
+        let signing_key = derive_signing_key(&seed, &DerivationPath::new_bip44(Some(0), Some(0)));
+        assert_eq!(
+            hex::encode(signing_key.to_bytes()),
+            "e690081813a2f3b5a3292c0b41bdb573dddf77c0af0c787d6f1e9a71aed2e274"
+        );
+        assert_ne!(
+            signing_key,
+            derive_signing_key(&seed, &DerivationPath::new_bip44(Some(1), Some(0)))
+        );
+    }
+}
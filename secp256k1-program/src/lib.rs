@@ -792,6 +792,9 @@ use serde_derive::{Deserialize, Serialize};
 use trezoa_instruction::Instruction;
 use {digest::Digest, trezoa_signature::error::Error};
 
+#[cfg(feature = "derivation")]
+pub mod derivation;
+
 pub const SECP256K1_PUBKEY_SIZE: usize = 64;
 pub const SECP256K1_PRIVATE_KEY_SIZE: usize = 32;
 pub const HASHED_PUBKEY_SERIALIZED_SIZE: usize = 20;
@@ -1,7 +1,30 @@
 //! Functions for generating keypairs from seed phrases.
+//!
+//! Seed phrases are [BIP39] mnemonics over the English wordlist. [`Mnemonic`]
+//! generates new phrases and validates existing ones, and
+//! [`generate_seed_from_seed_phrase_and_passphrase`] stretches a phrase into
+//! the seed that keys are derived from.
+//!
+//! [BIP39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
 #![cfg_attr(docsrs, feature(doc_cfg))]
-use hmac::Hmac;
+use {
+    core::fmt,
+    hmac::Hmac,
+    sha2::{Digest, Sha256},
+};
 
+pub mod wordlist;
+
+const BITS_PER_WORD: usize = 11;
+
+/// The greatest edit distance at which a wordlist entry is suggested for an
+/// unknown word.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// Derives a BIP39 seed from `seed_phrase` and `passphrase` with PBKDF2.
+///
+/// The phrase is used exactly as given and is not checked, so call
+/// [`validate_seed_phrase`] first to reject mistyped phrases.
 pub fn generate_seed_from_seed_phrase_and_passphrase(
     seed_phrase: &str,
     passphrase: &str,
@@ -20,3 +43,435 @@ pub fn generate_seed_from_seed_phrase_and_passphrase(
     );
     seed
 }
+
+/// The number of words in a mnemonic.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WordCount {
+    Words12,
+    Words15,
+    Words18,
+    Words21,
+    Words24,
+}
+
+impl WordCount {
+    /// Returns the word count for a mnemonic of `len` words.
+    pub const fn from_len(len: usize) -> Option<Self> {
+        match len {
+            12 => Some(Self::Words12),
+            15 => Some(Self::Words15),
+            18 => Some(Self::Words18),
+            21 => Some(Self::Words21),
+            24 => Some(Self::Words24),
+            _ => None,
+        }
+    }
+
+    /// Returns the word count for a mnemonic encoding `len` bytes of entropy.
+    pub const fn from_entropy_len(len: usize) -> Option<Self> {
+        match len {
+            16 => Some(Self::Words12),
+            20 => Some(Self::Words15),
+            24 => Some(Self::Words18),
+            28 => Some(Self::Words21),
+            32 => Some(Self::Words24),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of words.
+    pub const fn count(self) -> usize {
+        match self {
+            Self::Words12 => 12,
+            Self::Words15 => 15,
+            Self::Words18 => 18,
+            Self::Words21 => 21,
+            Self::Words24 => 24,
+        }
+    }
+
+    /// Returns the number of bytes of entropy the words encode.
+    pub const fn entropy_len(self) -> usize {
+        match self {
+            Self::Words12 => 16,
+            Self::Words15 => 20,
+            Self::Words18 => 24,
+            Self::Words21 => 28,
+            Self::Words24 => 32,
+        }
+    }
+
+    /// Returns the number of checksum bits appended to the entropy.
+    const fn checksum_bits(self) -> usize {
+        match self {
+            Self::Words12 => 4,
+            Self::Words15 => 5,
+            Self::Words18 => 6,
+            Self::Words21 => 7,
+            Self::Words24 => 8,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MnemonicError {
+    /// The phrase does not have 12, 15, 18, 21 or 24 words.
+    InvalidWordCount(usize),
+    /// The entropy is not 16, 20, 24, 28 or 32 bytes long.
+    InvalidEntropyLength(usize),
+    /// The word at `index` is not in the wordlist.
+    UnknownWord {
+        index: usize,
+        word: String,
+        /// The closest words in the wordlist, best first.
+        suggestions: Vec<&'static str>,
+    },
+    /// The words are valid but their checksum does not match, usually because
+    /// words were swapped or mistyped as other valid words.
+    InvalidChecksum,
+    /// The operating system failed to provide entropy.
+    Entropy(getrandom::Error),
+}
+
+impl core::error::Error for MnemonicError {}
+
+impl fmt::Display for MnemonicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidWordCount(count) => write!(
+                f,
+                "seed phrase has {count} words, expected 12, 15, 18, 21 or 24"
+            ),
+            Self::InvalidEntropyLength(len) => {
+                write!(f, "entropy is {len} bytes, expected 16, 20, 24, 28 or 32")
+            }
+            Self::UnknownWord {
+                index,
+                word,
+                suggestions,
+            } => {
+                write!(f, "word {index} `{word}` is not in the BIP39 wordlist")?;
+                if !suggestions.is_empty() {
+                    write!(f, ", did you mean {}?", suggestions.join(" or "))?;
+                }
+                Ok(())
+            }
+            Self::InvalidChecksum => f.write_str("seed phrase checksum is invalid"),
+            Self::Entropy(e) => write!(f, "failed to read entropy: {e}"),
+        }
+    }
+}
+
+/// A validated BIP39 mnemonic.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    phrase: String,
+    entropy: Vec<u8>,
+    word_count: WordCount,
+}
+
+impl Mnemonic {
+    /// Generates a mnemonic from operating system entropy.
+    pub fn generate(word_count: WordCount) -> Result<Self, MnemonicError> {
+        let mut entropy = vec![0u8; word_count.entropy_len()];
+        getrandom::fill(&mut entropy).map_err(MnemonicError::Entropy)?;
+        Self::from_entropy(&entropy)
+    }
+
+    /// Encodes `entropy` as a mnemonic.
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, MnemonicError> {
+        let word_count = WordCount::from_entropy_len(entropy.len())
+            .ok_or(MnemonicError::InvalidEntropyLength(entropy.len()))?;
+        let checksum = checksum(entropy);
+        let words: Vec<_> = (0..word_count.count())
+            .map(|word| wordlist::ENGLISH[word_index(entropy, checksum, word)])
+            .collect();
+        Ok(Self {
+            phrase: words.join(" "),
+            entropy: entropy.to_vec(),
+            word_count,
+        })
+    }
+
+    /// Parses and validates a mnemonic.
+    ///
+    /// Words are matched case-insensitively and may be separated by any
+    /// whitespace.
+    pub fn parse(phrase: &str) -> Result<Self, MnemonicError> {
+        let words: Vec<_> = phrase.split_whitespace().collect();
+        let word_count =
+            WordCount::from_len(words.len()).ok_or(MnemonicError::InvalidWordCount(words.len()))?;
+
+        let mut indexes = Vec::with_capacity(words.len());
+        for (index, word) in words.iter().enumerate() {
+            let word = word.to_lowercase();
+            match wordlist::ENGLISH.binary_search(&word.as_str()) {
+                Ok(word_index) => indexes.push(word_index),
+                Err(_) => {
+                    return Err(MnemonicError::UnknownWord {
+                        index,
+                        suggestions: suggest_words(&word),
+                        word,
+                    })
+                }
+            }
+        }
+
+        let (entropy, checksum_bits) = decode_indexes(&indexes, word_count);
+        if checksum_bits != leading_checksum_bits(&entropy, word_count) {
+            return Err(MnemonicError::InvalidChecksum);
+        }
+
+        Ok(Self {
+            phrase: indexes
+                .iter()
+                .map(|index| wordlist::ENGLISH[*index])
+                .collect::<Vec<_>>()
+                .join(" "),
+            entropy,
+            word_count,
+        })
+    }
+
+    /// Returns the words of the mnemonic separated by single spaces.
+    pub fn phrase(&self) -> &str {
+        &self.phrase
+    }
+
+    /// Returns the entropy the mnemonic encodes.
+    pub fn entropy(&self) -> &[u8] {
+        &self.entropy
+    }
+
+    pub fn word_count(&self) -> WordCount {
+        self.word_count
+    }
+
+    /// Derives the seed for the mnemonic protected by `passphrase`.
+    pub fn to_seed(&self, passphrase: &str) -> Vec<u8> {
+        generate_seed_from_seed_phrase_and_passphrase(&self.phrase, passphrase)
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // Keep the secret words out of logs
+        f.debug_struct("Mnemonic")
+            .field("word_count", &self.word_count)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Checks that `seed_phrase` is a valid BIP39 mnemonic.
+pub fn validate_seed_phrase(seed_phrase: &str) -> Result<(), MnemonicError> {
+    Mnemonic::parse(seed_phrase).map(|_| ())
+}
+
+/// Returns the wordlist entries closest to `word`, best first.
+///
+/// Entries within a small edit distance are suggested, as are entries that
+/// start with `word` when it is at least three letters long.
+pub fn suggest_words(word: &str) -> Vec<&'static str> {
+    let word = word.to_lowercase();
+    let mut suggestions: Vec<_> = wordlist::ENGLISH
+        .iter()
+        .filter_map(|candidate| {
+            let distance = if word.len() >= 3 && candidate.starts_with(&word) {
+                1
+            } else {
+                edit_distance(&word, candidate)
+            };
+            (distance <= MAX_SUGGESTION_DISTANCE).then_some((distance, *candidate))
+        })
+        .collect();
+    suggestions.sort();
+    suggestions.into_iter().map(|(_, word)| word).collect()
+}
+
+fn checksum(entropy: &[u8]) -> u8 {
+    Sha256::digest(entropy)[0]
+}
+
+/// Returns the checksum bits a mnemonic of `word_count` words appends to
+/// `entropy`.
+#[allow(clippy::arithmetic_side_effects)]
+fn leading_checksum_bits(entropy: &[u8], word_count: WordCount) -> u8 {
+    checksum(entropy) >> (8 - word_count.checksum_bits())
+}
+
+/// Returns the wordlist index of the `word`th group of eleven bits of the
+/// entropy followed by its checksum.
+#[allow(clippy::arithmetic_side_effects)]
+fn word_index(entropy: &[u8], checksum: u8, word: usize) -> usize {
+    (0..BITS_PER_WORD).fold(0, |index, bit| {
+        let position = word * BITS_PER_WORD + bit;
+        let byte = entropy.get(position / 8).copied().unwrap_or(checksum);
+        (index << 1) | usize::from((byte >> (7 - position % 8)) & 1)
+    })
+}
+
+/// Splits wordlist indexes back into the entropy and checksum bits they
+/// encode.
+#[allow(clippy::arithmetic_side_effects)]
+fn decode_indexes(indexes: &[usize], word_count: WordCount) -> (Vec<u8>, u8) {
+    let mut entropy = vec![0u8; word_count.entropy_len()];
+    let mut checksum_bits = 0u8;
+    for (word, index) in indexes.iter().enumerate() {
+        for bit in 0..BITS_PER_WORD {
+            let value = ((index >> (BITS_PER_WORD - 1 - bit)) & 1) as u8;
+            let position = word * BITS_PER_WORD + bit;
+            match entropy.get_mut(position / 8) {
+                Some(byte) => *byte |= value << (7 - position % 8),
+                None => checksum_bits = (checksum_bits << 1) | value,
+            }
+        }
+    }
+    (entropy, checksum_bits)
+}
+
+/// Returns the Levenshtein distance between two ASCII words.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.as_bytes();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len().saturating_add(1)];
+    for (i, a_byte) in a.bytes().enumerate() {
+        current[0] = i.saturating_add(1);
+        for (j, b_byte) in b.iter().enumerate() {
+            let substitution = previous[j].saturating_add(usize::from(a_byte != *b_byte));
+            let deletion = previous[j.saturating_add(1)].saturating_add(1);
+            let insertion = current[j].saturating_add(1);
+            current[j.saturating_add(1)] = substitution.min(deletion).min(insertion);
+        }
+        core::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vectors() {
+        // From the BIP39 reference test vectors, with passphrase "TREZOR"
+        let vectors: [(&[u8], &str, &str); 4] = [
+            (
+                &[0; 16],
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon about",
+                "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+            ),
+            (
+                &[0x7f; 16],
+                "legal winner thank year wave sausage worth useful legal winner thank yellow",
+                "2e8905819b8723fe2c1d161860e5ee1830318dbf49a83bd451cfb8440c28bd6fa457fe1296106559a3c80937a1c1069be3a3a5bd381ee6260e8d9739fce1f607",
+            ),
+            (
+                &[0xff; 16],
+                "zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo zoo wrong",
+                "ac27495480225222079d7be181583751e86f571027b0497b5b5d11218e0a8a13332572917f0f8e5a589620c6f15b11c61dee327651a14c34e18231052e48c069",
+            ),
+            (
+                &[0; 32],
+                "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                 abandon abandon abandon art",
+                "bda85446c68413707090a52022edd26a1c9462295029f2e60cd7c4f2bbd3097170af7a4d73245cafa9c3cca8d561a7c3de6f5d4a10be8ed2a5e608d68f92fcc8",
+            ),
+        ];
+        for (entropy, phrase, seed) in vectors {
+            let mnemonic = Mnemonic::from_entropy(entropy).unwrap();
+            assert_eq!(mnemonic.phrase(), phrase);
+            assert_eq!(hex::encode(mnemonic.to_seed("TREZOR")), seed);
+
+            let parsed = Mnemonic::parse(phrase).unwrap();
+            assert_eq!(parsed, mnemonic);
+            assert_eq!(parsed.entropy(), entropy);
+        }
+    }
+
+    #[test]
+    fn test_generate() {
+        for word_count in [
+            WordCount::Words12,
+            WordCount::Words15,
+            WordCount::Words18,
+            WordCount::Words21,
+            WordCount::Words24,
+        ] {
+            let mnemonic = Mnemonic::generate(word_count).unwrap();
+            assert_eq!(mnemonic.word_count(), word_count);
+            assert_eq!(mnemonic.entropy().len(), word_count.entropy_len());
+            assert_eq!(mnemonic.phrase().split(' ').count(), word_count.count());
+            assert_eq!(Mnemonic::parse(mnemonic.phrase()).unwrap(), mnemonic);
+        }
+        assert_ne!(
+            Mnemonic::generate(WordCount::Words12).unwrap(),
+            Mnemonic::generate(WordCount::Words12).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_normalizes() {
+        let mnemonic = Mnemonic::parse(
+            "  Legal winner thank year\twave sausage worth useful\nlegal WINNER thank yellow ",
+        )
+        .unwrap();
+        assert_eq!(
+            mnemonic.phrase(),
+            "legal winner thank year wave sausage worth useful legal winner thank yellow"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Mnemonic::parse("abandon abandon abandon"),
+            Err(MnemonicError::InvalidWordCount(3))
+        ));
+        assert!(matches!(
+            Mnemonic::from_entropy(&[0; 15]),
+            Err(MnemonicError::InvalidEntropyLength(15))
+        ));
+
+        let Err(MnemonicError::UnknownWord {
+            index,
+            word,
+            suggestions,
+        }) = Mnemonic::parse(
+            "legal winner thank year wave sausage worth usefull legal winner thank yellow",
+        )
+        else {
+            panic!("expected an unknown word");
+        };
+        assert_eq!(index, 7);
+        assert_eq!(word, "usefull");
+        assert_eq!(suggestions[0], "useful");
+
+        // Swapping two words keeps them valid but breaks the checksum
+        assert!(matches!(
+            Mnemonic::parse(
+                "legal winner thank year wave sausage worth useful legal winner yellow thank"
+            ),
+            Err(MnemonicError::InvalidChecksum)
+        ));
+        assert!(validate_seed_phrase(
+            "legal winner thank year wave sausage worth useful legal winner thank yellow"
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_suggest_words() {
+        assert_eq!(suggest_words("abandn"), vec!["abandon"]);
+        assert_eq!(suggest_words("zoo")[0], "zoo");
+        assert!(suggest_words("aband").contains(&"abandon"));
+        assert!(suggest_words("qqqqqqqq").is_empty());
+    }
+
+    #[test]
+    fn test_debug_hides_phrase() {
+        let mnemonic = Mnemonic::from_entropy(&[0; 16]).unwrap();
+        assert!(!format!("{mnemonic:?}").contains("abandon"));
+    }
+}
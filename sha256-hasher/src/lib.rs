@@ -1,42 +1,21 @@
 #![no_std]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 
+#[cfg(all(feature = "sha2", not(any(target_os = "trezoa", target_arch = "bpf"))))]
+use sha2::{Digest, Sha256};
 #[cfg(any(target_os = "trezoa", target_arch = "bpf"))]
 pub use trezoa_define_syscall::definitions::sol_sha256;
 use trezoa_hash::Hash;
 #[cfg(any(target_os = "trezoa", target_arch = "bpf"))]
 use {core::mem::MaybeUninit, trezoa_hash::HASH_BYTES};
-#[cfg(all(feature = "sha2", not(any(target_os = "trezoa", target_arch = "bpf"))))]
-use {
-    sha2::{Digest, Sha256},
-    trezoa_hash::HASH_BYTES,
-};
 
-#[cfg(all(feature = "sha2", not(any(target_os = "trezoa", target_arch = "bpf"))))]
-#[derive(Clone, Default)]
-pub struct Hasher {
-    hasher: Sha256,
-}
-
-#[cfg(all(feature = "sha2", not(any(target_os = "trezoa", target_arch = "bpf"))))]
-impl Hasher {
-    #[inline(always)]
-    pub fn hash(&mut self, val: &[u8]) {
-        self.hasher.update(val);
-    }
-
-    #[inline(always)]
-    pub fn hashv(&mut self, vals: &[&[u8]]) {
-        for val in vals {
-            self.hash(val);
-        }
-    }
-
-    #[inline(always)]
-    pub fn result(self) -> Hash {
-        let bytes: [u8; HASH_BYTES] = self.hasher.finalize().into();
-        bytes.into()
-    }
+trezoa_hash::define_hasher! {
+    feature = "sha2",
+    syscall = "sol_sha256",
+    hasher = Sha256,
+    hashv = hashv,
+    update = |hasher, val| hasher.update(val),
+    finalize = |hasher| hasher.finalize().into(),
 }
 
 /// Return a Sha256 hash for the given data.
@@ -79,3 +58,23 @@ pub fn hashv(vals: &[&[u8]]) -> Hash {
 pub fn hash(val: &[u8]) -> Hash {
     hashv(&[val])
 }
+
+#[cfg(test)]
+mod tests {
+    use {super::*, trezoa_hash::hasher::Hasher as _};
+
+    #[test]
+    fn test_hasher_known_answer() {
+        let expected = Hash::new_from_array([
+            0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae,
+            0x22, 0x23, 0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61,
+            0xf2, 0x00, 0x15, 0xad,
+        ]);
+        assert_eq!(hash(b"abc"), expected);
+
+        let mut hasher = Hasher::default();
+        hasher.hashv(&[b"a", b"bc"]);
+        assert_eq!(hasher.result(), expected);
+        assert_eq!(Hasher::hashv_once(&[b"a", b"bc"]), expected);
+    }
+}
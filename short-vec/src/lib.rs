@@ -282,6 +282,20 @@ pub fn decode_shortu16_len(bytes: &[u8]) -> Result<(usize, usize), ()> {
     Err(())
 }
 
+/// Return how many bytes the encoding of a length takes.
+///
+/// Lengths above `u16::MAX` cannot be encoded and are counted as the maximum
+/// encoding length.
+pub const fn encoded_len(len: usize) -> usize {
+    if len < 0x80 {
+        1
+    } else if len < 0x4000 {
+        2
+    } else {
+        3
+    }
+}
+
 #[cfg(all(test, feature = "serde"))]
 mod tests {
     use {
@@ -297,6 +311,11 @@ mod tests {
 
     fn assert_len_encoding(len: u16, bytes: &[u8]) {
         assert_eq!(encode_len(len), bytes, "unexpected usize encoding");
+        assert_eq!(
+            encoded_len(usize::from(len)),
+            bytes.len(),
+            "unexpected encoded length"
+        );
         assert_eq!(
             decode_shortu16_len(bytes).unwrap(),
             (usize::from(len), bytes.len()),
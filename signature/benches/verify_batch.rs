@@ -0,0 +1,44 @@
+use {
+    criterion::{criterion_group, criterion_main, Criterion},
+    ed25519_dalek::{Signer, SigningKey},
+    trezoa_signature::Signature,
+    std::hint::black_box,
+};
+
+// Compares per-signature verification with cofactored batch verification
+fn bench_verify_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_batch");
+
+    for num_signatures in [1u64, 16, 64, 256, 1024] {
+        let signed: Vec<([u8; 32], Signature, Vec<u8>)> = (0..num_signatures)
+            .map(|i| {
+                let mut seed = [0; 32];
+                seed[..8].copy_from_slice(&i.to_le_bytes());
+                let signing_key = SigningKey::from_bytes(&seed);
+                let message = format!("message_{i}").into_bytes();
+                let signature = Signature::from(signing_key.sign(&message).to_bytes());
+                (signing_key.verifying_key().to_bytes(), signature, message)
+            })
+            .collect();
+        let items: Vec<(&Signature, &[u8], &[u8])> = signed
+            .iter()
+            .map(|(pubkey, signature, message)| (signature, &pubkey[..], &message[..]))
+            .collect();
+
+        group.bench_function(format!("verify {num_signatures}"), |b| {
+            b.iter(|| {
+                for (signature, pubkey, message) in &items {
+                    assert!(black_box(signature.verify(pubkey, message)));
+                }
+            });
+        });
+
+        group.bench_function(format!("verify_batch_cofactored {num_signatures}"), |b| {
+            b.iter(|| black_box(Signature::verify_batch_cofactored(&items)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_verify_batch);
+criterion_main!(benches);
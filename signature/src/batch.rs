@@ -0,0 +1,216 @@
+//! Batch verification of many signatures.
+
+use {
+    crate::Signature,
+    alloc::{vec, vec::Vec},
+    curve25519_dalek::edwards::CompressedEdwardsY,
+    ed25519_dalek::VerifyingKey,
+};
+
+/// Number of signatures checked together by
+/// [`Signature::verify_batch_cofactored`].
+///
+/// Larger batches gain little speed, and a failed batch is verified again one
+/// signature at a time.
+const BATCH_SIZE: usize = 64;
+
+struct Batch<'a> {
+    indexes: Vec<usize>,
+    messages: Vec<&'a [u8]>,
+    signatures: Vec<ed25519_dalek::Signature>,
+    pubkeys: Vec<VerifyingKey>,
+}
+
+impl Batch<'_> {
+    fn new() -> Self {
+        Self {
+            indexes: Vec::with_capacity(BATCH_SIZE),
+            messages: Vec::with_capacity(BATCH_SIZE),
+            signatures: Vec::with_capacity(BATCH_SIZE),
+            pubkeys: Vec::with_capacity(BATCH_SIZE),
+        }
+    }
+
+    /// Verifies the batch, falling back to verifying each signature if it
+    /// fails, and records the results.
+    fn verify(&mut self, items: &[(&Signature, &[u8], &[u8])], results: &mut [bool]) {
+        if ed25519_dalek::verify_batch(&self.messages, &self.signatures, &self.pubkeys).is_ok() {
+            for &index in &self.indexes {
+                results[index] = true;
+            }
+        } else {
+            for &index in &self.indexes {
+                let (signature, pubkey, message) = items[index];
+                results[index] = signature.verify(pubkey, message);
+            }
+        }
+        self.indexes.clear();
+        self.messages.clear();
+        self.signatures.clear();
+        self.pubkeys.clear();
+    }
+}
+
+impl Signature {
+    /// Parses the signature and `pubkey_bytes` for batch verification.
+    ///
+    /// Returns `None` for the inputs [`Signature::verify`] rejects before
+    /// checking the signature equation: an invalid or weak public key, or a
+    /// small-order or invalid `R`.
+    fn batch_item(&self, pubkey_bytes: &[u8]) -> Option<(ed25519_dalek::Signature, VerifyingKey)> {
+        let pubkey = VerifyingKey::try_from(pubkey_bytes).ok()?;
+        if pubkey.is_weak() {
+            return None;
+        }
+        let signature = ed25519_dalek::Signature::from_bytes(&self.0);
+        let r = CompressedEdwardsY(*signature.r_bytes()).decompress()?;
+        (!r.is_small_order()).then_some((signature, pubkey))
+    }
+
+    /// Verifies many `(signature, pubkey, message)` triples with the
+    /// cofactored ed25519 equation, returning whether each signature is valid.
+    ///
+    /// Signatures are checked in batches with ed25519 batch verification,
+    /// which is much faster than [`Signature::verify`] on each. When a batch
+    /// fails, its signatures are verified one at a time to find the invalid
+    /// ones.
+    ///
+    /// Batch verification multiplies the signature equation by the cofactor,
+    /// so unlike [`Signature::verify`] it accepts a signature whose `R` or
+    /// public key has a small-order component that cancels out. Honest
+    /// signers never produce such signatures, but an attacker can, so only
+    /// use this where accepting them is harmless, such as re-verifying
+    /// transactions that are already confirmed. Use [`Signature::verify`]
+    /// where results must match the runtime's signature checks.
+    pub fn verify_batch_cofactored(items: &[(&Signature, &[u8], &[u8])]) -> Vec<bool> {
+        let mut results = vec![false; items.len()];
+        let mut batch = Batch::new();
+        for (index, &(signature, pubkey_bytes, message)) in items.iter().enumerate() {
+            let Some((batch_signature, pubkey)) = signature.batch_item(pubkey_bytes) else {
+                results[index] = signature.verify(pubkey_bytes, message);
+                continue;
+            };
+            batch.indexes.push(index);
+            batch.messages.push(message);
+            batch.signatures.push(batch_signature);
+            batch.pubkeys.push(pubkey);
+            if batch.indexes.len() == BATCH_SIZE {
+                batch.verify(items, &mut results);
+            }
+        }
+        if !batch.indexes.is_empty() {
+            batch.verify(items, &mut results);
+        }
+        results
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        curve25519_dalek::{
+            constants::{ED25519_BASEPOINT_POINT, EIGHT_TORSION},
+            edwards::EdwardsPoint,
+            scalar::Scalar,
+            traits::Identity,
+        },
+        ed25519_dalek::{Signer, SigningKey},
+        sha2::{Digest, Sha512},
+    };
+
+    fn sign(seed: u8, message: &[u8]) -> ([u8; 32], Signature) {
+        let signing_key = SigningKey::from_bytes(&[seed; 32]);
+        let signature = Signature::from(signing_key.sign(message).to_bytes());
+        (signing_key.verifying_key().to_bytes(), signature)
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let messages: Vec<Vec<u8>> = (0..150u8).map(|i| vec![i; 10]).collect();
+        let mut signed: Vec<_> = messages
+            .iter()
+            .zip(0u8..)
+            .map(|(message, seed)| sign(seed, message))
+            .collect();
+        // an invalid signature in the second batch
+        signed[100].1 = signed[99].1;
+
+        let items: Vec<_> = signed
+            .iter()
+            .zip(&messages)
+            .map(|((pubkey, signature), message)| (signature, &pubkey[..], &message[..]))
+            .collect();
+        let results = Signature::verify_batch_cofactored(&items);
+        let expected: Vec<bool> = items
+            .iter()
+            .map(|(signature, pubkey, message)| signature.verify(pubkey, message))
+            .collect();
+        assert_eq!(results, expected);
+        assert_eq!(results.iter().filter(|valid| !**valid).count(), 1);
+        assert!(!results[100]);
+
+        assert!(Signature::verify_batch_cofactored(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_verify_batch_rejects_unbatchable() {
+        let message: &[u8] = b"message";
+        let (pubkey, signature) = sign(1, message);
+        // Identity point, a weak public key
+        let mut weak_pubkey = [0; 32];
+        weak_pubkey[0] = 1;
+        // A signature with a small-order R
+        let mut small_order_r = <[u8; 64]>::from(signature);
+        small_order_r[..32].copy_from_slice(&weak_pubkey);
+        let small_order_r = Signature::from(small_order_r);
+
+        let results = Signature::verify_batch_cofactored(&[
+            (&signature, &pubkey[..], message),
+            (&signature, &weak_pubkey[..], message),
+            (&small_order_r, &pubkey[..], message),
+            (&signature, &pubkey[..31], message),
+        ]);
+        assert_eq!(results, vec![true, false, false, false]);
+    }
+
+    #[test]
+    fn test_verify_batch_cofactored_mixed_order() {
+        // Signatures that satisfy the cofactored equation, and so pass batch
+        // verification, but mostly not the one checked by `Signature::verify`,
+        // because the public key or `R` has a small-order component.
+        let a = Scalar::from(7u64);
+        let r = Scalar::from(11u64);
+        let torsion = EIGHT_TORSION[1];
+        let identity = EdwardsPoint::identity();
+        for (pubkey_torsion, r_torsion) in [(torsion, identity), (identity, torsion)] {
+            let pubkey = (ED25519_BASEPOINT_POINT * a + pubkey_torsion).compress();
+            let big_r = (ED25519_BASEPOINT_POINT * r + r_torsion).compress();
+            let signed: Vec<_> = (0u8..16)
+                .map(|i| {
+                    let message = [i];
+                    let k = Scalar::from_hash(
+                        Sha512::new()
+                            .chain_update(big_r.as_bytes())
+                            .chain_update(pubkey.as_bytes())
+                            .chain_update(message),
+                    );
+                    let mut signature = [0; 64];
+                    signature[..32].copy_from_slice(big_r.as_bytes());
+                    signature[32..].copy_from_slice((r + k * a).as_bytes());
+                    (Signature::from(signature), message)
+                })
+                .collect();
+            let items: Vec<_> = signed
+                .iter()
+                .map(|(signature, message)| (signature, &pubkey.as_bytes()[..], &message[..]))
+                .collect();
+
+            let results = Signature::verify_batch_cofactored(&items);
+            assert!(results.iter().all(|valid| *valid));
+            assert!(items
+                .iter()
+                .any(|(signature, pubkey, message)| !signature.verify(pubkey, message)));
+        }
+    }
+}
@@ -23,6 +23,8 @@ use {
     serde_derive::{Deserialize, Serialize},
 };
 
+#[cfg(feature = "batch-verify")]
+mod batch;
 pub mod error;
 
 /// Number of bytes in a signature
@@ -16,8 +16,9 @@ use {
 
 pub mod null_signer;
 pub mod signers;
+pub mod threshold_signer;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum PresignerError {
     VerificationFailure,
 }
@@ -32,7 +33,7 @@ impl fmt::Display for PresignerError {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum SignerError {
     KeypairPubkeyMismatch,
     NotEnoughSigners,
@@ -0,0 +1,340 @@
+//! A [`Signers`] collection that succeeds once a threshold of its signers
+//! have signed.
+//!
+//! A [`ThresholdSigner`] is useful when approvals for a transaction are
+//! collected from several backends over time. Each call to
+//! [`Signers::try_sign_message`] asks the signers that have not yet signed
+//! the message again, keeps the signatures they produce, and fails with
+//! [`SignerError::NotEnoughSigners`] until the [`ThresholdPolicy`] is met.
+//! [`ThresholdSigner::status`] reports who has signed, who is missing, and
+//! the error each missing signer returned.
+
+use {
+    crate::{signers::Signers, Signer, SignerError},
+    trezoa_pubkey::Pubkey,
+    trezoa_signature::Signature,
+    std::sync::{Mutex, MutexGuard, PoisonError},
+};
+
+/// How many signers of a [`ThresholdSigner`] must sign a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ThresholdPolicy {
+    /// Every signer must sign.
+    All,
+    /// At least one signer must sign.
+    Any,
+    /// At least `k` signers must sign.
+    AtLeast(usize),
+}
+
+impl ThresholdPolicy {
+    fn required_signatures(&self, num_signers: usize) -> usize {
+        match self {
+            Self::All => num_signers,
+            Self::Any => 1,
+            Self::AtLeast(k) => *k,
+        }
+    }
+}
+
+/// The signing progress of a [`ThresholdSigner`] on its current message.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ThresholdStatus {
+    /// Number of signatures required by the policy.
+    pub required: usize,
+    /// Signers that have signed the message.
+    pub signed: Vec<Pubkey>,
+    /// Signers that have not signed the message.
+    pub missing: Vec<Pubkey>,
+    /// The error each missing signer returned the last time it was asked to
+    /// sign the message.
+    pub errors: Vec<(Pubkey, SignerError)>,
+}
+
+impl ThresholdStatus {
+    /// Whether enough signers have signed to satisfy the policy.
+    pub fn is_satisfied(&self) -> bool {
+        self.signed.len() >= self.required
+    }
+}
+
+#[derive(Default)]
+struct SigningState {
+    message: Vec<u8>,
+    signatures: Vec<Option<Signature>>,
+    errors: Vec<Option<SignerError>>,
+}
+
+/// Wraps several [`Signer`]s behind a [`ThresholdPolicy`].
+///
+/// `ThresholdSigner` implements [`Signers`], returning one signature per
+/// inner signer in order, so it can be passed directly to
+/// `Transaction::try_sign`. Signers that have not signed are given
+/// `Signature::default()`; when the policy does not require every signer, use
+/// `Transaction::try_partial_sign` so those positions may be left unsigned.
+///
+/// Signatures are kept until a different message is signed, so a signer that
+/// has approved a message is not asked again when signing is retried. When
+/// signing fails with [`SignerError::NotEnoughSigners`],
+/// [`ThresholdSigner::status`] reports the missing signers and their errors.
+pub struct ThresholdSigner<S = Box<dyn Signer>> {
+    signers: Vec<S>,
+    policy: ThresholdPolicy,
+    state: Mutex<SigningState>,
+}
+
+impl<S: Signer> ThresholdSigner<S> {
+    /// Creates a `ThresholdSigner`, failing with
+    /// [`SignerError::InvalidInput`] if the policy requires no signatures or
+    /// more signatures than there are `signers`.
+    pub fn new(signers: Vec<S>, policy: ThresholdPolicy) -> Result<Self, SignerError> {
+        let required = policy.required_signatures(signers.len());
+        if required == 0 || required > signers.len() {
+            return Err(SignerError::InvalidInput(format!(
+                "threshold of {required} signatures is invalid for {} signers",
+                signers.len()
+            )));
+        }
+        Ok(Self {
+            signers,
+            policy,
+            state: Mutex::default(),
+        })
+    }
+
+    pub fn policy(&self) -> ThresholdPolicy {
+        self.policy
+    }
+
+    pub fn signers(&self) -> &[S] {
+        &self.signers
+    }
+
+    /// Returns who has and has not signed the most recent message, and why
+    /// the missing signers failed to sign it.
+    pub fn status(&self) -> ThresholdStatus {
+        let state = self.lock_state();
+        let mut status = ThresholdStatus {
+            required: self.policy.required_signatures(self.signers.len()),
+            ..ThresholdStatus::default()
+        };
+        for (index, signer) in self.signers.iter().enumerate() {
+            match state.signatures.get(index) {
+                Some(Some(_)) => status.signed.push(signer.pubkey()),
+                _ => status.missing.push(signer.pubkey()),
+            }
+            if let Some(Some(error)) = state.errors.get(index) {
+                status.errors.push((signer.pubkey(), error.clone()));
+            }
+        }
+        status
+    }
+
+    /// Forgets the signatures collected for the most recent message.
+    pub fn reset(&self) {
+        *self.lock_state() = SigningState::default();
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, SigningState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Asks every signer that has not yet signed `message` to sign it, and
+    /// returns the signatures collected so far.
+    fn collect_signatures(&self, message: &[u8]) -> Vec<Option<Signature>> {
+        let mut state = self.lock_state();
+        if state.message != message || state.signatures.len() != self.signers.len() {
+            state.message = message.to_vec();
+            state.signatures = vec![None; self.signers.len()];
+            state.errors = vec![None; self.signers.len()];
+        }
+        let SigningState {
+            signatures, errors, ..
+        } = &mut *state;
+        for ((signer, signature), error) in self.signers.iter().zip(signatures).zip(errors) {
+            if signature.is_none() {
+                match signer.try_sign_message(message) {
+                    Ok(new_signature) => {
+                        *signature = Some(new_signature);
+                        *error = None;
+                    }
+                    Err(new_error) => *error = Some(new_error),
+                }
+            }
+        }
+        state.signatures.clone()
+    }
+}
+
+impl<S: Signer> Signers for ThresholdSigner<S> {
+    fn pubkeys(&self) -> Vec<Pubkey> {
+        self.signers.iter().map(|signer| signer.pubkey()).collect()
+    }
+
+    fn try_pubkeys(&self) -> Result<Vec<Pubkey>, SignerError> {
+        self.signers
+            .iter()
+            .map(|signer| signer.try_pubkey())
+            .collect()
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Vec<Signature> {
+        self.collect_signatures(message)
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Vec<Signature>, SignerError> {
+        let signatures = self.collect_signatures(message);
+        let num_signed = signatures.iter().flatten().count();
+        if num_signed < self.policy.required_signatures(self.signers.len()) {
+            return Err(SignerError::NotEnoughSigners);
+        }
+        Ok(signatures
+            .into_iter()
+            .map(Option::unwrap_or_default)
+            .collect())
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.signers.iter().any(|signer| signer.is_interactive())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::cell::Cell};
+
+    struct Approver {
+        pubkey: Pubkey,
+        approved: Cell<bool>,
+        num_requests: Cell<usize>,
+    }
+
+    impl Approver {
+        fn new(approved: bool) -> Self {
+            Self {
+                pubkey: Pubkey::new_unique(),
+                approved: Cell::new(approved),
+                num_requests: Cell::new(0),
+            }
+        }
+    }
+
+    impl Signer for Approver {
+        fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+            Ok(self.pubkey)
+        }
+
+        fn try_sign_message(&self, _message: &[u8]) -> Result<Signature, SignerError> {
+            self.num_requests
+                .set(self.num_requests.get().saturating_add(1));
+            if !self.approved.get() {
+                return Err(SignerError::UserCancel("pending approval".to_string()));
+            }
+            let mut signature = [0; 64];
+            signature[..32].copy_from_slice(self.pubkey.as_ref());
+            Ok(Signature::from(signature))
+        }
+
+        fn is_interactive(&self) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_invalid_policy() {
+        let signers = vec![Approver::new(true), Approver::new(true)];
+        assert!(matches!(
+            ThresholdSigner::new(signers, ThresholdPolicy::AtLeast(3)),
+            Err(SignerError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            ThresholdSigner::<Approver>::new(vec![], ThresholdPolicy::All),
+            Err(SignerError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            ThresholdSigner::new(vec![Approver::new(true)], ThresholdPolicy::AtLeast(0)),
+            Err(SignerError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_k_of_n() {
+        let signers = vec![
+            Approver::new(true),
+            Approver::new(false),
+            Approver::new(true),
+        ];
+        let missing = signers[1].pubkey;
+        let threshold_signer = ThresholdSigner::new(signers, ThresholdPolicy::AtLeast(2)).unwrap();
+        assert!(threshold_signer.is_interactive());
+
+        let signatures = threshold_signer.try_sign_message(b"message").unwrap();
+        assert_eq!(signatures.len(), 3);
+        assert_ne!(signatures[0], Signature::default());
+        assert_eq!(signatures[1], Signature::default());
+        assert_ne!(signatures[2], Signature::default());
+
+        let status = threshold_signer.status();
+        assert!(status.is_satisfied());
+        assert_eq!(status.missing, vec![missing]);
+        assert_eq!(status.signed.len(), 2);
+    }
+
+    #[test]
+    fn test_collects_approvals_across_attempts() {
+        let signers = vec![Approver::new(true), Approver::new(false)];
+        let threshold_signer = ThresholdSigner::new(signers, ThresholdPolicy::All).unwrap();
+
+        assert_eq!(
+            threshold_signer.try_sign_message(b"message"),
+            Err(SignerError::NotEnoughSigners)
+        );
+        let status = threshold_signer.status();
+        assert!(!status.is_satisfied());
+        assert_eq!(status.required, 2);
+        assert_eq!(status.missing, vec![threshold_signer.signers()[1].pubkey]);
+        assert_eq!(
+            status.errors,
+            vec![(
+                threshold_signer.signers()[1].pubkey,
+                SignerError::UserCancel("pending approval".to_string())
+            )]
+        );
+
+        threshold_signer.signers()[1].approved.set(true);
+        let signatures = threshold_signer.try_sign_message(b"message").unwrap();
+        assert_eq!(signatures, threshold_signer.sign_message(b"message"));
+        let status = threshold_signer.status();
+        assert!(status.missing.is_empty());
+        assert!(status.errors.is_empty());
+        // The first signer approved once and was not asked again
+        assert_eq!(threshold_signer.signers()[0].num_requests.get(), 1);
+        assert_eq!(threshold_signer.signers()[1].num_requests.get(), 2);
+
+        // A new message asks every signer again
+        threshold_signer.try_sign_message(b"other").unwrap();
+        assert_eq!(threshold_signer.signers()[0].num_requests.get(), 2);
+
+        threshold_signer.reset();
+        assert_eq!(threshold_signer.status().signed, vec![]);
+    }
+
+    #[test]
+    fn test_any() {
+        let signers: Vec<Box<dyn Signer>> = vec![
+            Box::new(Approver::new(false)),
+            Box::new(Approver::new(true)),
+        ];
+        let pubkeys: Vec<_> = signers.iter().map(|signer| signer.pubkey()).collect();
+        let threshold_signer = ThresholdSigner::new(signers, ThresholdPolicy::Any).unwrap();
+        assert_eq!(threshold_signer.pubkeys(), pubkeys);
+        assert_eq!(threshold_signer.try_pubkeys().unwrap(), pubkeys);
+
+        let signatures = threshold_signer.try_sign_message(b"message").unwrap();
+        assert_eq!(signatures[0], Signature::default());
+        assert_eq!(threshold_signer.status().signed, vec![pubkeys[1]]);
+    }
+}
@@ -7,6 +7,8 @@ use {
     trezoa_transaction::Transaction,
 };
 
+pub mod versioned;
+
 /// Create and sign new SystemInstruction::CreateAccount transaction
 pub fn create_account(
     from_keypair: &Keypair,
@@ -24,6 +26,63 @@ pub fn create_account(
     Transaction::new(&[from_keypair, to_keypair], message, recent_blockhash)
 }
 
+/// Create and sign new SystemInstruction::CreateAccountAllowPrefund transaction
+///
+/// The payer only signs for the transfer into the new account when `lamports`
+/// is non-zero; it always signs as the fee payer.
+pub fn create_account_allow_prefund(
+    payer_keypair: &Keypair,
+    new_account_keypair: &Keypair,
+    recent_blockhash: Hash,
+    lamports: u64,
+    space: u64,
+    program_id: &Pubkey,
+) -> Transaction {
+    let payer_pubkey = payer_keypair.pubkey();
+    let new_account_pubkey = new_account_keypair.pubkey();
+    let payer_and_lamports = (lamports > 0).then_some((&payer_pubkey, lamports));
+    let instruction = system_instruction::create_account_allow_prefund(
+        &new_account_pubkey,
+        payer_and_lamports,
+        space,
+        program_id,
+    );
+    let message = Message::new(&[instruction], Some(&payer_pubkey));
+    Transaction::new(
+        &[payer_keypair, new_account_keypair],
+        message,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new SystemInstruction::CreateAccountWithSeed transaction
+///
+/// `to` must match `Pubkey::create_with_seed(base, seed, program_id)`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_account_with_seed(
+    from_keypair: &Keypair,
+    to: &Pubkey,
+    base_keypair: &Keypair,
+    seed: &str,
+    recent_blockhash: Hash,
+    lamports: u64,
+    space: u64,
+    program_id: &Pubkey,
+) -> Transaction {
+    let from_pubkey = from_keypair.pubkey();
+    let instruction = system_instruction::create_account_with_seed(
+        &from_pubkey,
+        to,
+        &base_keypair.pubkey(),
+        seed,
+        lamports,
+        space,
+        program_id,
+    );
+    let message = Message::new(&[instruction], Some(&from_pubkey));
+    Transaction::new(&[from_keypair, base_keypair], message, recent_blockhash)
+}
+
 /// Create and sign new SystemInstruction::Allocate transaction
 pub fn allocate(
     payer_keypair: &Keypair,
@@ -38,6 +97,30 @@ pub fn allocate(
     Transaction::new(&[payer_keypair, account_keypair], message, recent_blockhash)
 }
 
+/// Create and sign new SystemInstruction::AllocateWithSeed transaction
+///
+/// `account` must match `Pubkey::create_with_seed(base, seed, program_id)`.
+pub fn allocate_with_seed(
+    payer_keypair: &Keypair,
+    account: &Pubkey,
+    base_keypair: &Keypair,
+    seed: &str,
+    recent_blockhash: Hash,
+    space: u64,
+    program_id: &Pubkey,
+) -> Transaction {
+    let payer_pubkey = payer_keypair.pubkey();
+    let instruction = system_instruction::allocate_with_seed(
+        account,
+        &base_keypair.pubkey(),
+        seed,
+        space,
+        program_id,
+    );
+    let message = Message::new(&[instruction], Some(&payer_pubkey));
+    Transaction::new(&[payer_keypair, base_keypair], message, recent_blockhash)
+}
+
 /// Create and sign new system_instruction::Assign transaction
 pub fn assign(from_keypair: &Keypair, recent_blockhash: Hash, program_id: &Pubkey) -> Transaction {
     let from_pubkey = from_keypair.pubkey();
@@ -46,6 +129,24 @@ pub fn assign(from_keypair: &Keypair, recent_blockhash: Hash, program_id: &Pubke
     Transaction::new(&[from_keypair], message, recent_blockhash)
 }
 
+/// Create and sign new system_instruction::AssignWithSeed transaction
+///
+/// `account` must match `Pubkey::create_with_seed(base, seed, program_id)`.
+pub fn assign_with_seed(
+    payer_keypair: &Keypair,
+    account: &Pubkey,
+    base_keypair: &Keypair,
+    seed: &str,
+    recent_blockhash: Hash,
+    program_id: &Pubkey,
+) -> Transaction {
+    let payer_pubkey = payer_keypair.pubkey();
+    let instruction =
+        system_instruction::assign_with_seed(account, &base_keypair.pubkey(), seed, program_id);
+    let message = Message::new(&[instruction], Some(&payer_pubkey));
+    Transaction::new(&[payer_keypair, base_keypair], message, recent_blockhash)
+}
+
 /// Create and sign new system_instruction::Transfer transaction
 pub fn transfer(
     from_keypair: &Keypair,
@@ -59,6 +160,37 @@ pub fn transfer(
     Transaction::new(&[from_keypair], message, recent_blockhash)
 }
 
+/// Create and sign new system_instruction::TransferWithSeed transaction
+///
+/// `from` must match `Pubkey::create_with_seed(from_base, from_seed, from_owner)`.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_seed(
+    payer_keypair: &Keypair,
+    from: &Pubkey,
+    from_base_keypair: &Keypair,
+    from_seed: String,
+    from_owner: &Pubkey,
+    to: &Pubkey,
+    lamports: u64,
+    recent_blockhash: Hash,
+) -> Transaction {
+    let payer_pubkey = payer_keypair.pubkey();
+    let instruction = system_instruction::transfer_with_seed(
+        from,
+        &from_base_keypair.pubkey(),
+        from_seed,
+        from_owner,
+        to,
+        lamports,
+    );
+    let message = Message::new(&[instruction], Some(&payer_pubkey));
+    Transaction::new(
+        &[payer_keypair, from_base_keypair],
+        message,
+        recent_blockhash,
+    )
+}
+
 /// Create and sign new nonced system_instruction::Transfer transaction
 pub fn nonced_transfer(
     from_keypair: &Keypair,
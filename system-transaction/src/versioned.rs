@@ -0,0 +1,433 @@
+//! Versioned counterparts of the system transaction helpers.
+//!
+//! Each function compiles a v0 message, optionally looking up accounts in the
+//! given [`AddressLookupTableAccount`]s, and signs it. Signers that appear more
+//! than once, such as a payer that is also the base of a derived address, only
+//! sign once.
+
+use {
+    core::fmt,
+    trezoa_hash::Hash,
+    trezoa_instruction::Instruction,
+    trezoa_keypair::Keypair,
+    trezoa_message::{v0, AddressLookupTableAccount, CompileError, VersionedMessage},
+    trezoa_pubkey::Pubkey,
+    trezoa_signer::{Signer, SignerError},
+    trezoa_system_interface::instruction as system_instruction,
+    trezoa_transaction::versioned::VersionedTransaction,
+};
+
+/// Errors produced while building a versioned system transaction.
+#[derive(Debug, PartialEq, Eq)]
+pub enum VersionedTransactionError {
+    /// The message could not be compiled.
+    CompileError(CompileError),
+    /// The message could not be signed.
+    SignerError(SignerError),
+}
+
+impl core::error::Error for VersionedTransactionError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::CompileError(e) => Some(e),
+            Self::SignerError(e) => Some(e),
+        }
+    }
+}
+
+impl fmt::Display for VersionedTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::CompileError(e) => write!(f, "failed to compile message: {e}"),
+            Self::SignerError(e) => write!(f, "failed to sign message: {e}"),
+        }
+    }
+}
+
+impl From<CompileError> for VersionedTransactionError {
+    fn from(e: CompileError) -> Self {
+        Self::CompileError(e)
+    }
+}
+
+impl From<SignerError> for VersionedTransactionError {
+    fn from(e: SignerError) -> Self {
+        Self::SignerError(e)
+    }
+}
+
+type Result<T> = core::result::Result<T, VersionedTransactionError>;
+
+fn compile_and_sign(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+    recent_blockhash: Hash,
+) -> Result<VersionedTransaction> {
+    let message = v0::Message::try_compile(
+        payer,
+        instructions,
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )?;
+    let mut unique_signers: Vec<&Keypair> = Vec::with_capacity(signers.len());
+    for signer in signers {
+        if !unique_signers
+            .iter()
+            .any(|unique| unique.pubkey() == signer.pubkey())
+        {
+            unique_signers.push(signer);
+        }
+    }
+    Ok(VersionedTransaction::try_new(
+        VersionedMessage::V0(message),
+        &unique_signers,
+    )?)
+}
+
+/// Create and sign new SystemInstruction::CreateAccount transaction
+pub fn create_account(
+    from_keypair: &Keypair,
+    to_keypair: &Keypair,
+    recent_blockhash: Hash,
+    lamports: u64,
+    space: u64,
+    program_id: &Pubkey,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let from_pubkey = from_keypair.pubkey();
+    let to_pubkey = to_keypair.pubkey();
+    let instruction =
+        system_instruction::create_account(&from_pubkey, &to_pubkey, lamports, space, program_id);
+    compile_and_sign(
+        &from_pubkey,
+        &[instruction],
+        &[from_keypair, to_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new SystemInstruction::CreateAccountAllowPrefund transaction
+///
+/// The payer only signs for the transfer into the new account when `lamports`
+/// is non-zero; it always signs as the fee payer.
+pub fn create_account_allow_prefund(
+    payer_keypair: &Keypair,
+    new_account_keypair: &Keypair,
+    recent_blockhash: Hash,
+    lamports: u64,
+    space: u64,
+    program_id: &Pubkey,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let payer_pubkey = payer_keypair.pubkey();
+    let new_account_pubkey = new_account_keypair.pubkey();
+    let payer_and_lamports = (lamports > 0).then_some((&payer_pubkey, lamports));
+    let instruction = system_instruction::create_account_allow_prefund(
+        &new_account_pubkey,
+        payer_and_lamports,
+        space,
+        program_id,
+    );
+    compile_and_sign(
+        &payer_pubkey,
+        &[instruction],
+        &[payer_keypair, new_account_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new SystemInstruction::CreateAccountWithSeed transaction
+///
+/// `to` must match `Pubkey::create_with_seed(base, seed, program_id)`.
+#[allow(clippy::too_many_arguments)]
+pub fn create_account_with_seed(
+    from_keypair: &Keypair,
+    to: &Pubkey,
+    base_keypair: &Keypair,
+    seed: &str,
+    recent_blockhash: Hash,
+    lamports: u64,
+    space: u64,
+    program_id: &Pubkey,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let from_pubkey = from_keypair.pubkey();
+    let instruction = system_instruction::create_account_with_seed(
+        &from_pubkey,
+        to,
+        &base_keypair.pubkey(),
+        seed,
+        lamports,
+        space,
+        program_id,
+    );
+    compile_and_sign(
+        &from_pubkey,
+        &[instruction],
+        &[from_keypair, base_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new SystemInstruction::Allocate transaction
+pub fn allocate(
+    payer_keypair: &Keypair,
+    account_keypair: &Keypair,
+    recent_blockhash: Hash,
+    space: u64,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let payer_pubkey = payer_keypair.pubkey();
+    let instruction = system_instruction::allocate(&account_keypair.pubkey(), space);
+    compile_and_sign(
+        &payer_pubkey,
+        &[instruction],
+        &[payer_keypair, account_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new SystemInstruction::AllocateWithSeed transaction
+///
+/// `account` must match `Pubkey::create_with_seed(base, seed, program_id)`.
+#[allow(clippy::too_many_arguments)]
+pub fn allocate_with_seed(
+    payer_keypair: &Keypair,
+    account: &Pubkey,
+    base_keypair: &Keypair,
+    seed: &str,
+    recent_blockhash: Hash,
+    space: u64,
+    program_id: &Pubkey,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let payer_pubkey = payer_keypair.pubkey();
+    let instruction = system_instruction::allocate_with_seed(
+        account,
+        &base_keypair.pubkey(),
+        seed,
+        space,
+        program_id,
+    );
+    compile_and_sign(
+        &payer_pubkey,
+        &[instruction],
+        &[payer_keypair, base_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new system_instruction::Assign transaction
+pub fn assign(
+    from_keypair: &Keypair,
+    recent_blockhash: Hash,
+    program_id: &Pubkey,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let from_pubkey = from_keypair.pubkey();
+    let instruction = system_instruction::assign(&from_pubkey, program_id);
+    compile_and_sign(
+        &from_pubkey,
+        &[instruction],
+        &[from_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new system_instruction::AssignWithSeed transaction
+///
+/// `account` must match `Pubkey::create_with_seed(base, seed, program_id)`.
+pub fn assign_with_seed(
+    payer_keypair: &Keypair,
+    account: &Pubkey,
+    base_keypair: &Keypair,
+    seed: &str,
+    recent_blockhash: Hash,
+    program_id: &Pubkey,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let payer_pubkey = payer_keypair.pubkey();
+    let instruction =
+        system_instruction::assign_with_seed(account, &base_keypair.pubkey(), seed, program_id);
+    compile_and_sign(
+        &payer_pubkey,
+        &[instruction],
+        &[payer_keypair, base_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new system_instruction::Transfer transaction
+pub fn transfer(
+    from_keypair: &Keypair,
+    to: &Pubkey,
+    lamports: u64,
+    recent_blockhash: Hash,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let from_pubkey = from_keypair.pubkey();
+    let instruction = system_instruction::transfer(&from_pubkey, to, lamports);
+    compile_and_sign(
+        &from_pubkey,
+        &[instruction],
+        &[from_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new system_instruction::TransferWithSeed transaction
+///
+/// `from` must match `Pubkey::create_with_seed(from_base, from_seed, from_owner)`.
+#[allow(clippy::too_many_arguments)]
+pub fn transfer_with_seed(
+    payer_keypair: &Keypair,
+    from: &Pubkey,
+    from_base_keypair: &Keypair,
+    from_seed: String,
+    from_owner: &Pubkey,
+    to: &Pubkey,
+    lamports: u64,
+    recent_blockhash: Hash,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let payer_pubkey = payer_keypair.pubkey();
+    let instruction = system_instruction::transfer_with_seed(
+        from,
+        &from_base_keypair.pubkey(),
+        from_seed,
+        from_owner,
+        to,
+        lamports,
+    );
+    compile_and_sign(
+        &payer_pubkey,
+        &[instruction],
+        &[payer_keypair, from_base_keypair],
+        address_lookup_table_accounts,
+        recent_blockhash,
+    )
+}
+
+/// Create and sign new nonced system_instruction::Transfer transaction
+///
+/// The `AdvanceNonceAccount` instruction is placed first, as the runtime
+/// requires of durable nonce transactions.
+pub fn nonced_transfer(
+    from_keypair: &Keypair,
+    to: &Pubkey,
+    lamports: u64,
+    nonce_account: &Pubkey,
+    nonce_authority: &Keypair,
+    nonce_hash: Hash,
+    address_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<VersionedTransaction> {
+    let from_pubkey = from_keypair.pubkey();
+    let instructions = [
+        system_instruction::advance_nonce_account(nonce_account, &nonce_authority.pubkey()),
+        system_instruction::transfer(&from_pubkey, to, lamports),
+    ];
+    compile_and_sign(
+        &from_pubkey,
+        &instructions,
+        &[from_keypair, nonce_authority],
+        address_lookup_table_accounts,
+        nonce_hash,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transfer_with_lookup_table() {
+        let from_keypair = Keypair::new();
+        let to = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![to],
+        };
+        let transaction = transfer(&from_keypair, &to, 42, Hash::default(), &[table]).unwrap();
+        let VersionedMessage::V0(message) = &transaction.message else {
+            panic!("expected a v0 message");
+        };
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert!(!message.account_keys.contains(&to));
+        assert!(transaction
+            .verify_with_results()
+            .into_iter()
+            .all(|verified| verified));
+    }
+
+    #[test]
+    fn test_nonced_transfer_advances_nonce_first() {
+        let from_keypair = Keypair::new();
+        let nonce_authority = Keypair::new();
+        let transaction = nonced_transfer(
+            &from_keypair,
+            &Pubkey::new_unique(),
+            42,
+            &Pubkey::new_unique(),
+            &nonce_authority,
+            Hash::new_unique(),
+            &[],
+        )
+        .unwrap();
+        assert!(transaction.uses_durable_nonce());
+        assert_eq!(transaction.signatures.len(), 2);
+    }
+
+    #[test]
+    fn test_duplicate_signers_sign_once() {
+        let payer_keypair = Keypair::new();
+        let owner = Pubkey::new_unique();
+        let seed = "seed";
+        let account = Pubkey::create_with_seed(&payer_keypair.pubkey(), seed, &owner).unwrap();
+        let transaction = create_account_with_seed(
+            &payer_keypair,
+            &account,
+            &payer_keypair,
+            seed,
+            Hash::default(),
+            1,
+            0,
+            &owner,
+            &[],
+        )
+        .unwrap();
+        assert_eq!(transaction.signatures.len(), 1);
+        assert!(transaction
+            .verify_with_results()
+            .into_iter()
+            .all(|verified| verified));
+    }
+
+    #[test]
+    fn test_missing_signer() {
+        let payer_keypair = Keypair::new();
+        let mut allocate_ix = system_instruction::allocate(&Pubkey::new_unique(), 0);
+        allocate_ix.accounts[0].is_signer = true;
+        assert_eq!(
+            compile_and_sign(
+                &payer_keypair.pubkey(),
+                &[allocate_ix],
+                &[&payer_keypair],
+                &[],
+                Hash::default(),
+            ),
+            Err(VersionedTransactionError::SignerError(
+                SignerError::NotEnoughSigners
+            ))
+        );
+    }
+}
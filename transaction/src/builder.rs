@@ -0,0 +1,323 @@
+//! A builder for transaction messages that manages compute-budget instructions.
+//!
+//! [`TransactionBuilder`] collects the instructions of a transaction together
+//! with its compute budget settings and compiles them into a legacy or v0
+//! message. Any [`ComputeBudgetInstruction`]s already present in the supplied
+//! instructions are deduplicated, and replaced by the builder's own settings
+//! where those are given, so that the resulting message never carries more than
+//! one instruction of each kind.
+//!
+//! The compute-budget instructions are placed at the front of the message, in
+//! the order: unit limit, unit price, loaded accounts data size limit, heap
+//! frame. If the first supplied instruction advances a durable nonce it stays
+//! in the first position, which the runtime requires of nonced transactions.
+
+use {
+    trezoa_address::Address,
+    trezoa_compute_budget_interface::ComputeBudgetInstruction,
+    trezoa_hash::Hash,
+    trezoa_instruction::Instruction,
+    trezoa_message::{
+        inline_nonce::is_advance_nonce_instruction_data, v0, AddressLookupTableAccount,
+        CompileError, Message,
+    },
+    trezoa_sdk_ids::system_program,
+};
+
+/// Builds transaction messages with deduplicated compute-budget instructions.
+///
+/// # Examples
+///
+/// ```
+/// use {
+///     trezoa_hash::Hash,
+///     trezoa_keypair::Keypair,
+///     trezoa_message::VersionedMessage,
+///     trezoa_pubkey::Pubkey,
+///     trezoa_signer::Signer,
+///     trezoa_system_interface::instruction::transfer,
+///     trezoa_transaction::{builder::TransactionBuilder, versioned::VersionedTransaction},
+/// };
+///
+/// let payer = Keypair::new();
+/// let recipient = Pubkey::new_unique();
+/// let message = TransactionBuilder::new(payer.pubkey())
+///     .instruction(transfer(&payer.pubkey(), &recipient, 42))
+///     .compute_unit_limit(450)
+///     .compute_unit_price(1_000)
+///     .v0_message(Hash::default())?;
+///
+/// // limit, price, transfer
+/// assert_eq!(message.instructions.len(), 3);
+/// let transaction = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer])?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionBuilder {
+    payer: Address,
+    instructions: Vec<Instruction>,
+    compute_unit_limit: Option<u32>,
+    compute_unit_price: Option<u64>,
+    loaded_accounts_data_size_limit: Option<u32>,
+    address_lookup_table_accounts: Vec<AddressLookupTableAccount>,
+}
+
+impl TransactionBuilder {
+    /// Creates a builder for a transaction whose fees are paid by `payer`.
+    pub fn new(payer: Address) -> Self {
+        Self {
+            payer,
+            ..Self::default()
+        }
+    }
+
+    /// Appends an instruction.
+    pub fn instruction(mut self, instruction: Instruction) -> Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Appends a sequence of instructions.
+    pub fn instructions(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    /// Sets the compute unit limit, replacing any
+    /// [`ComputeBudgetInstruction::SetComputeUnitLimit`] in the instructions.
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_unit_limit = Some(units);
+        self
+    }
+
+    /// Sets the compute unit price in micro-lamports, replacing any
+    /// [`ComputeBudgetInstruction::SetComputeUnitPrice`] in the instructions.
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Sets the loaded accounts data size limit in bytes, replacing any
+    /// [`ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit`] in the
+    /// instructions.
+    pub fn loaded_accounts_data_size_limit(mut self, bytes: u32) -> Self {
+        self.loaded_accounts_data_size_limit = Some(bytes);
+        self
+    }
+
+    /// Sets the address lookup tables used when compiling a v0 message.
+    pub fn address_lookup_table_accounts(
+        mut self,
+        address_lookup_table_accounts: impl IntoIterator<Item = AddressLookupTableAccount>,
+    ) -> Self {
+        self.address_lookup_table_accounts = address_lookup_table_accounts.into_iter().collect();
+        self
+    }
+
+    /// Returns the fee payer.
+    pub fn payer(&self) -> &Address {
+        &self.payer
+    }
+
+    /// Returns the final list of instructions, with the compute-budget
+    /// instructions deduplicated and moved to the front.
+    pub fn build_instructions(&self) -> Vec<Instruction> {
+        let mut compute_unit_limit = None;
+        let mut compute_unit_price = None;
+        let mut loaded_accounts_data_size_limit = None;
+        let mut heap_frame = None;
+        let mut advance_nonce = None;
+        let mut other_instructions = Vec::with_capacity(self.instructions.len());
+
+        for (index, instruction) in self.instructions.iter().enumerate() {
+            if index == 0 && is_advance_nonce_instruction(instruction) {
+                advance_nonce = Some(instruction.clone());
+                continue;
+            }
+            if !trezoa_compute_budget_interface::check_id(&instruction.program_id) {
+                other_instructions.push(instruction.clone());
+                continue;
+            }
+            // Keep the first instruction of each kind, the runtime rejects
+            // transactions with duplicates.
+            match ComputeBudgetInstruction::from_instruction_data(&instruction.data) {
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                    compute_unit_limit.get_or_insert(units);
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(micro_lamports)) => {
+                    compute_unit_price.get_or_insert(micro_lamports);
+                }
+                Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => {
+                    loaded_accounts_data_size_limit.get_or_insert(bytes);
+                }
+                Some(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                    heap_frame.get_or_insert(bytes);
+                }
+                Some(ComputeBudgetInstruction::Unused) | None => {
+                    other_instructions.push(instruction.clone());
+                }
+            }
+        }
+
+        let compute_unit_limit = self.compute_unit_limit.or(compute_unit_limit);
+        let compute_unit_price = self.compute_unit_price.or(compute_unit_price);
+        let loaded_accounts_data_size_limit = self
+            .loaded_accounts_data_size_limit
+            .or(loaded_accounts_data_size_limit);
+
+        advance_nonce
+            .into_iter()
+            .chain(compute_unit_limit.map(ComputeBudgetInstruction::set_compute_unit_limit))
+            .chain(compute_unit_price.map(ComputeBudgetInstruction::set_compute_unit_price))
+            .chain(
+                loaded_accounts_data_size_limit
+                    .map(ComputeBudgetInstruction::set_loaded_accounts_data_size_limit),
+            )
+            .chain(heap_frame.map(ComputeBudgetInstruction::request_heap_frame))
+            .chain(other_instructions)
+            .collect()
+    }
+
+    /// Compiles a legacy message. Address lookup tables are ignored.
+    pub fn legacy_message(&self, recent_blockhash: Hash) -> Message {
+        Message::new_with_blockhash(
+            &self.build_instructions(),
+            Some(&self.payer),
+            &recent_blockhash,
+        )
+    }
+
+    /// Compiles a v0 message, looking up accounts in the configured address
+    /// lookup tables.
+    pub fn v0_message(&self, recent_blockhash: Hash) -> Result<v0::Message, CompileError> {
+        v0::Message::try_compile(
+            &self.payer,
+            &self.build_instructions(),
+            &self.address_lookup_table_accounts,
+            recent_blockhash,
+        )
+    }
+}
+
+fn is_advance_nonce_instruction(instruction: &Instruction) -> bool {
+    system_program::check_id(&instruction.program_id)
+        && is_advance_nonce_instruction_data(&instruction.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        trezoa_pubkey::Pubkey,
+        trezoa_system_interface::instruction::{advance_nonce_account, transfer},
+    };
+
+    #[test]
+    fn test_compute_budget_instructions_are_prepended() {
+        let payer = Pubkey::new_unique();
+        let transfer_ix = transfer(&payer, &Pubkey::new_unique(), 1);
+        let instructions = TransactionBuilder::new(payer)
+            .instruction(transfer_ix.clone())
+            .compute_unit_limit(1_000)
+            .compute_unit_price(7)
+            .loaded_accounts_data_size_limit(64 * 1024)
+            .build_instructions();
+        assert_eq!(
+            instructions,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(1_000),
+                ComputeBudgetInstruction::set_compute_unit_price(7),
+                ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(64 * 1024),
+                transfer_ix,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_existing_compute_budget_instructions_are_replaced_and_deduped() {
+        let payer = Pubkey::new_unique();
+        let transfer_ix = transfer(&payer, &Pubkey::new_unique(), 1);
+        let instructions = TransactionBuilder::new(payer)
+            .instructions([
+                transfer_ix.clone(),
+                ComputeBudgetInstruction::set_compute_unit_price(1),
+                ComputeBudgetInstruction::set_compute_unit_limit(5),
+                ComputeBudgetInstruction::set_compute_unit_limit(6),
+                ComputeBudgetInstruction::request_heap_frame(64 * 1024),
+            ])
+            .compute_unit_price(2)
+            .build_instructions();
+        assert_eq!(
+            instructions,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(5),
+                ComputeBudgetInstruction::set_compute_unit_price(2),
+                ComputeBudgetInstruction::request_heap_frame(64 * 1024),
+                transfer_ix,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_compute_budget_instruction_is_kept() {
+        let payer = Pubkey::new_unique();
+        let invalid =
+            Instruction::new_with_bytes(trezoa_compute_budget_interface::id(), &[2, 1], vec![]);
+        let instructions = TransactionBuilder::new(payer)
+            .instruction(invalid.clone())
+            .compute_unit_limit(10)
+            .build_instructions();
+        assert_eq!(
+            instructions,
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(10),
+                invalid
+            ]
+        );
+    }
+
+    #[test]
+    fn test_advance_nonce_stays_first() {
+        let payer = Pubkey::new_unique();
+        let advance_ix = advance_nonce_account(&Pubkey::new_unique(), &payer);
+        let transfer_ix = transfer(&payer, &Pubkey::new_unique(), 1);
+        let builder = TransactionBuilder::new(payer)
+            .instructions([advance_ix.clone(), transfer_ix.clone()])
+            .compute_unit_price(3);
+        assert_eq!(
+            builder.build_instructions(),
+            vec![
+                advance_ix,
+                ComputeBudgetInstruction::set_compute_unit_price(3),
+                transfer_ix,
+            ]
+        );
+
+        let message = builder.legacy_message(Hash::default());
+        let transaction = crate::Transaction::new_unsigned(message);
+        assert!(crate::uses_durable_nonce(&transaction).is_some());
+    }
+
+    #[test]
+    fn test_v0_message_uses_lookup_tables() {
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![recipient],
+        };
+        let builder = TransactionBuilder::new(payer)
+            .instruction(transfer(&payer, &recipient, 1))
+            .compute_unit_limit(300)
+            .address_lookup_table_accounts([table.clone()]);
+
+        let message = builder.v0_message(Hash::default()).unwrap();
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert_eq!(message.address_table_lookups[0].account_key, table.key);
+        assert!(!message.account_keys.contains(&recipient));
+        assert_eq!(message.instructions.len(), 2);
+
+        let legacy_message = builder.legacy_message(Hash::default());
+        assert!(legacy_message.account_keys.contains(&recipient));
+    }
+}
@@ -0,0 +1,414 @@
+//! Helpers for the lifecycle of durable nonce transactions.
+//!
+//! A durable nonce transaction uses the value stored in a nonce account in
+//! place of a recent blockhash, and must begin with a
+//! `SystemInstruction::AdvanceNonceAccount` instruction. This makes it
+//! possible to sign a transaction offline and submit it much later, as long
+//! as the nonce has not been advanced in the meantime.
+//!
+//! [`DurableNonceTransaction`] keeps the advance instruction in place, checks
+//! the transaction against the current state of its nonce account, and
+//! re-signs it once the nonce has moved on.
+
+use {
+    crate::{uses_durable_nonce, Transaction},
+    core::fmt,
+    trezoa_address::Address,
+    trezoa_hash::Hash,
+    trezoa_instruction::Instruction,
+    trezoa_message::Message,
+    trezoa_nonce::{
+        state::{Data, State},
+        versions::Versions,
+    },
+    trezoa_signer::{signers::Signers, SignerError},
+};
+
+// Account indexes of `SystemInstruction::AdvanceNonceAccount`.
+const NONCE_ACCOUNT_INDEX: usize = 0;
+const NONCE_AUTHORITY_INDEX: usize = 2;
+
+/// Reasons a transaction cannot be used, or is no longer usable, as a durable
+/// nonce transaction.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DurableNonceError {
+    /// The first instruction is not a well-formed `AdvanceNonceAccount`.
+    MissingAdvanceNonceInstruction,
+    /// The nonce account has not been initialized.
+    UninitializedNonceAccount,
+    /// The nonce account still holds a legacy nonce, which cannot be used
+    /// until it is advanced once.
+    LegacyNonceAccount,
+    /// The nonce authority stored in the account does not match the one in
+    /// the transaction.
+    AuthorityMismatch { expected: Address, actual: Address },
+    /// The nonce has been advanced since the transaction was signed.
+    ExpiredNonce { signed_with: Hash, current: Hash },
+    /// The transaction could not be signed.
+    SignerError(SignerError),
+}
+
+impl core::error::Error for DurableNonceError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::SignerError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DurableNonceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingAdvanceNonceInstruction => {
+                f.write_str("transaction does not begin with an advance nonce instruction")
+            }
+            Self::UninitializedNonceAccount => f.write_str("nonce account is not initialized"),
+            Self::LegacyNonceAccount => f.write_str("nonce account holds a legacy nonce"),
+            Self::AuthorityMismatch { expected, actual } => write!(
+                f,
+                "nonce authority mismatch: account has {expected}, transaction uses {actual}"
+            ),
+            Self::ExpiredNonce {
+                signed_with,
+                current,
+            } => write!(
+                f,
+                "nonce expired: transaction uses {signed_with}, account holds {current}"
+            ),
+            Self::SignerError(e) => write!(f, "signing failed: {e}"),
+        }
+    }
+}
+
+impl From<SignerError> for DurableNonceError {
+    fn from(e: SignerError) -> Self {
+        Self::SignerError(e)
+    }
+}
+
+/// A [`Transaction`] whose first instruction advances a durable nonce.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DurableNonceTransaction {
+    transaction: Transaction,
+    nonce_account: Address,
+    nonce_authority: Address,
+}
+
+impl DurableNonceTransaction {
+    /// Creates an unsigned transaction that advances `nonce_account` before
+    /// running `instructions`, using `nonce` as its recent blockhash.
+    pub fn new_unsigned(
+        instructions: Vec<Instruction>,
+        payer: Option<&Address>,
+        nonce_account: &Address,
+        nonce_authority: &Address,
+        nonce: Hash,
+    ) -> Self {
+        let mut message =
+            Message::new_with_nonce(instructions, payer, nonce_account, nonce_authority);
+        message.recent_blockhash = nonce;
+        Self {
+            transaction: Transaction::new_unsigned(message),
+            nonce_account: *nonce_account,
+            nonce_authority: *nonce_authority,
+        }
+    }
+
+    /// Creates a transaction like [`DurableNonceTransaction::new_unsigned`],
+    /// taking the nonce from the fetched state of the nonce account.
+    pub fn new_from_nonce_account(
+        instructions: Vec<Instruction>,
+        payer: Option<&Address>,
+        nonce_account: &Address,
+        nonce_authority: &Address,
+        versions: &Versions,
+    ) -> Result<Self, DurableNonceError> {
+        let data = nonce_data(versions, nonce_authority)?;
+        Ok(Self::new_unsigned(
+            instructions,
+            payer,
+            nonce_account,
+            nonce_authority,
+            data.blockhash(),
+        ))
+    }
+
+    /// Returns the address of the nonce account.
+    pub fn nonce_account(&self) -> &Address {
+        &self.nonce_account
+    }
+
+    /// Returns the address of the nonce authority.
+    pub fn nonce_authority(&self) -> &Address {
+        &self.nonce_authority
+    }
+
+    /// Returns the nonce the transaction is built with.
+    pub fn nonce(&self) -> &Hash {
+        &self.transaction.message.recent_blockhash
+    }
+
+    /// Returns the underlying transaction.
+    pub fn transaction(&self) -> &Transaction {
+        &self.transaction
+    }
+
+    /// Consumes `self`, returning the underlying transaction.
+    pub fn into_transaction(self) -> Transaction {
+        self.transaction
+    }
+
+    /// Checks the transaction against the fetched state of its nonce account.
+    ///
+    /// Returns the nonce data when the transaction can still be submitted,
+    /// and [`DurableNonceError::ExpiredNonce`] when the nonce has been
+    /// advanced since the transaction was built.
+    pub fn verify_nonce<'a>(&self, versions: &'a Versions) -> Result<&'a Data, DurableNonceError> {
+        let data = nonce_data(versions, &self.nonce_authority)?;
+        if versions.verify_recent_blockhash(self.nonce()).is_none() {
+            return Err(DurableNonceError::ExpiredNonce {
+                signed_with: *self.nonce(),
+                current: data.blockhash(),
+            });
+        }
+        Ok(data)
+    }
+
+    /// Returns `true` if the nonce account has been advanced past the nonce
+    /// the transaction is built with.
+    pub fn is_expired(&self, versions: &Versions) -> bool {
+        matches!(
+            self.verify_nonce(versions),
+            Err(DurableNonceError::ExpiredNonce { .. })
+        )
+    }
+
+    /// Signs the transaction with all of its required signers.
+    pub fn try_sign<T: Signers + ?Sized>(&mut self, keypairs: &T) -> Result<(), DurableNonceError> {
+        let nonce = *self.nonce();
+        Ok(self.transaction.try_sign(keypairs, nonce)?)
+    }
+
+    /// Signs the transaction with a subset of its required signers.
+    pub fn try_partial_sign<T: Signers + ?Sized>(
+        &mut self,
+        keypairs: &T,
+    ) -> Result<(), DurableNonceError> {
+        let nonce = *self.nonce();
+        Ok(self.transaction.try_partial_sign(keypairs, nonce)?)
+    }
+
+    /// Rebuilds the transaction on the current nonce of the account and signs
+    /// it again.
+    ///
+    /// Prior signatures are cleared if the nonce has been advanced. Returns
+    /// `true` if the nonce changed.
+    pub fn refresh<T: Signers + ?Sized>(
+        &mut self,
+        versions: &Versions,
+        keypairs: &T,
+    ) -> Result<bool, DurableNonceError> {
+        let nonce = nonce_data(versions, &self.nonce_authority)?.blockhash();
+        let advanced = nonce != *self.nonce();
+        self.transaction.try_sign(keypairs, nonce)?;
+        Ok(advanced)
+    }
+}
+
+impl TryFrom<Transaction> for DurableNonceTransaction {
+    type Error = DurableNonceError;
+
+    fn try_from(transaction: Transaction) -> Result<Self, Self::Error> {
+        let instruction = uses_durable_nonce(&transaction)
+            .ok_or(DurableNonceError::MissingAdvanceNonceInstruction)?;
+        let account_key = |index: usize| {
+            instruction
+                .accounts
+                .get(index)
+                .and_then(|key_index| transaction.message.account_keys.get(*key_index as usize))
+                .copied()
+                .ok_or(DurableNonceError::MissingAdvanceNonceInstruction)
+        };
+        let nonce_account = account_key(NONCE_ACCOUNT_INDEX)?;
+        let nonce_authority = account_key(NONCE_AUTHORITY_INDEX)?;
+        Ok(Self {
+            transaction,
+            nonce_account,
+            nonce_authority,
+        })
+    }
+}
+
+impl From<DurableNonceTransaction> for Transaction {
+    fn from(transaction: DurableNonceTransaction) -> Self {
+        transaction.transaction
+    }
+}
+
+fn nonce_data<'a>(
+    versions: &'a Versions,
+    nonce_authority: &Address,
+) -> Result<&'a Data, DurableNonceError> {
+    let Versions::Current(state) = versions else {
+        return Err(DurableNonceError::LegacyNonceAccount);
+    };
+    let State::Initialized(data) = state.as_ref() else {
+        return Err(DurableNonceError::UninitializedNonceAccount);
+    };
+    if data.authority != *nonce_authority {
+        return Err(DurableNonceError::AuthorityMismatch {
+            expected: data.authority,
+            actual: *nonce_authority,
+        });
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        trezoa_keypair::Keypair,
+        trezoa_nonce::state::DurableNonce,
+        trezoa_signer::Signer,
+        trezoa_system_interface::instruction::{transfer, withdraw_nonce_account},
+    };
+
+    fn nonce_versions(authority: &Address, blockhash: &Hash) -> Versions {
+        let durable_nonce = DurableNonce::from_blockhash(blockhash);
+        Versions::new(State::Initialized(Data::new(
+            *authority,
+            durable_nonce,
+            5000,
+        )))
+    }
+
+    #[test]
+    fn test_advance_nonce_instruction_is_first() {
+        let payer = Keypair::new();
+        let nonce_account = Address::new_unique();
+        let transaction = DurableNonceTransaction::new_unsigned(
+            vec![transfer(&payer.pubkey(), &Address::new_unique(), 1)],
+            Some(&payer.pubkey()),
+            &nonce_account,
+            &payer.pubkey(),
+            Hash::new_unique(),
+        );
+        assert!(uses_durable_nonce(transaction.transaction()).is_some());
+
+        let round_trip = DurableNonceTransaction::try_from(transaction.clone().into_transaction());
+        assert_eq!(round_trip, Ok(transaction));
+    }
+
+    #[test]
+    fn test_try_from_without_advance_nonce() {
+        let payer = Address::new_unique();
+        let message = Message::new(&[transfer(&payer, &Address::new_unique(), 1)], Some(&payer));
+        assert_eq!(
+            DurableNonceTransaction::try_from(Transaction::new_unsigned(message)),
+            Err(DurableNonceError::MissingAdvanceNonceInstruction)
+        );
+
+        // Withdrawing from a nonce account is not an advance
+        let message = Message::new(
+            &[withdraw_nonce_account(
+                &Address::new_unique(),
+                &payer,
+                &payer,
+                1,
+            )],
+            Some(&payer),
+        );
+        assert_eq!(
+            DurableNonceTransaction::try_from(Transaction::new_unsigned(message)),
+            Err(DurableNonceError::MissingAdvanceNonceInstruction)
+        );
+    }
+
+    #[test]
+    fn test_verify_nonce() {
+        let payer = Keypair::new();
+        let nonce_account = Address::new_unique();
+        let versions = nonce_versions(&payer.pubkey(), &Hash::new_unique());
+        let transaction = DurableNonceTransaction::new_from_nonce_account(
+            vec![transfer(&payer.pubkey(), &Address::new_unique(), 1)],
+            Some(&payer.pubkey()),
+            &nonce_account,
+            &payer.pubkey(),
+            &versions,
+        )
+        .unwrap();
+        assert!(transaction.verify_nonce(&versions).is_ok());
+        assert!(!transaction.is_expired(&versions));
+
+        let blockhash = Hash::new_unique();
+        let advanced = nonce_versions(&payer.pubkey(), &blockhash);
+        assert_eq!(
+            transaction.verify_nonce(&advanced),
+            Err(DurableNonceError::ExpiredNonce {
+                signed_with: *transaction.nonce(),
+                current: *DurableNonce::from_blockhash(&blockhash).as_hash(),
+            })
+        );
+        assert!(transaction.is_expired(&advanced));
+
+        let other_authority = Address::new_unique();
+        assert_eq!(
+            transaction.verify_nonce(&nonce_versions(&other_authority, &Hash::new_unique())),
+            Err(DurableNonceError::AuthorityMismatch {
+                expected: other_authority,
+                actual: payer.pubkey(),
+            })
+        );
+        assert_eq!(
+            transaction.verify_nonce(&Versions::new(State::Uninitialized)),
+            Err(DurableNonceError::UninitializedNonceAccount)
+        );
+        assert_eq!(
+            transaction.verify_nonce(&Versions::Legacy(Box::new(State::Uninitialized))),
+            Err(DurableNonceError::LegacyNonceAccount)
+        );
+    }
+
+    #[test]
+    fn test_refresh_resigns_after_advance() {
+        let payer = Keypair::new();
+        let nonce_authority = Keypair::new();
+        let versions = nonce_versions(&nonce_authority.pubkey(), &Hash::new_unique());
+        let mut transaction = DurableNonceTransaction::new_from_nonce_account(
+            vec![transfer(&payer.pubkey(), &Address::new_unique(), 1)],
+            Some(&payer.pubkey()),
+            &Address::new_unique(),
+            &nonce_authority.pubkey(),
+            &versions,
+        )
+        .unwrap();
+        transaction.try_sign(&[&payer, &nonce_authority]).unwrap();
+        let signatures = transaction.transaction().signatures.clone();
+
+        assert_eq!(
+            transaction.refresh(&versions, &[&payer, &nonce_authority]),
+            Ok(false)
+        );
+        assert_eq!(transaction.transaction().signatures, signatures);
+
+        let advanced = nonce_versions(&nonce_authority.pubkey(), &Hash::new_unique());
+        assert_eq!(
+            transaction.refresh(&advanced, &[&payer, &nonce_authority]),
+            Ok(true)
+        );
+        assert_ne!(transaction.transaction().signatures, signatures);
+        assert!(transaction.verify_nonce(&advanced).is_ok());
+        assert!(transaction.transaction().verify().is_ok());
+
+        // A missing signer leaves the transaction unusable
+        let readvanced = nonce_versions(&nonce_authority.pubkey(), &Hash::new_unique());
+        assert_eq!(
+            transaction.refresh(&readvanced, &[&payer]),
+            Err(DurableNonceError::SignerError(
+                SignerError::NotEnoughSigners
+            ))
+        );
+    }
+}
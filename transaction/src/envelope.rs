@@ -0,0 +1,505 @@
+//! An envelope for passing partially signed transactions between co-signers.
+//!
+//! A [`SigningEnvelope`] holds a [`VersionedTransaction`] together with the
+//! list of its required signers and which of them have signed. Each co-signer
+//! loads the envelope, adds their signature, and passes it on, either as JSON
+//! or as base64 encoded bincode. Envelopes signed in parallel can be combined
+//! with [`SigningEnvelope::merge`].
+//!
+//! Every signature that enters an envelope from outside is checked against
+//! the message with a [`Presigner`], so a corrupted or tampered envelope is
+//! rejected before it reaches the network.
+//!
+//! The JSON form keeps the transaction as base64 encoded bincode and lists
+//! the signers by their base58 address:
+//!
+//! ```json
+//! {
+//!   "transaction": "AgAAAA...",
+//!   "signers": [
+//!     { "pubkey": "7o36UsWR1JQLpZ9PE2gn9L4SQ69CNNiWAXd4Jt7rqz9Z", "signed": true },
+//!     { "pubkey": "FhVcYNEe58SMtxpZGnTu2kpYJrTu2vwCZDGpPLqbd2yG", "signed": false }
+//!   ]
+//! }
+//! ```
+
+use {
+    crate::versioned::VersionedTransaction,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    core::fmt,
+    serde_derive::{Deserialize, Serialize},
+    trezoa_address::Address,
+    trezoa_presigner::Presigner,
+    trezoa_signature::Signature,
+    trezoa_signer::{signers::Signers, Signer, SignerError},
+    std::borrow::Cow,
+};
+
+/// Errors produced while building, merging or decoding a [`SigningEnvelope`].
+#[derive(Debug)]
+pub enum EnvelopeError {
+    /// The envelopes being merged do not carry the same message.
+    MessageMismatch,
+    /// The signer list does not match the required signers of the message.
+    SignerListMismatch,
+    /// The address is not a required signer of the message.
+    UnknownSigner(Address),
+    /// The signature of the address does not verify against the message.
+    InvalidSignature(Address),
+    /// The transaction is still missing signatures from these addresses.
+    MissingSignatures(Vec<Address>),
+    /// A signer failed to sign the message.
+    SignerError(SignerError),
+    /// The envelope could not be encoded or decoded as bincode.
+    Bincode(bincode::Error),
+    /// The envelope is not valid base64.
+    Base64(base64::DecodeError),
+    /// The envelope is not valid JSON.
+    Json(serde_json::Error),
+    /// A signer address in the JSON envelope is not valid base58.
+    InvalidAddress(String),
+}
+
+impl core::error::Error for EnvelopeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Self::SignerError(e) => Some(e),
+            Self::Bincode(e) => Some(e),
+            Self::Base64(e) => Some(e),
+            Self::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MessageMismatch => f.write_str("envelopes carry different messages"),
+            Self::SignerListMismatch => {
+                f.write_str("signer list does not match the message's required signers")
+            }
+            Self::UnknownSigner(address) => write!(f, "{address} is not a required signer"),
+            Self::InvalidSignature(address) => write!(f, "invalid signature from {address}"),
+            Self::MissingSignatures(addresses) => {
+                write!(f, "missing signatures from {} signer(s)", addresses.len())
+            }
+            Self::SignerError(e) => write!(f, "signing failed: {e}"),
+            Self::Bincode(e) => write!(f, "bincode error: {e}"),
+            Self::Base64(e) => write!(f, "base64 error: {e}"),
+            Self::Json(e) => write!(f, "JSON error: {e}"),
+            Self::InvalidAddress(address) => write!(f, "invalid signer address: {address}"),
+        }
+    }
+}
+
+impl From<SignerError> for EnvelopeError {
+    fn from(e: SignerError) -> Self {
+        Self::SignerError(e)
+    }
+}
+
+impl From<bincode::Error> for EnvelopeError {
+    fn from(e: bincode::Error) -> Self {
+        Self::Bincode(e)
+    }
+}
+
+impl From<base64::DecodeError> for EnvelopeError {
+    fn from(e: base64::DecodeError) -> Self {
+        Self::Base64(e)
+    }
+}
+
+impl From<serde_json::Error> for EnvelopeError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+/// A required signer of the enveloped transaction.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Copy)]
+pub struct EnvelopeSigner {
+    pub pubkey: Address,
+    pub signed: bool,
+}
+
+/// A partially signed transaction and the state of its required signers.
+///
+/// An envelope is only decoded through [`SigningEnvelope::from_base64`] and
+/// [`SigningEnvelope::from_json`], which verify it, so it does not implement
+/// the serde traits.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SigningEnvelope {
+    transaction: VersionedTransaction,
+    signers: Vec<EnvelopeSigner>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BincodeEnvelope<'a> {
+    transaction: Cow<'a, VersionedTransaction>,
+    signers: Cow<'a, [EnvelopeSigner]>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonEnvelope {
+    transaction: String,
+    signers: Vec<JsonEnvelopeSigner>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonEnvelopeSigner {
+    pubkey: String,
+    signed: bool,
+}
+
+impl SigningEnvelope {
+    /// Wraps a transaction, keeping any signatures it already carries.
+    ///
+    /// Existing signatures are verified, and missing ones are filled with
+    /// [`Signature::default`].
+    pub fn new(mut transaction: VersionedTransaction) -> Result<Self, EnvelopeError> {
+        let num_required_signatures = transaction.message.header().num_required_signatures;
+        transaction
+            .signatures
+            .resize(num_required_signatures as usize, Signature::default());
+        let signers = transaction
+            .message
+            .static_account_keys()
+            .iter()
+            .zip(&transaction.signatures)
+            .map(|(pubkey, signature)| EnvelopeSigner {
+                pubkey: *pubkey,
+                signed: *signature != Signature::default(),
+            })
+            .collect::<Vec<_>>();
+        if signers.len() != num_required_signatures as usize {
+            return Err(EnvelopeError::SignerListMismatch);
+        }
+        let envelope = Self {
+            transaction,
+            signers,
+        };
+        envelope.verify()?;
+        Ok(envelope)
+    }
+
+    /// Returns the enveloped transaction.
+    pub fn transaction(&self) -> &VersionedTransaction {
+        &self.transaction
+    }
+
+    /// Returns the required signers, in signature order.
+    pub fn signers(&self) -> &[EnvelopeSigner] {
+        &self.signers
+    }
+
+    /// Returns the required signers that have not signed yet.
+    pub fn missing_signers(&self) -> Vec<Address> {
+        self.signers
+            .iter()
+            .filter(|signer| !signer.signed)
+            .map(|signer| signer.pubkey)
+            .collect()
+    }
+
+    /// Returns `true` once every required signer has signed.
+    pub fn is_fully_signed(&self) -> bool {
+        self.signers.iter().all(|signer| signer.signed)
+    }
+
+    /// Signs the message with `keypairs`, each of which must be a required
+    /// signer.
+    pub fn sign<T: Signers + ?Sized>(&mut self, keypairs: &T) -> Result<(), EnvelopeError> {
+        let pubkeys = keypairs.try_pubkeys()?;
+        let positions = pubkeys
+            .iter()
+            .map(|pubkey| {
+                self.position(pubkey)
+                    .ok_or(EnvelopeError::UnknownSigner(*pubkey))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let signatures = keypairs.try_sign_message(&self.transaction.message.serialize())?;
+        for (position, signature) in positions.into_iter().zip(signatures) {
+            self.set_signature(position, signature);
+        }
+        Ok(())
+    }
+
+    /// Adds a signature produced elsewhere, verifying it against the message.
+    pub fn add_signature(
+        &mut self,
+        pubkey: &Address,
+        signature: &Signature,
+    ) -> Result<(), EnvelopeError> {
+        let position = self
+            .position(pubkey)
+            .ok_or(EnvelopeError::UnknownSigner(*pubkey))?;
+        let message_data = self.transaction.message.serialize();
+        Presigner::new(pubkey, signature)
+            .try_sign_message(&message_data)
+            .map_err(|_| EnvelopeError::InvalidSignature(*pubkey))?;
+        self.set_signature(position, *signature);
+        Ok(())
+    }
+
+    /// Copies the signatures of `other` that are missing from `self`.
+    ///
+    /// Both envelopes must carry the same message. Every copied signature is
+    /// verified, and no signature is copied if any of them fails.
+    pub fn merge(&mut self, other: &Self) -> Result<(), EnvelopeError> {
+        if self.transaction.message != other.transaction.message {
+            return Err(EnvelopeError::MessageMismatch);
+        }
+        let message_data = self.transaction.message.serialize();
+        let mut new_signatures = vec![];
+        for (position, (signer, signature)) in other
+            .signers
+            .iter()
+            .zip(&other.transaction.signatures)
+            .enumerate()
+        {
+            if !signer.signed || self.signers[position].signed {
+                continue;
+            }
+            Presigner::new(&signer.pubkey, signature)
+                .try_sign_message(&message_data)
+                .map_err(|_| EnvelopeError::InvalidSignature(signer.pubkey))?;
+            new_signatures.push((position, *signature));
+        }
+        for (position, signature) in new_signatures {
+            self.set_signature(position, signature);
+        }
+        Ok(())
+    }
+
+    /// Checks that the signer list matches the message and that every
+    /// signature present verifies against it.
+    ///
+    /// A partially signed envelope verifies; use
+    /// [`SigningEnvelope::into_transaction`] to require all signatures.
+    pub fn verify(&self) -> Result<(), EnvelopeError> {
+        let message = &self.transaction.message;
+        let num_required_signatures = message.header().num_required_signatures as usize;
+        if self.signers.len() != num_required_signatures
+            || self.transaction.signatures.len() != num_required_signatures
+            || self
+                .signers
+                .iter()
+                .zip(message.static_account_keys())
+                .any(|(signer, pubkey)| signer.pubkey != *pubkey)
+        {
+            return Err(EnvelopeError::SignerListMismatch);
+        }
+        let message_data = message.serialize();
+        for (signer, signature) in self.signers.iter().zip(&self.transaction.signatures) {
+            if signer.signed != (*signature != Signature::default()) {
+                return Err(EnvelopeError::SignerListMismatch);
+            }
+            if signer.signed
+                && Presigner::new(&signer.pubkey, signature)
+                    .try_sign_message(&message_data)
+                    .is_err()
+            {
+                return Err(EnvelopeError::InvalidSignature(signer.pubkey));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the transaction once every required signer has signed.
+    pub fn into_transaction(self) -> Result<VersionedTransaction, EnvelopeError> {
+        let missing_signers = self.missing_signers();
+        if !missing_signers.is_empty() {
+            return Err(EnvelopeError::MissingSignatures(missing_signers));
+        }
+        Ok(self.transaction)
+    }
+
+    /// Encodes the envelope as base64 encoded bincode.
+    pub fn to_base64(&self) -> Result<String, EnvelopeError> {
+        let envelope = BincodeEnvelope {
+            transaction: Cow::Borrowed(&self.transaction),
+            signers: Cow::Borrowed(&self.signers),
+        };
+        Ok(BASE64_STANDARD.encode(bincode::serialize(&envelope)?))
+    }
+
+    /// Decodes and verifies an envelope encoded with
+    /// [`SigningEnvelope::to_base64`].
+    pub fn from_base64(encoded: &str) -> Result<Self, EnvelopeError> {
+        let envelope: BincodeEnvelope = bincode::deserialize(&BASE64_STANDARD.decode(encoded)?)?;
+        let envelope = Self {
+            transaction: envelope.transaction.into_owned(),
+            signers: envelope.signers.into_owned(),
+        };
+        envelope.verify()?;
+        Ok(envelope)
+    }
+
+    /// Encodes the envelope as JSON.
+    pub fn to_json(&self) -> Result<String, EnvelopeError> {
+        let envelope = JsonEnvelope {
+            transaction: BASE64_STANDARD.encode(bincode::serialize(&self.transaction)?),
+            signers: self
+                .signers
+                .iter()
+                .map(|signer| JsonEnvelopeSigner {
+                    pubkey: signer.pubkey.to_string(),
+                    signed: signer.signed,
+                })
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&envelope)?)
+    }
+
+    /// Decodes and verifies an envelope encoded with
+    /// [`SigningEnvelope::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, EnvelopeError> {
+        let envelope: JsonEnvelope = serde_json::from_str(json)?;
+        let transaction = bincode::deserialize(&BASE64_STANDARD.decode(envelope.transaction)?)?;
+        let signers = envelope
+            .signers
+            .into_iter()
+            .map(|signer| {
+                let pubkey = signer
+                    .pubkey
+                    .parse()
+                    .map_err(|_| EnvelopeError::InvalidAddress(signer.pubkey))?;
+                Ok(EnvelopeSigner {
+                    pubkey,
+                    signed: signer.signed,
+                })
+            })
+            .collect::<Result<_, EnvelopeError>>()?;
+        let envelope = Self {
+            transaction,
+            signers,
+        };
+        envelope.verify()?;
+        Ok(envelope)
+    }
+
+    fn position(&self, pubkey: &Address) -> Option<usize> {
+        self.signers
+            .iter()
+            .position(|signer| signer.pubkey == *pubkey)
+    }
+
+    fn set_signature(&mut self, position: usize, signature: Signature) {
+        self.transaction.signatures[position] = signature;
+        self.signers[position].signed = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        trezoa_hash::Hash,
+        trezoa_keypair::Keypair,
+        trezoa_message::{Message, VersionedMessage},
+        trezoa_system_interface::instruction::transfer,
+    };
+
+    fn unsigned_envelope(payer: &Keypair, co_signer: &Keypair) -> SigningEnvelope {
+        let mut instruction = transfer(&payer.pubkey(), &Address::new_unique(), 1);
+        instruction
+            .accounts
+            .push(trezoa_instruction::AccountMeta::new_readonly(
+                co_signer.pubkey(),
+                true,
+            ));
+        let message =
+            Message::new_with_blockhash(&[instruction], Some(&payer.pubkey()), &Hash::new_unique());
+        SigningEnvelope::new(VersionedTransaction {
+            signatures: vec![],
+            message: VersionedMessage::Legacy(message),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sign_in_turns() {
+        let payer = Keypair::new();
+        let co_signer = Keypair::new();
+        let mut envelope = unsigned_envelope(&payer, &co_signer);
+        assert_eq!(
+            envelope.missing_signers(),
+            vec![payer.pubkey(), co_signer.pubkey()]
+        );
+
+        envelope.sign(&[&payer]).unwrap();
+        let mut envelope = SigningEnvelope::from_json(&envelope.to_json().unwrap()).unwrap();
+        assert_eq!(envelope.missing_signers(), vec![co_signer.pubkey()]);
+        assert!(matches!(
+            envelope.clone().into_transaction(),
+            Err(EnvelopeError::MissingSignatures(missing)) if missing == vec![co_signer.pubkey()]
+        ));
+
+        envelope.sign(&[&co_signer]).unwrap();
+        let envelope = SigningEnvelope::from_base64(&envelope.to_base64().unwrap()).unwrap();
+        assert!(envelope.is_fully_signed());
+        let transaction = envelope.into_transaction().unwrap();
+        assert!(transaction.verify_with_results().into_iter().all(|ok| ok));
+    }
+
+    #[test]
+    fn test_merge() {
+        let payer = Keypair::new();
+        let co_signer = Keypair::new();
+        let envelope = unsigned_envelope(&payer, &co_signer);
+
+        let mut payer_copy = envelope.clone();
+        payer_copy.sign(&[&payer]).unwrap();
+        let mut co_signer_copy = envelope.clone();
+        co_signer_copy.sign(&[&co_signer]).unwrap();
+
+        payer_copy.merge(&co_signer_copy).unwrap();
+        assert!(payer_copy.is_fully_signed());
+        assert!(payer_copy.verify().is_ok());
+
+        let other = unsigned_envelope(&payer, &co_signer);
+        assert!(matches!(
+            payer_copy.merge(&other),
+            Err(EnvelopeError::MessageMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_reject_invalid_signatures() {
+        let payer = Keypair::new();
+        let co_signer = Keypair::new();
+        let mut envelope = unsigned_envelope(&payer, &co_signer);
+
+        let stranger = Keypair::new();
+        assert!(matches!(
+            envelope.sign(&[&stranger]),
+            Err(EnvelopeError::UnknownSigner(pubkey)) if pubkey == stranger.pubkey()
+        ));
+        let bogus = payer.sign_message(b"something else");
+        assert!(matches!(
+            envelope.add_signature(&payer.pubkey(), &bogus),
+            Err(EnvelopeError::InvalidSignature(pubkey)) if pubkey == payer.pubkey()
+        ));
+
+        let mut tampered = envelope.clone();
+        tampered.transaction.signatures[1] = bogus;
+        tampered.signers[1].signed = true;
+        assert!(matches!(
+            SigningEnvelope::from_base64(&tampered.to_base64().unwrap()),
+            Err(EnvelopeError::InvalidSignature(pubkey)) if pubkey == co_signer.pubkey()
+        ));
+        assert!(matches!(
+            envelope.merge(&tampered),
+            Err(EnvelopeError::InvalidSignature(_))
+        ));
+
+        let mut mislabeled = envelope.clone();
+        mislabeled.signers[0].signed = true;
+        assert!(matches!(
+            SigningEnvelope::from_json(&mislabeled.to_json().unwrap()),
+            Err(EnvelopeError::SignerListMismatch)
+        ));
+    }
+}
@@ -135,8 +135,14 @@ use {
     std::result,
 };
 
+pub mod builder;
+#[cfg(feature = "nonce")]
+pub mod durable_nonce;
+#[cfg(feature = "envelope")]
+pub mod envelope;
 pub mod sanitized;
 pub mod simple_vote_transaction_checker;
+pub mod size;
 pub mod versioned;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -1084,6 +1090,36 @@ impl Transaction {
             .iter()
             .all(|signature| *signature != Signature::default())
     }
+
+    /// Returns the serialized size of the transaction broken down by section,
+    /// without serializing it.
+    ///
+    /// Space is counted for every signature the message requires, even if the
+    /// transaction is not signed yet.
+    pub fn size_breakdown(&self) -> size::TransactionSize {
+        let num_signatures = self
+            .signatures
+            .len()
+            .max(usize::from(self.message.header.num_required_signatures));
+        size::TransactionSize::new(num_signatures, self.message.size_breakdown())
+    }
+
+    /// Returns the serialized size of the transaction, without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.size_breakdown().total()
+    }
+
+    /// Returns `true` if the transaction fits in a single packet of
+    /// [`size::PACKET_DATA_SIZE`] bytes.
+    pub fn fits_in_packet(&self) -> bool {
+        self.size_breakdown().fits_in_packet()
+    }
+
+    /// Returns an error with the size breakdown of the transaction if it does
+    /// not fit in a single packet.
+    pub fn check_packet_size(&self) -> result::Result<(), size::TransactionTooLargeError> {
+        self.size_breakdown().check_packet_size()
+    }
 }
 
 /// Returns true if transaction begins with an advance nonce instruction.
@@ -0,0 +1,195 @@
+//! The serialized size of a transaction, broken down by section.
+//!
+//! A transaction is sent to the cluster in a single packet, so its
+//! serialized size must not exceed [`PACKET_DATA_SIZE`]. [`TransactionSize`]
+//! adds the signatures to the [`MessageSize`] reported by the message, so
+//! the transaction is never serialized, which makes it cheap enough to check
+//! while building batches of instructions.
+
+pub use trezoa_message::PACKET_DATA_SIZE;
+use {
+    core::fmt,
+    trezoa_message::size::{signatures_size, MessageSize},
+};
+
+/// The serialized size of a transaction, broken down by section.
+///
+/// Each section includes its short-vec length prefix.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TransactionSize {
+    /// Size of the signatures.
+    pub signatures: usize,
+    /// Size of the message header, the version prefix of versioned messages
+    /// and the recent blockhash.
+    pub header: usize,
+    /// Size of the static account keys.
+    pub static_account_keys: usize,
+    /// Size of the compiled instructions, including their program id and
+    /// account indexes.
+    pub instructions: usize,
+    /// Size of the address table lookups, zero for legacy messages.
+    pub address_table_lookups: usize,
+}
+
+impl TransactionSize {
+    pub(crate) fn new(num_signatures: usize, message: MessageSize) -> Self {
+        Self {
+            signatures: signatures_size(num_signatures),
+            header: message.header,
+            static_account_keys: message.static_account_keys,
+            instructions: message.instructions,
+            address_table_lookups: message.address_table_lookups,
+        }
+    }
+
+    /// Returns the total serialized size of the transaction.
+    pub fn total(&self) -> usize {
+        self.signatures
+            .saturating_add(self.header)
+            .saturating_add(self.static_account_keys)
+            .saturating_add(self.instructions)
+            .saturating_add(self.address_table_lookups)
+    }
+
+    /// Returns `true` if the transaction fits in a single packet.
+    pub fn fits_in_packet(&self) -> bool {
+        self.total() <= PACKET_DATA_SIZE
+    }
+
+    /// Returns an error describing the size of the transaction if it does not
+    /// fit in a single packet.
+    pub fn check_packet_size(&self) -> Result<(), TransactionTooLargeError> {
+        if self.fits_in_packet() {
+            Ok(())
+        } else {
+            Err(TransactionTooLargeError { size: *self })
+        }
+    }
+}
+
+/// A transaction does not fit in a single packet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionTooLargeError {
+    pub size: TransactionSize,
+}
+
+impl core::error::Error for TransactionTooLargeError {}
+
+impl fmt::Display for TransactionTooLargeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let size = &self.size;
+        write!(
+            f,
+            "transaction of {} bytes exceeds the packet data size of {PACKET_DATA_SIZE} bytes \
+             (signatures: {}, header: {}, static account keys: {}, instructions: {}, address \
+             table lookups: {})",
+            size.total(),
+            size.signatures,
+            size.header,
+            size.static_account_keys,
+            size.instructions,
+            size.address_table_lookups,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        crate::{versioned::VersionedTransaction, Transaction},
+        trezoa_hash::Hash,
+        trezoa_instruction::{AccountMeta, Instruction},
+        trezoa_keypair::Keypair,
+        trezoa_message::{v0, Message, VersionedMessage},
+        trezoa_pubkey::Pubkey,
+        trezoa_signer::Signer,
+    };
+
+    fn instruction(num_accounts: usize, data_len: usize) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &vec![1; data_len],
+            (0..num_accounts)
+                .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_legacy_serialized_size() {
+        let payer = Keypair::new();
+        let message = Message::new(
+            &[instruction(3, 10), instruction(1, 0)],
+            Some(&payer.pubkey()),
+        );
+
+        let unsigned = Transaction::new_unsigned(message.clone());
+        assert_eq!(
+            unsigned.serialized_size(),
+            bincode::serialize(&unsigned).unwrap().len()
+        );
+
+        let signed = Transaction::new(&[&payer], message, Hash::default());
+        let size = signed.size_breakdown();
+        assert_eq!(size.total(), bincode::serialize(&signed).unwrap().len());
+        assert_eq!(size.signatures, 1 + 64);
+        assert_eq!(size.address_table_lookups, 0);
+        assert!(signed.fits_in_packet());
+        assert_eq!(signed.check_packet_size(), Ok(()));
+    }
+
+    #[test]
+    fn test_versioned_serialized_size() {
+        let payer = Keypair::new();
+        let instructions = [instruction(4, 300), instruction(0, 1)];
+        let lookup_table = trezoa_message::AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: instructions[0]
+                .accounts
+                .iter()
+                .map(|meta| meta.pubkey)
+                .collect(),
+        };
+        let message = v0::Message::try_compile(
+            &payer.pubkey(),
+            &instructions,
+            &[lookup_table],
+            Hash::default(),
+        )
+        .unwrap();
+        let transaction =
+            VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+
+        let size = transaction.size_breakdown();
+        assert_eq!(
+            size.total(),
+            bincode::serialize(&transaction).unwrap().len()
+        );
+        assert_eq!(transaction.serialized_size(), size.total());
+        // one lookup of four writable and no readonly accounts
+        assert_eq!(size.address_table_lookups, 1 + 32 + 1 + 4 + 1);
+        assert_eq!(size.header, 1 + 3 + 32);
+
+        let legacy = VersionedTransaction::from(Transaction::new_unsigned(Message::new(
+            &instructions,
+            Some(&payer.pubkey()),
+        )));
+        assert_eq!(
+            legacy.serialized_size(),
+            bincode::serialize(&legacy).unwrap().len()
+        );
+    }
+
+    #[test]
+    fn test_too_large() {
+        let payer = Keypair::new();
+        let instructions: Vec<_> = (0..4).map(|_| instruction(8, 200)).collect();
+        let transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+
+        assert!(!transaction.fits_in_packet());
+        let err = transaction.check_packet_size().unwrap_err();
+        assert_eq!(err.size, transaction.size_breakdown());
+        assert!(err.size.total() > super::PACKET_DATA_SIZE);
+        assert_eq!(err.size.static_account_keys, 1 + 37 * 32);
+    }
+}
@@ -5,7 +5,10 @@ use trezoa_signer::{signers::Signers, SignerError};
 #[cfg(feature = "wincode")]
 use wincode::{containers, len::ShortU16Len, SchemaRead, SchemaWrite};
 use {
-    crate::Transaction,
+    crate::{
+        size::{TransactionSize, TransactionTooLargeError},
+        Transaction,
+    },
     trezoa_message::{inline_nonce::is_advance_nonce_instruction_data, VersionedMessage},
     trezoa_sanitize::SanitizeError,
     trezoa_sdk_ids::system_program,
@@ -163,6 +166,36 @@ impl VersionedTransaction {
         }
     }
 
+    /// Returns the serialized size of the transaction broken down by section,
+    /// without serializing it.
+    ///
+    /// Space is counted for every signature the message requires, even if the
+    /// transaction is not signed yet.
+    pub fn size_breakdown(&self) -> TransactionSize {
+        let num_signatures = self
+            .signatures
+            .len()
+            .max(usize::from(self.message.header().num_required_signatures));
+        TransactionSize::new(num_signatures, self.message.size_breakdown())
+    }
+
+    /// Returns the serialized size of the transaction, without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.size_breakdown().total()
+    }
+
+    /// Returns `true` if the transaction fits in a single packet of
+    /// [`PACKET_DATA_SIZE`](crate::size::PACKET_DATA_SIZE) bytes.
+    pub fn fits_in_packet(&self) -> bool {
+        self.size_breakdown().fits_in_packet()
+    }
+
+    /// Returns an error with the size breakdown of the transaction if it does
+    /// not fit in a single packet.
+    pub fn check_packet_size(&self) -> Result<(), TransactionTooLargeError> {
+        self.size_breakdown().check_packet_size()
+    }
+
     /// Returns a legacy transaction if the transaction message is legacy.
     pub fn into_legacy_transaction(self) -> Option<Transaction> {
         match self.message {
@@ -207,6 +240,48 @@ impl VersionedTransaction {
             .collect()
     }
 
+    #[cfg(feature = "batch-verify")]
+    /// Verify the signatures of many transactions together and return, for
+    /// each transaction, a list of results like
+    /// [`VersionedTransaction::verify_with_results`]
+    ///
+    /// Signatures are checked with cofactored batch verification, which also
+    /// accepts signatures with a small-order component that
+    /// [`VersionedTransaction::verify_with_results`] rejects. See
+    /// [`Signature::verify_batch_cofactored`] for when that is acceptable.
+    pub fn verify_batch_cofactored_with_results(
+        transactions: &[VersionedTransaction],
+    ) -> Vec<Vec<bool>> {
+        let message_bytes: Vec<Vec<u8>> = transactions
+            .iter()
+            .map(|transaction| transaction.message.serialize())
+            .collect();
+        let items: Vec<(&Signature, &[u8], &[u8])> = transactions
+            .iter()
+            .zip(&message_bytes)
+            .flat_map(|(transaction, message_bytes)| {
+                transaction
+                    .signatures
+                    .iter()
+                    .zip(transaction.message.static_account_keys().iter())
+                    .map(move |(signature, pubkey)| {
+                        (signature, pubkey.as_ref(), message_bytes.as_slice())
+                    })
+            })
+            .collect();
+        let mut results = Signature::verify_batch_cofactored(&items).into_iter();
+        transactions
+            .iter()
+            .map(|transaction| {
+                let num_results = transaction
+                    .signatures
+                    .len()
+                    .min(transaction.message.static_account_keys().len());
+                results.by_ref().take(num_results).collect()
+            })
+            .collect()
+    }
+
     /// Returns true if transaction begins with an advance nonce instruction.
     pub fn uses_durable_nonce(&self) -> bool {
         let message = &self.message;
@@ -281,6 +356,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_verify_batch_cofactored_with_results() {
+        let mut transactions: Vec<_> = (0..3)
+            .map(|_| {
+                let keypair = Keypair::new();
+                let message = VersionedMessage::Legacy(LegacyMessage::new(
+                    &[system_instruction::transfer(
+                        &keypair.pubkey(),
+                        &Pubkey::new_unique(),
+                        1,
+                    )],
+                    Some(&keypair.pubkey()),
+                ));
+                VersionedTransaction::try_new(message, &[&keypair]).unwrap()
+            })
+            .collect();
+        transactions[1].signatures[0] = Signature::default();
+        transactions[2].signatures.clear();
+
+        let results = VersionedTransaction::verify_batch_cofactored_with_results(&transactions);
+        assert_eq!(results, vec![vec![true], vec![false], vec![]]);
+        for (transaction, results) in transactions.iter().zip(&results) {
+            assert_eq!(&transaction.verify_with_results(), results);
+        }
+    }
+
     fn nonced_transfer_tx() -> (Pubkey, Pubkey, VersionedTransaction) {
         let from_keypair = Keypair::new();
         let from_pubkey = from_keypair.pubkey();
@@ -0,0 +1,137 @@
+//! Derive macro for `trezoa-zero-copy-account`.
+//!
+//! See the documentation of `trezoa-zero-copy-account` for details.
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+extern crate proc_macro;
+
+use {
+    proc_macro::TokenStream,
+    proc_macro2::{TokenStream as TokenStream2, TokenTree},
+    quote::quote,
+    sha2::{Digest, Sha256},
+    syn::{
+        parse_macro_input, token::Paren, Attribute, Data, DeriveInput, Error, LitInt, LitStr,
+        Result,
+    },
+};
+
+/// The arguments of the `#[zero_copy_account(...)]` attribute.
+#[derive(Default)]
+struct LayoutArgs {
+    name: Option<LitStr>,
+    version: Option<LitInt>,
+}
+
+impl LayoutArgs {
+    fn from_attributes(attrs: &[Attribute]) -> Result<Self> {
+        let mut args = Self::default();
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("zero_copy_account"))
+        {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    args.name = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("version") {
+                    args.version = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error("unknown argument, expected `name` or `version`"));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(args)
+    }
+}
+
+/// Whether the struct has a `#[repr(C)]` or `#[repr(transparent)]` attribute.
+fn has_stable_repr(attrs: &[Attribute]) -> Result<bool> {
+    let mut stable = false;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") || meta.path.is_ident("transparent") {
+                stable = true;
+            } else if meta.path.is_ident("packed") || meta.path.is_ident("align") {
+                // Skip the argument of `packed(N)` or `align(N)`.
+                if meta.input.peek(Paren) {
+                    meta.input.parse::<TokenTree>()?;
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(stable)
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2> {
+    let krate = quote! { ::trezoa_zero_copy_account };
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(&input, "expected a struct"));
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "generic layouts are not supported",
+        ));
+    }
+    if !has_stable_repr(&input.attrs)? {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "expected `#[repr(C)]` or `#[repr(transparent)]`",
+        ));
+    }
+
+    let ident = &input.ident;
+    let LayoutArgs { name, version } = LayoutArgs::from_attributes(&input.attrs)?;
+    let name = name.map_or_else(|| ident.to_string(), |name| name.value());
+    let version = match version {
+        Some(version) => version.base10_parse::<u8>()?,
+        None => 1,
+    };
+    let hash = Sha256::digest(format!("account:{name}:{version}"));
+    let discriminator = &hash[..8];
+
+    // Like `bytemuck_derive`, a struct is plain old data if its fields are and
+    // it has no padding.
+    let field_types: Vec<_> = data.fields.iter().map(|field| &field.ty).collect();
+    let padding_error = format!("`{ident}` must not have padding");
+    Ok(quote! {
+        const _: () = {
+            fn assert_pod<T: #krate::__private::bytemuck::Pod>() {}
+            let _ = || {
+                #(assert_pod::<#field_types>();)*
+            };
+            assert!(
+                ::core::mem::size_of::<#ident>()
+                    == 0 #(+ ::core::mem::size_of::<#field_types>())*,
+                #padding_error,
+            );
+        };
+
+        // SAFETY: The struct has a stable representation, no padding and
+        // only plain old data fields.
+        unsafe impl #krate::__private::bytemuck::Zeroable for #ident {}
+        unsafe impl #krate::__private::bytemuck::Pod for #ident {}
+
+        impl #krate::ZeroCopyAccount for #ident {
+            const DISCRIMINATOR: [u8; #krate::DISCRIMINATOR_LEN] = [#(#discriminator),*];
+            const VERSION: u8 = #version;
+        }
+    })
+}
+
+/// Derive `ZeroCopyAccount`, and `Pod` and `Zeroable` from `bytemuck`, for a
+/// `#[repr(C)]` struct without padding.
+///
+/// The `#[zero_copy_account(name = "...", version = N)]` attribute sets the
+/// name and version the discriminator is derived from.
+///
+/// See the documentation of `trezoa-zero-copy-account` for details.
+#[proc_macro_derive(ZeroCopyAccount, attributes(zero_copy_account))]
+pub fn derive_zero_copy_account(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,192 @@
+//! Typed layouts of account data that are read and written in place.
+//!
+//! Unlike `trezoa-program-pack`, which copies account data in and out of a
+//! value, a [`ZeroCopyAccount`] is a plain old data struct that is cast
+//! directly from the account data, so a large account costs nothing to read.
+//!
+//! The data of the account starts with an 8-byte discriminator, followed by
+//! the struct:
+//!
+//! | Offset | Length | Content |
+//! |---|---|---|
+//! | 0 | 8 | [`ZeroCopyAccount::DISCRIMINATOR`] |
+//! | 8 | `size_of::<T>()` | the struct |
+//!
+//! Data shorter than [`ZeroCopyAccount::SPACE`] fails with
+//! [`AccountDataTooSmall`], and data with another discriminator, or whose
+//! struct is not aligned, fails with [`InvalidAccountData`]. Data after the
+//! struct is ignored.
+//!
+//! The [`ZeroCopyAccount`] derive macro implements the trait, and `Pod` and
+//! `Zeroable` from `bytemuck`, for a `#[repr(C)]` struct without padding.
+//! The discriminator is the first 8 bytes of the SHA-256 hash of
+//! `account:<name>:<version>`, where the name defaults to the name of the
+//! struct and the version to 1.
+//!
+//! ```ignore
+//! use {
+//!     trezoa_account_view::AccountView,
+//!     trezoa_program_error::ProgramResult,
+//!     trezoa_zero_copy_account::ZeroCopyAccount,
+//! };
+//!
+//! #[derive(Clone, Copy, ZeroCopyAccount)]
+//! #[repr(C)]
+//! struct Counter {
+//!     authority: [u8; 32],
+//!     count: u64,
+//! }
+//!
+//! fn process_increment(counter: &AccountView) -> ProgramResult {
+//!     let mut counter = Counter::load_mut(counter)?;
+//!     counter.count = counter.count.saturating_add(1);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Versioned layouts
+//!
+//! Layouts with the same name and different versions have different
+//! discriminators, so a program can tell the versions of an account apart
+//! with [`ZeroCopyAccount::is_layout_of`] and migrate it by resetting its
+//! discriminator and initializing the new layout:
+//!
+//! ```ignore
+//! #[derive(Clone, Copy, ZeroCopyAccount)]
+//! #[zero_copy_account(name = "Counter", version = 2)]
+//! #[repr(C)]
+//! struct CounterV2 {
+//!     authority: [u8; 32],
+//!     count: u64,
+//!     last_update: i64,
+//! }
+//!
+//! fn migrate(account: &AccountView) -> ProgramResult {
+//!     if Counter::is_layout_of(&account.try_borrow()?) {
+//!         let old = *Counter::load(account)?;
+//!         account.resize(CounterV2::SPACE)?;
+//!         account.try_borrow_mut()?[..DISCRIMINATOR_LEN].fill(0);
+//!         let mut new = CounterV2::init_account(account)?;
+//!         new.authority = old.authority;
+//!         new.count = old.count;
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
+//! [`AccountDataTooSmall`]: ProgramError::AccountDataTooSmall
+//! [`InvalidAccountData`]: ProgramError::InvalidAccountData
+#![no_std]
+#![cfg_attr(docsrs, feature(doc_cfg))]
+
+#[cfg(feature = "account-view")]
+use trezoa_account_view::{AccountView, Ref, RefMut};
+#[cfg(feature = "macro")]
+pub use trezoa_zero_copy_account_macro::ZeroCopyAccount;
+use {bytemuck::Pod, core::mem::size_of, trezoa_program_error::ProgramError};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use bytemuck;
+}
+
+/// Length of the discriminator at the start of the account data.
+pub const DISCRIMINATOR_LEN: usize = 8;
+
+/// A layout of account data, which is cast in place from the bytes after
+/// its discriminator.
+pub trait ZeroCopyAccount: Pod {
+    /// The discriminator identifying the layout, which must not be all
+    /// zeros.
+    const DISCRIMINATOR: [u8; DISCRIMINATOR_LEN];
+
+    /// The version of the layout.
+    const VERSION: u8;
+
+    /// The length of the account data holding the layout.
+    const SPACE: usize = DISCRIMINATOR_LEN.saturating_add(size_of::<Self>());
+
+    /// Whether `data` starts with the discriminator of the layout.
+    #[inline]
+    fn is_layout_of(data: &[u8]) -> bool {
+        data.get(..DISCRIMINATOR_LEN) == Some(&Self::DISCRIMINATOR[..])
+    }
+
+    /// Cast the account data to the layout.
+    #[inline]
+    fn from_bytes(data: &[u8]) -> Result<&Self, ProgramError> {
+        check_len::<Self>(data)?;
+        if !Self::is_layout_of(data) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        bytemuck::try_from_bytes(&data[DISCRIMINATOR_LEN..Self::SPACE])
+            .map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Cast the account data to the layout, mutably.
+    #[inline]
+    fn from_bytes_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        check_len::<Self>(data)?;
+        if !Self::is_layout_of(data) {
+            return Err(ProgramError::InvalidAccountData);
+        }
+        bytemuck::try_from_bytes_mut(&mut data[DISCRIMINATOR_LEN..Self::SPACE])
+            .map_err(|_| ProgramError::InvalidAccountData)
+    }
+
+    /// Write the discriminator to uninitialized account data and zero the
+    /// layout.
+    ///
+    /// Returns [`ProgramError::AccountAlreadyInitialized`] if the account
+    /// data already has a discriminator, that is, if its first
+    /// [`DISCRIMINATOR_LEN`] bytes are not all zeros.
+    #[inline]
+    fn init(data: &mut [u8]) -> Result<&mut Self, ProgramError> {
+        check_len::<Self>(data)?;
+        let (discriminator, layout) = data[..Self::SPACE].split_at_mut(DISCRIMINATOR_LEN);
+        if discriminator.iter().any(|byte| *byte != 0) {
+            return Err(ProgramError::AccountAlreadyInitialized);
+        }
+        let layout =
+            bytemuck::try_from_bytes_mut(layout).map_err(|_| ProgramError::InvalidAccountData)?;
+        discriminator.copy_from_slice(&Self::DISCRIMINATOR);
+        *layout = Self::zeroed();
+        Ok(layout)
+    }
+
+    /// Borrow the data of the account as the layout.
+    #[cfg(feature = "account-view")]
+    #[inline]
+    fn load(account: &AccountView) -> Result<Ref<'_, Self>, ProgramError> {
+        Ref::try_map(account.try_borrow()?, Self::from_bytes).map_err(|(_, error)| error)
+    }
+
+    /// Borrow the data of the account mutably as the layout.
+    #[cfg(feature = "account-view")]
+    #[inline]
+    fn load_mut(account: &AccountView) -> Result<RefMut<'_, Self>, ProgramError> {
+        RefMut::try_map(account.try_borrow_mut()?, Self::from_bytes_mut).map_err(|(_, error)| error)
+    }
+
+    /// Initialize the data of the account with [`ZeroCopyAccount::init`] and
+    /// borrow it mutably as the layout.
+    #[cfg(feature = "account-view")]
+    #[inline]
+    fn init_account(account: &AccountView) -> Result<RefMut<'_, Self>, ProgramError> {
+        RefMut::try_map(account.try_borrow_mut()?, Self::init).map_err(|(_, error)| error)
+    }
+}
+
+/// The discriminator of account data, if it is long enough to have one.
+#[inline]
+pub fn discriminator(data: &[u8]) -> Option<&[u8; DISCRIMINATOR_LEN]> {
+    data.first_chunk()
+}
+
+#[inline]
+fn check_len<T: ZeroCopyAccount>(data: &[u8]) -> Result<(), ProgramError> {
+    if data.len() < T::SPACE {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    Ok(())
+}
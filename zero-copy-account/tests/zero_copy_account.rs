@@ -0,0 +1,132 @@
+use {
+    trezoa_account_view::{test_utils::AccountBuffer, RuntimeAccount},
+    trezoa_address::Address,
+    trezoa_program_error::ProgramError,
+    trezoa_zero_copy_account::{discriminator, ZeroCopyAccount, DISCRIMINATOR_LEN},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, ZeroCopyAccount)]
+#[repr(C)]
+struct Counter {
+    authority: [u8; 32],
+    count: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ZeroCopyAccount)]
+#[zero_copy_account(name = "Counter", version = 2)]
+#[repr(C)]
+struct CounterV2 {
+    authority: [u8; 32],
+    count: u64,
+    last_update: i64,
+}
+
+#[test]
+fn test_layout() {
+    assert_eq!(Counter::VERSION, 1);
+    assert_eq!(CounterV2::VERSION, 2);
+    assert_eq!(Counter::SPACE, 48);
+    assert_eq!(CounterV2::SPACE, 56);
+    assert_ne!(Counter::DISCRIMINATOR, CounterV2::DISCRIMINATOR);
+    assert_ne!(Counter::DISCRIMINATOR, [0; DISCRIMINATOR_LEN]);
+}
+
+#[test]
+fn test_from_bytes() {
+    let mut buffer = [0u64; 8];
+    let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut buffer);
+
+    // uninitialized
+    assert_eq!(
+        Counter::from_bytes(bytes).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+
+    let counter = Counter::init(bytes).unwrap();
+    assert_eq!(counter.count, 0);
+    counter.count = 5;
+    assert_eq!(discriminator(bytes), Some(&Counter::DISCRIMINATOR));
+    assert_eq!(Counter::from_bytes(bytes).unwrap().count, 5);
+    assert!(Counter::is_layout_of(bytes));
+    assert!(!CounterV2::is_layout_of(bytes));
+    assert_eq!(
+        CounterV2::from_bytes(bytes).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+    assert_eq!(
+        Counter::init(bytes).err(),
+        Some(ProgramError::AccountAlreadyInitialized)
+    );
+
+    Counter::from_bytes_mut(bytes).unwrap().count = 6;
+    assert_eq!(
+        Counter::from_bytes(&bytes[..Counter::SPACE]).unwrap().count,
+        6
+    );
+    assert_eq!(
+        Counter::from_bytes(&bytes[..Counter::SPACE.saturating_sub(1)]).err(),
+        Some(ProgramError::AccountDataTooSmall)
+    );
+}
+
+#[test]
+fn test_misaligned() {
+    let mut buffer = [0u64; 8];
+    let bytes: &mut [u8] = bytemuck::cast_slice_mut(&mut buffer);
+    let misaligned = &mut bytes[1..];
+    assert_eq!(
+        Counter::init(misaligned).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+    assert_eq!(discriminator(misaligned), Some(&[0; DISCRIMINATOR_LEN]));
+
+    misaligned[..DISCRIMINATOR_LEN].copy_from_slice(&Counter::DISCRIMINATOR);
+    assert_eq!(
+        Counter::from_bytes(misaligned).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+fn test_account_view() {
+    let mut buffer = AccountBuffer::<56>::new(RuntimeAccount {
+        is_writable: 1,
+        address: Address::new_from_array([1; 32]),
+        ..RuntimeAccount::default()
+    });
+    let account = unsafe { buffer.view() };
+
+    assert_eq!(
+        Counter::load(&account).err(),
+        Some(ProgramError::InvalidAccountData)
+    );
+    // the borrow is released on failure
+    assert!(!account.is_borrowed());
+
+    let mut counter = Counter::init_account(&account).unwrap();
+    counter.authority = [2; 32];
+    assert!(Counter::load(&account).is_err());
+    drop(counter);
+
+    Counter::load_mut(&account).unwrap().count = 3;
+    let counter = Counter::load(&account).unwrap();
+    assert_eq!(
+        *counter,
+        Counter {
+            authority: [2; 32],
+            count: 3
+        }
+    );
+    assert!(Counter::load_mut(&account).is_err());
+    drop(counter);
+
+    // migrate to the second version
+    let old = *Counter::load(&account).unwrap();
+    account.try_borrow_mut().unwrap()[..DISCRIMINATOR_LEN].fill(0);
+    let mut counter = CounterV2::init_account(&account).unwrap();
+    counter.authority = old.authority;
+    counter.count = old.count;
+    drop(counter);
+    assert!(Counter::load(&account).is_err());
+    assert_eq!(CounterV2::load(&account).unwrap().count, 3);
+}